@@ -1,17 +1,39 @@
 use crate::error::*;
-use crate::terminal::{ColEncode, Terminal};
+use crate::terminal::{ColEncode, ColourSupport, Terminal, TerminalEvent, Viewport};
 #[cfg(feature = "gamepad")]
 use chargrid_gamepad::GamepadContext;
-use chargrid_runtime::{Component, FrameBuffer, UCoord, app, on_frame, on_input};
+use chargrid_input::{Input, KeyEvent};
+use chargrid_runtime::{
+    Component, FrameBuffer, FrameConfig, FrameScheduler, UCoord, app, on_frame, on_input,
+    on_lifecycle, on_paste, on_resize,
+};
+use std::io::{Read, Write};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
-const FRAME_DURATION: Duration = Duration::from_micros(1_000_000 / 60);
+/// Requests a component can make of the terminal context by yielding them as output. Components
+/// which only ever exit can yield `app::Exit` instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AppRequest {
+    Exit,
+    /// Suspend the process, returning control of the terminal to the shell (see
+    /// `Context::set_suspend_key`)
+    Suspend,
+}
+
+impl From<app::Exit> for AppRequest {
+    fn from(app::Exit: app::Exit) -> Self {
+        Self::Exit
+    }
+}
 
 /// An interface to a terminal for rendering `View`s, and getting input.
 pub struct Context {
     terminal: Terminal,
     chargrid_frame_buffer: FrameBuffer,
+    render_on_event: bool,
+    frame_config: FrameConfig,
+    suspend_key: Option<KeyEvent>,
     #[cfg(feature = "gamepad")]
     gamepad: GamepadContext,
 }
@@ -19,7 +41,39 @@ pub struct Context {
 impl Context {
     /// Initialise a new context using the current terminal.
     pub fn new() -> Result<Self> {
-        Terminal::new().and_then(Self::from_terminal)
+        Terminal::new(Viewport::Fullscreen).and_then(Self::from_terminal)
+    }
+
+    /// Initialise a new context which renders to `height` rows of the current terminal, starting
+    /// at the cursor, rather than taking over the entire terminal. This is intended for widgets
+    /// such as prompts displayed by command-line tools. On exit, the final frame is left in the
+    /// terminal's scrollback, followed by the cursor.
+    pub fn new_inline(height: u32) -> Result<Self> {
+        Terminal::new(Viewport::Inline { height }).and_then(Self::from_terminal)
+    }
+
+    /// Initialise a new context which communicates with a terminal over the given reader and
+    /// writer (e.g. a pty or a network connection) instead of the controlling terminal of the
+    /// process. The reader is read from a background thread, which stops when the reader reaches
+    /// the end of its input or fails, or when it next reads some input after the context has been
+    /// dropped. Since the size of a remote terminal can't be queried directly, `size` is called to
+    /// find out the current size of the terminal. Likewise, `env` is called to look up the
+    /// environment variables of the terminal (such as `TERM`), which may differ from those of the
+    /// process.
+    pub fn from_read_write<R, W, S, E>(
+        reader: R,
+        writer: W,
+        size: S,
+        viewport: Viewport,
+        env: E,
+    ) -> Result<Self>
+    where
+        R: 'static + Read + Send,
+        W: 'static + Write,
+        S: 'static + Fn() -> UCoord,
+        E: Fn(&str) -> Option<String>,
+    {
+        Terminal::from_read_write(reader, writer, size, viewport, env).and_then(Self::from_terminal)
     }
 
     fn from_terminal(mut terminal: Terminal) -> Result<Self> {
@@ -28,55 +82,116 @@ impl Context {
         Ok(Self {
             terminal,
             chargrid_frame_buffer,
+            render_on_event: false,
+            frame_config: FrameConfig::default(),
+            suspend_key: None,
             #[cfg(feature = "gamepad")]
             gamepad: GamepadContext::new(),
         })
     }
 
-    pub fn run<C, E>(self, mut component: C, col_encode: E)
+    /// Run a component until it exits. The component may yield `app::Exit`, or an `AppRequest`
+    /// to also be able to suspend the process.
+    pub fn run<C, O, E>(self, mut component: C, col_encode: E)
     where
-        C: 'static + Component<State = (), Output = app::Output>,
+        C: 'static + Component<State = (), Output = Option<O>>,
+        O: Into<AppRequest>,
         E: ColEncode,
     {
         let _ = col_encode;
         let Self {
             mut terminal,
             mut chargrid_frame_buffer,
+            render_on_event,
+            frame_config,
+            suspend_key,
             #[cfg(feature = "gamepad")]
             mut gamepad,
         } = self;
+        let start = Instant::now();
+        let mut frame_scheduler = FrameScheduler::new(frame_config);
+        let is_suspend_key = |input: &Input| {
+            suspend_key.is_some_and(|suspend_key| {
+                input.key_press().is_some_and(|key_event| {
+                    key_event.key == suspend_key.key && key_event.modifiers == suspend_key.modifiers
+                })
+            })
+        };
+        let mut suspend = false;
         loop {
-            let frame_start = Instant::now();
-            for input in terminal.drain_input().unwrap() {
-                if let Some(app::Exit) = on_input(&mut component, input, &chargrid_frame_buffer) {
-                    return;
+            let frame_start = start.elapsed();
+            for event in terminal.drain_input().unwrap() {
+                let output = match event {
+                    TerminalEvent::Input(input) => {
+                        if is_suspend_key(&input) {
+                            suspend = true;
+                            continue;
+                        }
+                        on_input(&mut component, input, &chargrid_frame_buffer)
+                    }
+                    TerminalEvent::Paste(text) => {
+                        on_paste(&mut component, &text, &chargrid_frame_buffer)
+                    }
+                    TerminalEvent::Lifecycle(lifecycle) => {
+                        on_lifecycle(&mut component, lifecycle, &chargrid_frame_buffer)
+                    }
+                };
+                match output.map(Into::into) {
+                    Some(AppRequest::Exit) => return,
+                    Some(AppRequest::Suspend) => suspend = true,
+                    None => (),
                 }
             }
             #[cfg(feature = "gamepad")]
             for input in gamepad.drain_input() {
-                if let Some(app::Exit) = on_input(
+                match on_input(
                     &mut component,
                     chargrid_input::Input::Gamepad(input),
                     &chargrid_frame_buffer,
-                ) {
-                    return;
+                )
+                .map(Into::into)
+                {
+                    Some(AppRequest::Exit) => return,
+                    Some(AppRequest::Suspend) => suspend = true,
+                    None => (),
                 }
             }
-            let terminal_size = terminal.resize_if_necessary().unwrap();
-            if terminal_size != chargrid_frame_buffer.size() {
-                chargrid_frame_buffer.resize(terminal_size);
+            if suspend {
+                terminal.suspend().unwrap();
+                suspend = false;
             }
-            if let Some(app::Exit) =
-                on_frame(&mut component, FRAME_DURATION, &mut chargrid_frame_buffer)
+            if terminal.size_may_have_changed() {
+                let terminal_size = terminal.resize_if_necessary().unwrap();
+                match on_resize(&mut component, terminal_size, &mut chargrid_frame_buffer)
+                    .map(Into::into)
+                {
+                    Some(AppRequest::Exit) => return,
+                    Some(AppRequest::Suspend) => suspend = true,
+                    None => (),
+                }
+            }
+            let since_last_frame = frame_scheduler.start_frame(frame_start);
+            match on_frame(&mut component, since_last_frame, &mut chargrid_frame_buffer)
+                .map(Into::into)
             {
-                return;
+                Some(AppRequest::Exit) => return,
+                Some(AppRequest::Suspend) => suspend = true,
+                None => (),
             }
             terminal
                 .draw_frame::<E>(&mut chargrid_frame_buffer)
                 .unwrap();
-            let since_frame_start = frame_start.elapsed();
-            if let Some(until_next_frame) = FRAME_DURATION.checked_sub(since_frame_start) {
-                thread::sleep(until_next_frame);
+            if suspend {
+                // suspend at the start of the next iteration without waiting for input
+                continue;
+            }
+            if render_on_event {
+                terminal.wait_for_input(None).unwrap();
+            } else {
+                let until_next_frame = frame_scheduler.until_next_frame(start.elapsed());
+                if !until_next_frame.is_zero() {
+                    thread::sleep(until_next_frame);
+                }
             }
         }
     }
@@ -84,4 +199,53 @@ impl Context {
     pub fn size(&self) -> Result<UCoord> {
         self.terminal.size()
     }
+
+    /// Whether the terminal supports the kitty keyboard protocol. When it does, keys which are
+    /// ambiguous in the legacy encoding (such as escape, alt+key, and ctrl+i) are reported
+    /// unambiguously.
+    pub fn kitty_keyboard(&self) -> bool {
+        self.terminal.kitty_keyboard()
+    }
+
+    /// The colours which the terminal is assumed to support, as used by `col_encode::Auto`. This
+    /// is guessed from the `NO_COLOR`, `COLORTERM` and `TERM` environment variables and the
+    /// terminfo database, and upgraded to `ColourSupport::TrueColour` if the terminal reports
+    /// supporting 24-bit colour when asked (with DECRQSS and XTGETTCAP) during initialisation.
+    pub fn colour_support(&self) -> ColourSupport {
+        self.terminal.colour_support()
+    }
+
+    /// Set the frame rate and the durations reported by `Event::Tick`
+    pub fn set_frame_config(&mut self, frame_config: FrameConfig) {
+        self.frame_config = frame_config;
+    }
+
+    /// Only render a frame in response to input or the terminal being resized, rather than
+    /// rendering frames continuously. While waiting, the process sleeps until the terminal
+    /// becomes readable or SIGWINCH is received, so an idle application uses no CPU. This is
+    /// intended for applications without animations. The frame rate of the frame config is
+    /// ignored in this mode.
+    ///
+    /// Gamepad input (with the "gamepad" feature) doesn't wake the application, and is only
+    /// noticed once some other event arrives.
+    pub fn set_render_on_event(&mut self, render_on_event: bool) {
+        self.render_on_event = render_on_event;
+    }
+
+    /// Suspend the process when the given key is pressed, as ctrl+z would if the terminal wasn't
+    /// in raw mode (typically `KeyEvent::new(KeyboardInput::Char('z'), KeyModifiers::CTRL)`).
+    /// The key isn't passed on to the component. On resuming, the terminal is initialised again
+    /// and the entire frame is redrawn. Components can also suspend the process by yielding
+    /// `AppRequest::Suspend`. Suspending is only possible when running in the current terminal.
+    pub fn set_suspend_key(&mut self, suspend_key: Option<KeyEvent>) {
+        self.suspend_key = suspend_key;
+    }
+
+    /// Report key repeats and releases as `Input::Key` events. This relies on the terminal
+    /// supporting the kitty keyboard protocol. By default (and on terminals without support), key
+    /// repeats are reported as presses and releases aren't reported.
+    pub fn set_key_repeat_and_release(&mut self, key_repeat_and_release: bool) -> Result<()> {
+        self.terminal
+            .set_key_repeat_and_release(key_repeat_and_release)
+    }
 }
@@ -3,8 +3,13 @@ mod error;
 mod terminal;
 
 pub use chargrid_input;
+pub use chargrid_runtime::{FrameConfig, FrameRate, Timestep};
 pub use context::*;
 pub use error::*;
 pub use terminal::ColEncode;
+pub use terminal::ColourSupport;
+pub use terminal::Viewport;
 pub use terminal::col_encode;
 pub use terminal::col_encode::*;
+#[cfg(unix)]
+pub use terminal::restore_terminal;
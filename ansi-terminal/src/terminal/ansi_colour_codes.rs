@@ -1,4 +1,13 @@
-use chargrid_runtime::rgb_int::Rgb24;
+//! Mapping of 24-bit colours onto the palettes of terminals without 24-bit colour support. The
+//! nearest palette entry is chosen by distance in the OKLab colour space
+//! (https://bottosson.github.io/posts/oklab/), in which distances correspond closely to perceived
+//! differences between colours, so that e.g. dark greys with a slight tint don't come out as
+//! saturated colours. The palette colours are assumed to have their xterm default values.
+
+use chargrid_runtime::{ICoord, rgb_int::Rgb24};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::OnceLock;
 
 const RGB_START: u8 = 16;
 const RGB_MAX_FIELD: u8 = 5;
@@ -8,116 +17,391 @@ const RGB_END: u8 = RGB_START + RGB_COUNT - 1;
 const GREY_SCALE_START: u8 = RGB_END + 1;
 const GREY_SCALE_MAX_LEVEL: u8 = 23;
 
-pub fn nearest_palette_code(Rgb24 { r, g, b }: Rgb24) -> u8 {
-    let r = ((r as u16 * RGB_MAX_FIELD as u16) / 255) as u8;
-    let g = ((g as u16 * RGB_MAX_FIELD as u16) / 255) as u8;
-    let b = ((b as u16 * RGB_MAX_FIELD as u16) / 255) as u8;
-    RGB_START + (RGB_FIELD_RANGE * RGB_FIELD_RANGE) * r + RGB_FIELD_RANGE * g + b
-}
-
-pub fn nearest_mean_greyscale_code(Rgb24 { r, g, b }: Rgb24) -> u8 {
-    let offset = (r as u16 + g as u16 + b as u16) / (GREY_SCALE_MAX_LEVEL as u16 * 3);
-    GREY_SCALE_START + offset as u8
-}
-
-pub fn nearest_ansi_code(Rgb24 { r, g, b }: Rgb24) -> u8 {
-    mod codes {
-        pub mod normal {
-            pub const BLACK: u8 = 0;
-            pub const RED: u8 = 1;
-            pub const GREEN: u8 = 2;
-            pub const YELLOW: u8 = 3;
-            pub const BLUE: u8 = 4;
-            pub const MAGENTA: u8 = 5;
-            pub const CYAN: u8 = 6;
-            pub const GREY: u8 = 7;
+/// The value of each channel at each level of the 6x6x6 colour cube
+const RGB_FIELD_LEVELS: [u8; RGB_FIELD_RANGE as usize] = [0, 95, 135, 175, 215, 255];
+
+/// The 16 ansi colours as displayed by xterm by default. Many terminals (and users) choose
+/// different values, but they're usually similar in hue and lightness.
+const ANSI_COLOURS: [Rgb24; 16] = [
+    Rgb24::new(0, 0, 0),
+    Rgb24::new(205, 0, 0),
+    Rgb24::new(0, 205, 0),
+    Rgb24::new(205, 205, 0),
+    Rgb24::new(0, 0, 238),
+    Rgb24::new(205, 0, 205),
+    Rgb24::new(0, 205, 205),
+    Rgb24::new(229, 229, 229),
+    Rgb24::new(127, 127, 127),
+    Rgb24::new(255, 0, 0),
+    Rgb24::new(0, 255, 0),
+    Rgb24::new(255, 255, 0),
+    Rgb24::new(92, 92, 255),
+    Rgb24::new(255, 0, 255),
+    Rgb24::new(0, 255, 255),
+    Rgb24::new(255, 255, 255),
+];
+
+/// Once this many colours have been cached, the cache is cleared rather than growing further
+const CACHE_CAPACITY: usize = 4096;
+
+/// Thresholds of a 4x4 ordered dithering (Bayer) matrix
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Oklab {
+    l: f32,
+    a: f32,
+    b: f32,
+}
+
+impl Oklab {
+    fn distance_squared(self, other: Self) -> f32 {
+        let l = self.l - other.l;
+        let a = self.a - other.a;
+        let b = self.b - other.b;
+        (l * l) + (a * a) + (b * b)
+    }
+}
+
+fn srgb_to_linear_table() -> &'static [f32; 256] {
+    static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        std::array::from_fn(|i| {
+            let c = i as f32 / 255.;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        })
+    })
+}
+
+fn oklab(Rgb24 { r, g, b }: Rgb24) -> Oklab {
+    let table = srgb_to_linear_table();
+    let (r, g, b) = (table[r as usize], table[g as usize], table[b as usize]);
+    let l = (0.41222147 * r + 0.53633254 * g + 0.051445993 * b).cbrt();
+    let m = (0.2119035 * r + 0.6806995 * g + 0.10739696 * b).cbrt();
+    let s = (0.08830246 * r + 0.28171884 * g + 0.6299787 * b).cbrt();
+    Oklab {
+        l: 0.21045426 * l + 0.7936178 * m - 0.004072047 * s,
+        a: 1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+        b: 0.025904037 * l + 0.78277177 * m - 0.80867577 * s,
+    }
+}
+
+fn palette_rgb24(code: u8) -> Rgb24 {
+    if code < RGB_START {
+        ANSI_COLOURS[code as usize]
+    } else if code < GREY_SCALE_START {
+        let index = code - RGB_START;
+        Rgb24::new(
+            RGB_FIELD_LEVELS[(index / (RGB_FIELD_RANGE * RGB_FIELD_RANGE)) as usize],
+            RGB_FIELD_LEVELS[((index / RGB_FIELD_RANGE) % RGB_FIELD_RANGE) as usize],
+            RGB_FIELD_LEVELS[(index % RGB_FIELD_RANGE) as usize],
+        )
+    } else {
+        Rgb24::new_grey(8 + 10 * (code - GREY_SCALE_START))
+    }
+}
+
+/// The OKLab coordinates of each entry of the 256 colour palette
+fn palette_oklab_table() -> &'static [Oklab; 256] {
+    static TABLE: OnceLock<[Oklab; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| std::array::from_fn(|code| oklab(palette_rgb24(code as u8))))
+}
+
+/// The code among `codes` of the palette entry nearest to `target`
+fn nearest_of(target: Oklab, codes: &[u8]) -> u8 {
+    let table = palette_oklab_table();
+    let mut nearest = codes[0];
+    let mut nearest_distance = f32::INFINITY;
+    for &code in codes {
+        let distance = target.distance_squared(table[code as usize]);
+        if distance < nearest_distance {
+            nearest = code;
+            nearest_distance = distance;
         }
-        pub mod bright {
-            pub const DARK_GREY: u8 = 8;
-            pub const RED: u8 = 9;
-            pub const GREEN: u8 = 10;
-            pub const YELLOW: u8 = 11;
-            pub const BLUE: u8 = 12;
-            pub const MAGENTA: u8 = 13;
-            pub const CYAN: u8 = 14;
-            pub const WHITE: u8 = 15;
+    }
+    nearest
+}
+
+/// The codes of the grey ramp entries on either side of `target` in lightness
+fn bracketing_grey_codes(target: Oklab) -> [u8; 2] {
+    let greys = &palette_oklab_table()[GREY_SCALE_START as usize..];
+    let upper = greys
+        .partition_point(|grey| grey.l < target.l)
+        .min(GREY_SCALE_MAX_LEVEL as usize) as u8;
+    [
+        GREY_SCALE_START + upper.saturating_sub(1),
+        GREY_SCALE_START + upper,
+    ]
+}
+
+/// The code of the grey ramp entry closest in lightness to `target`
+fn nearest_grey_code(target: Oklab) -> u8 {
+    let table = palette_oklab_table();
+    let [lower, upper] = bracketing_grey_codes(target);
+    if (target.l - table[lower as usize].l).abs() <= (table[upper as usize].l - target.l).abs() {
+        lower
+    } else {
+        upper
+    }
+}
+
+/// The indices of the levels of the colour cube on either side of `value`
+fn bracketing_levels(value: u8) -> [u8; 2] {
+    let upper = RGB_FIELD_LEVELS
+        .partition_point(|&level| level < value)
+        .min(RGB_MAX_FIELD as usize) as u8;
+    [upper.saturating_sub(1), upper]
+}
+
+/// The codes of the corners of the cell of the 6x6x6 colour cube containing `rgb24`
+fn bracketing_cube_codes(rgb24: Rgb24) -> [u8; 8] {
+    let mut codes = [0; 8];
+    let mut i = 0;
+    for r in bracketing_levels(rgb24.r) {
+        for g in bracketing_levels(rgb24.g) {
+            for b in bracketing_levels(rgb24.b) {
+                codes[i] =
+                    RGB_START + (RGB_FIELD_RANGE * RGB_FIELD_RANGE) * r + RGB_FIELD_RANGE * g + b;
+                i += 1;
+            }
         }
     }
-    const THIRD: u8 = 255 / 3;
-    macro_rules! lo {
-        () => {
-            0..=1
-        };
+    codes
+}
+
+/// The perceptually nearest entry of the 6x6x6 colour cube or the grey ramp of the 256 colour
+/// palette. The first 16 entries are avoided as their actual colours vary between terminals.
+pub fn nearest_palette_code(rgb24: Rgb24) -> u8 {
+    let target = oklab(rgb24);
+    // The nearest colour in the cube is (in practice) one of the corners of the cube cell
+    // containing the target colour, and the nearest grey is the one closest in lightness.
+    let mut candidates = [0; 9];
+    candidates[..8].copy_from_slice(&bracketing_cube_codes(rgb24));
+    candidates[8] = nearest_grey_code(target);
+    nearest_of(target, &candidates)
+}
+
+/// The entry of the grey ramp of the 256 colour palette closest in perceived lightness
+pub fn nearest_greyscale_code(rgb24: Rgb24) -> u8 {
+    nearest_grey_code(oklab(rgb24))
+}
+
+/// The perceptually nearest of the 16 ansi colours
+pub fn nearest_ansi_code(rgb24: Rgb24) -> u8 {
+    const CODES: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+    nearest_of(oklab(rgb24), &CODES)
+}
+
+/// The palettes which colours can be mapped onto, for the purpose of caching
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Palette {
+    Palette256,
+    Greyscale,
+    Ansi16,
+}
+
+impl Palette {
+    fn nearest_code_uncached(self, rgb24: Rgb24) -> u8 {
+        match self {
+            Self::Palette256 => nearest_palette_code(rgb24),
+            Self::Greyscale => nearest_greyscale_code(rgb24),
+            Self::Ansi16 => nearest_ansi_code(rgb24),
+        }
     }
-    macro_rules! hi {
-        () => {
-            2..=3
-        };
+
+    /// Like the corresponding `nearest_*_code` function, but remembers the codes of recently
+    /// seen colours. Frames tend to be drawn with few distinct colours, so this avoids repeating
+    /// the search for the same colours on every frame.
+    pub fn nearest_code(self, rgb24: Rgb24) -> u8 {
+        thread_local! {
+            static CACHE: RefCell<HashMap<(Palette, Rgb24), u8>> = RefCell::new(HashMap::new());
+        }
+        CACHE.with_borrow_mut(|cache| {
+            if let Some(&code) = cache.get(&(self, rgb24)) {
+                return code;
+            }
+            if cache.len() >= CACHE_CAPACITY {
+                cache.clear();
+            }
+            let code = self.nearest_code_uncached(rgb24);
+            cache.insert((self, rgb24), code);
+            code
+        })
     }
-    match (r / THIRD, g / THIRD, b / THIRD) {
-        (0, 0, 0) => codes::normal::BLACK,
-        (1, 0, 0) => codes::normal::RED,
-        (0, 1, 0) => codes::normal::GREEN,
-        (0, 0, 1) => codes::normal::BLUE,
-        (1, 1, 0) => codes::normal::YELLOW,
-        (0, 1, 1) => codes::normal::CYAN,
-        (1, 0, 1) => codes::normal::MAGENTA,
-        (1, 1, 1) => codes::bright::DARK_GREY,
-        col @ (hi!(), hi!(), hi!()) => {
-            if col == (2, 2, 2) {
-                // despite not being "bright", this tends to be brighter than dark grey
-                codes::normal::GREY
-            } else {
-                codes::bright::WHITE
+
+    /// For each channel, the difference between the nearest values on either side of the
+    /// channel of `rgb24` among the palette entries which `rgb24` could be drawn with. This is
+    /// zero for channels whose value those entries already match.
+    fn spacing(self, rgb24: Rgb24) -> [u8; 3] {
+        let mut candidates = [0; 10];
+        let candidates: &[u8] = match self {
+            Self::Palette256 => {
+                candidates[..8].copy_from_slice(&bracketing_cube_codes(rgb24));
+                candidates[8..].copy_from_slice(&bracketing_grey_codes(oklab(rgb24)));
+                &candidates
+            }
+            Self::Greyscale => {
+                candidates[..2].copy_from_slice(&bracketing_grey_codes(oklab(rgb24)));
+                &candidates[..2]
+            }
+            Self::Ansi16 => &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+        };
+        let channels = |Rgb24 { r, g, b }: Rgb24| [r, g, b];
+        let target = channels(rgb24);
+        std::array::from_fn(|i| {
+            let values = candidates
+                .iter()
+                .map(|&code| channels(palette_rgb24(code))[i]);
+            let lower = values.clone().filter(|&value| value <= target[i]).max();
+            let upper = values.filter(|&value| value >= target[i]).min();
+            match (lower, upper) {
+                (Some(lower), Some(upper)) => upper - lower,
+                _ => 0,
             }
+        })
+    }
+
+    /// Apply `ordered_dither` to `rgb24`, with offsets ranging over the distance between the
+    /// palette entries on either side of each channel. Colours which the palette can show exactly
+    /// are left unchanged.
+    pub fn dither(self, rgb24: Rgb24, coord: ICoord) -> Rgb24 {
+        if palette_rgb24(self.nearest_code(rgb24)) == rgb24 {
+            return rgb24;
         }
-        (hi!(), lo!(), lo!()) => codes::bright::RED,
-        (lo!(), hi!(), lo!()) => codes::bright::GREEN,
-        (lo!(), lo!(), hi!()) => codes::bright::BLUE,
-        (hi!(), hi!(), lo!()) => codes::bright::YELLOW,
-        (lo!(), hi!(), hi!()) => codes::bright::CYAN,
-        (hi!(), lo!(), hi!()) => codes::bright::MAGENTA,
-        _ => unreachable!(),
+        ordered_dither(rgb24, coord, self.spacing(rgb24))
     }
 }
 
+/// Offset each channel of `rgb24` by an amount which depends on `coord`, following a 4x4 ordered
+/// dithering pattern, with offsets ranging over the corresponding element of `spacing`. Colours
+/// between two palette entries then map to a mixture of both across neighbouring cells, in
+/// proportion to their distance from each, so gradients appear smooth rather than banded.
+pub fn ordered_dither(Rgb24 { r, g, b }: Rgb24, coord: ICoord, spacing: [u8; 3]) -> Rgb24 {
+    let threshold = BAYER_4X4[coord.y.rem_euclid(4) as usize][coord.x.rem_euclid(4) as usize];
+    let dither = |channel: u8, spacing: u8| {
+        let spacing = spacing as i32;
+        let offset = (((threshold as i32 * 2) + 1) * spacing) / 32 - (spacing / 2);
+        (channel as i32 + offset).clamp(0, 255) as u8
+    };
+    Rgb24::new(
+        dither(r, spacing[0]),
+        dither(g, spacing[1]),
+        dither(b, spacing[2]),
+    )
+}
+
 #[cfg(test)]
 mod tests {
-    use chargrid_runtime::rgb_int::Rgb24;
+    use chargrid_runtime::{ICoord, rgb_int::Rgb24};
+
+    /// A lattice through the colour cube including its corners. Searching for the nearest
+    /// palette entry of every colour is too slow to run with the other tests in debug builds, so
+    /// the searches are tested on every third value of each channel.
+    fn sampled_colours() -> impl Iterator<Item = Rgb24> {
+        (0..=255).step_by(3).flat_map(|r| {
+            (0..=255)
+                .step_by(3)
+                .flat_map(move |g| (0..=255).step_by(3).map(move |b| Rgb24::new(r, g, b)))
+        })
+    }
 
     #[test]
     fn nearest_palette_code_all_cases() {
-        for r in 0..=255 {
-            for g in 0..=255 {
-                for b in 0..=255 {
-                    let c = Rgb24::new(r, g, b);
-                    let _ = super::nearest_palette_code(c);
-                }
-            }
+        for c in sampled_colours() {
+            let _ = super::nearest_palette_code(c);
         }
     }
 
     #[test]
     fn nearest_ansi_code_all_cases() {
-        for r in 0..=255 {
-            for g in 0..=255 {
-                for b in 0..=255 {
-                    let c = Rgb24::new(r, g, b);
-                    let _ = super::nearest_ansi_code(c);
-                }
-            }
+        for c in sampled_colours() {
+            let _ = super::nearest_ansi_code(c);
+        }
+    }
+
+    #[test]
+    fn nearest_greyscale_code_all_cases() {
+        for c in sampled_colours() {
+            let _ = super::nearest_greyscale_code(c);
         }
     }
 
     #[test]
-    fn nearest_mean_greyscale_code_all_cases() {
-        for r in 0..=255 {
-            for g in 0..=255 {
-                for b in 0..=255 {
-                    let c = Rgb24::new(r, g, b);
-                    let _ = super::nearest_mean_greyscale_code(c);
-                }
+    fn palette_entries_map_to_themselves() {
+        for code in super::RGB_START..=u8::MAX {
+            let rgb24 = super::palette_rgb24(code);
+            assert_eq!(
+                super::palette_rgb24(super::nearest_palette_code(rgb24)),
+                rgb24
+            );
+        }
+        for code in 0..16 {
+            assert_eq!(
+                super::nearest_ansi_code(super::ANSI_COLOURS[code]),
+                code as u8
+            );
+        }
+    }
+
+    #[test]
+    fn dark_colours() {
+        // a dark blue-grey as commonly used for backgrounds, which integer division maps to black
+        assert_eq!(super::nearest_palette_code(Rgb24::new(40, 44, 52)), 236);
+        assert_eq!(super::nearest_palette_code(Rgb24::new(0, 0, 0)), 16);
+        assert_eq!(super::nearest_greyscale_code(Rgb24::new_grey(255)), 255);
+        assert_eq!(super::nearest_ansi_code(Rgb24::new(128, 0, 0)), 1);
+        assert_eq!(super::nearest_ansi_code(Rgb24::new_grey(190)), 7);
+    }
+
+    #[test]
+    fn ordered_dither() {
+        let colour = Rgb24::new_grey(100);
+        let dithered = (0..4)
+            .flat_map(|y| (0..4).map(move |x| ICoord::new(x, y)))
+            .map(|coord| super::ordered_dither(colour, coord, [32; 3]).r as i32)
+            .collect::<Vec<_>>();
+        assert_eq!(dithered.iter().min(), Some(&85));
+        assert_eq!(dithered.iter().max(), Some(&115));
+        assert_eq!(dithered.iter().sum::<i32>() / 16, 100);
+        assert_eq!(
+            super::ordered_dither(Rgb24::new_grey(255), ICoord::new(3, 2), [32; 3]),
+            Rgb24::new_grey(255)
+        );
+    }
+
+    #[test]
+    fn palette_dither() {
+        use super::Palette;
+        let coords = || (0..4).flat_map(|y| (0..4).map(move |x| ICoord::new(x, y)));
+        // colours the palette can show exactly aren't dithered
+        for colour in [
+            Rgb24::new(0, 0, 0),
+            Rgb24::new(215, 0, 0),
+            Rgb24::new_grey(8),
+        ] {
+            for coord in coords() {
+                assert_eq!(Palette::Palette256.dither(colour, coord), colour);
             }
         }
+        // a dark grey is dithered between the neighbouring entries of the grey ramp
+        let colour = Rgb24::new_grey(43);
+        let codes = coords()
+            .map(|coord| {
+                Palette::Palette256.nearest_code(Palette::Palette256.dither(colour, coord))
+            })
+            .collect::<Vec<_>>();
+        assert!(codes.iter().all(|&code| code == 235 || code == 236));
+        assert_eq!(codes.iter().filter(|&&code| code == 235).count(), 8);
+        // only the red channel of a colour between two levels of the colour cube is dithered
+        let colour = Rgb24::new(195, 0, 0);
+        for coord in coords() {
+            let dithered = Palette::Palette256.dither(colour, coord);
+            assert!((175..=215).contains(&dithered.r));
+            assert_eq!((dithered.g, dithered.b), (0, 0));
+        }
     }
 }
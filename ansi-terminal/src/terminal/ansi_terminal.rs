@@ -1,46 +1,108 @@
+use super::ansi_colour_codes::Palette;
+use super::bracketed_paste;
 use super::byte_prefix_tree::{BytePrefixTree, Found};
+use super::colour_support::{self, ColourSupport};
+use super::csi_key;
+use super::cursor_position;
+use super::cursor_shape;
+use super::io::TerminalIo;
+use super::kitty_keyboard;
 use super::low_level::LowLevel;
+use super::sgr_mouse;
 use super::term_info_cache::{MousePrefix, TermInfoCache, TerminalInput};
 use crate::error::Result;
-use chargrid_input::{Input, KeyboardInput, MouseInput, NotSupported};
-use chargrid_runtime::{ICoord, UCoord, rgb_int::Rgb24};
+use chargrid_input::{
+    Input, KeyEvent, KeyModifiers, KeyboardInput, MouseInput, NotSupported, keys,
+};
+use chargrid_runtime::{CursorShape, ICoord, Lifecycle, UCoord, UnderlineStyle, rgb_int::Rgb24};
+use std::cmp;
 use std::collections::{VecDeque, vec_deque};
+use std::time::{Duration, Instant};
 use term::terminfo::parm::{self, Param};
 
 const OUTPUT_BUFFER_INITIAL_CAPACITY: usize = 32 * 1024;
 const INPUT_BUFFER_INITIAL_CAPACITY: usize = 32;
 const INPUT_RING_INITIAL_CAPACITY: usize = 32;
 
-pub type DrainInput<'a> = vec_deque::Drain<'a, Input>;
+/// How long to wait for the terminal to answer a query before assuming it never will
+const QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// An event read from the terminal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TerminalEvent {
+    Input(Input),
+    /// Text pasted into the terminal while bracketed paste mode is enabled
+    Paste(String),
+    Lifecycle(Lifecycle),
+}
+
+pub type DrainInput<'a> = vec_deque::Drain<'a, TerminalEvent>;
+
+/// The region of the terminal which is drawn to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Viewport {
+    /// Take over the entire terminal using the alternate screen, restoring the original contents
+    /// of the terminal on exit
+    Fullscreen,
+    /// Draw to the given number of rows starting at the line containing the cursor, scrolling
+    /// the terminal if there isn't enough room below the cursor. The final contents of the
+    /// viewport is left in place on exit, with the cursor on the line below it.
+    Inline { height: u32 },
+}
 
 pub struct AnsiTerminal {
-    low_level: LowLevel,
+    low_level: Box<dyn TerminalIo>,
     output_buffer: String,
     input_buffer: Vec<u8>,
     ti_cache: TermInfoCache,
-    input_ring: VecDeque<Input>,
+    input_ring: VecDeque<TerminalEvent>,
+    kitty_keyboard: bool,
+    key_repeat_and_release: bool,
+    viewport: Viewport,
+    /// The row of the terminal on which the viewport begins
+    origin_row: i32,
+    /// The row of the viewport containing the cursor, as of the last time it was moved
+    cursor_row: i32,
+    /// The size of the whole terminal when the viewport was last found (see `reanchor_if_resized`)
+    anchored_terminal_size: UCoord,
+    cursor_visible: bool,
+    /// The shape and blinking of the cursor, if it has been changed from the terminal's default
+    cursor_shape: Option<(CursorShape, bool)>,
 }
 
 pub mod col_encode {
-    use crate::terminal::ansi_colour_codes::{
-        nearest_ansi_code, nearest_mean_greyscale_code, nearest_palette_code,
-    };
+    use crate::terminal::ansi_colour_codes::Palette;
+    use crate::terminal::colour_support::ColourSupport;
     use crate::terminal::term_info_cache::TermInfoCache;
-    use chargrid_runtime::rgb_int::Rgb24;
+    use chargrid_runtime::{ICoord, rgb_int::Rgb24};
 
     pub trait Trait: Clone {
         fn encode_foreground(buffer: &mut String, rgb24: Rgb24, term_info_cache: &TermInfoCache);
         fn encode_background(buffer: &mut String, rgb24: Rgb24, term_info_cache: &TermInfoCache);
+
+        /// The palette onto which this encoding maps colours, which `Dithered` dithers between.
+        /// `None` for encodings which can produce every colour.
+        fn palette(_term_info_cache: &TermInfoCache) -> Option<Palette> {
+            None
+        }
+
+        /// Adjust the colour of the cell at `coord` before it's encoded
+        fn dither(rgb24: Rgb24, _coord: ICoord, _term_info_cache: &TermInfoCache) -> Rgb24 {
+            rgb24
+        }
     }
 
     #[derive(Clone, Copy)]
     pub struct FromTermInfoRgb;
     impl Trait for FromTermInfoRgb {
         fn encode_foreground(buffer: &mut String, rgb24: Rgb24, term_info_cache: &TermInfoCache) {
-            buffer.push_str(term_info_cache.fg_colour(nearest_palette_code(rgb24)));
+            buffer.push_str(term_info_cache.fg_colour(Palette::Palette256.nearest_code(rgb24)));
         }
         fn encode_background(buffer: &mut String, rgb24: Rgb24, term_info_cache: &TermInfoCache) {
-            buffer.push_str(term_info_cache.bg_colour(nearest_palette_code(rgb24)));
+            buffer.push_str(term_info_cache.bg_colour(Palette::Palette256.nearest_code(rgb24)));
+        }
+        fn palette(_term_info_cache: &TermInfoCache) -> Option<Palette> {
+            Some(Palette::Palette256)
         }
     }
 
@@ -48,10 +110,13 @@ pub mod col_encode {
     pub struct FromTermInfoGreyscale;
     impl Trait for FromTermInfoGreyscale {
         fn encode_foreground(buffer: &mut String, rgb24: Rgb24, term_info_cache: &TermInfoCache) {
-            buffer.push_str(term_info_cache.fg_colour(nearest_mean_greyscale_code(rgb24)));
+            buffer.push_str(term_info_cache.fg_colour(Palette::Greyscale.nearest_code(rgb24)));
         }
         fn encode_background(buffer: &mut String, rgb24: Rgb24, term_info_cache: &TermInfoCache) {
-            buffer.push_str(term_info_cache.bg_colour(nearest_mean_greyscale_code(rgb24)));
+            buffer.push_str(term_info_cache.bg_colour(Palette::Greyscale.nearest_code(rgb24)));
+        }
+        fn palette(_term_info_cache: &TermInfoCache) -> Option<Palette> {
+            Some(Palette::Greyscale)
         }
     }
 
@@ -59,10 +124,13 @@ pub mod col_encode {
     pub struct FromTermInfoAnsi16Colour;
     impl Trait for FromTermInfoAnsi16Colour {
         fn encode_foreground(buffer: &mut String, rgb24: Rgb24, term_info_cache: &TermInfoCache) {
-            buffer.push_str(term_info_cache.fg_colour(nearest_ansi_code(rgb24)));
+            buffer.push_str(term_info_cache.fg_colour(Palette::Ansi16.nearest_code(rgb24)));
         }
         fn encode_background(buffer: &mut String, rgb24: Rgb24, term_info_cache: &TermInfoCache) {
-            buffer.push_str(term_info_cache.bg_colour(nearest_ansi_code(rgb24)));
+            buffer.push_str(term_info_cache.bg_colour(Palette::Ansi16.nearest_code(rgb24)));
+        }
+        fn palette(_term_info_cache: &TermInfoCache) -> Option<Palette> {
+            Some(Palette::Ansi16)
         }
     }
 
@@ -83,6 +151,76 @@ pub mod col_encode {
         }
     }
 
+    /// Chooses an encoding at runtime according to the colours the terminal supports (see
+    /// `Context::colour_support`)
+    #[derive(Clone, Copy)]
+    pub struct Auto;
+    impl Trait for Auto {
+        fn encode_foreground(buffer: &mut String, rgb24: Rgb24, term_info_cache: &TermInfoCache) {
+            match term_info_cache.colour_support {
+                ColourSupport::TrueColour => {
+                    XtermTrueColour::encode_foreground(buffer, rgb24, term_info_cache)
+                }
+                ColourSupport::Palette256 => {
+                    FromTermInfoRgb::encode_foreground(buffer, rgb24, term_info_cache)
+                }
+                ColourSupport::Ansi16 => {
+                    FromTermInfoAnsi16Colour::encode_foreground(buffer, rgb24, term_info_cache)
+                }
+                ColourSupport::NoColour => {
+                    NoColour::encode_foreground(buffer, rgb24, term_info_cache)
+                }
+            }
+        }
+        fn encode_background(buffer: &mut String, rgb24: Rgb24, term_info_cache: &TermInfoCache) {
+            match term_info_cache.colour_support {
+                ColourSupport::TrueColour => {
+                    XtermTrueColour::encode_background(buffer, rgb24, term_info_cache)
+                }
+                ColourSupport::Palette256 => {
+                    FromTermInfoRgb::encode_background(buffer, rgb24, term_info_cache)
+                }
+                ColourSupport::Ansi16 => {
+                    FromTermInfoAnsi16Colour::encode_background(buffer, rgb24, term_info_cache)
+                }
+                ColourSupport::NoColour => {
+                    NoColour::encode_background(buffer, rgb24, term_info_cache)
+                }
+            }
+        }
+        fn palette(term_info_cache: &TermInfoCache) -> Option<Palette> {
+            match term_info_cache.colour_support {
+                ColourSupport::TrueColour | ColourSupport::NoColour => None,
+                ColourSupport::Palette256 => FromTermInfoRgb::palette(term_info_cache),
+                ColourSupport::Ansi16 => FromTermInfoAnsi16Colour::palette(term_info_cache),
+            }
+        }
+    }
+
+    /// Wraps another encoding, applying ordered dithering to colours before they're encoded.
+    /// Regions of a colour which the terminal can't display are then drawn with a pattern of
+    /// the nearest colours it can display, which makes gradients look smoother at the cost of
+    /// some graininess. Has no effect when the wrapped encoding can produce every colour.
+    #[derive(Clone, Copy)]
+    pub struct Dithered<E>(pub E);
+    impl<E: Trait> Trait for Dithered<E> {
+        fn encode_foreground(buffer: &mut String, rgb24: Rgb24, term_info_cache: &TermInfoCache) {
+            E::encode_foreground(buffer, rgb24, term_info_cache);
+        }
+        fn encode_background(buffer: &mut String, rgb24: Rgb24, term_info_cache: &TermInfoCache) {
+            E::encode_background(buffer, rgb24, term_info_cache);
+        }
+        fn palette(term_info_cache: &TermInfoCache) -> Option<Palette> {
+            E::palette(term_info_cache)
+        }
+        fn dither(rgb24: Rgb24, coord: ICoord, term_info_cache: &TermInfoCache) -> Rgb24 {
+            match E::palette(term_info_cache) {
+                Some(palette) => palette.dither(rgb24, coord),
+                None => rgb24,
+            }
+        }
+    }
+
     #[derive(Clone, Copy)]
     pub struct XtermTrueColour;
     impl Trait for XtermTrueColour {
@@ -106,11 +244,17 @@ pub mod col_encode {
 pub use self::col_encode::Trait as ColEncode;
 
 impl AnsiTerminal {
-    pub fn new() -> Result<Self> {
-        let low_level = LowLevel::new()?;
+    pub fn new(viewport: Viewport) -> Result<Self> {
+        Self::from_io(Box::new(LowLevel::new()?), TermInfoCache::new()?, viewport)
+    }
+
+    pub fn from_io(
+        low_level: Box<dyn TerminalIo>,
+        ti_cache: TermInfoCache,
+        viewport: Viewport,
+    ) -> Result<Self> {
         let output_buffer = String::with_capacity(OUTPUT_BUFFER_INITIAL_CAPACITY);
         let input_buffer = Vec::with_capacity(INPUT_BUFFER_INITIAL_CAPACITY);
-        let ti_cache = TermInfoCache::new()?;
         let input_ring = VecDeque::with_capacity(INPUT_RING_INITIAL_CAPACITY);
         let mut terminal = Self {
             low_level,
@@ -118,75 +262,466 @@ impl AnsiTerminal {
             input_buffer,
             ti_cache,
             input_ring,
+            kitty_keyboard: false,
+            key_repeat_and_release: false,
+            viewport,
+            origin_row: 0,
+            cursor_row: 0,
+            anchored_terminal_size: UCoord::new(0, 0),
+            cursor_visible: false,
+            cursor_shape: None,
         };
         terminal.init()?;
         Ok(terminal)
     }
 
     fn init(&mut self) -> Result<()> {
-        if let Some(enter_ca) = self.ti_cache.enter_ca.as_ref() {
-            self.output_buffer.push_str(enter_ca);
+        match self.viewport {
+            Viewport::Fullscreen => {
+                if let Some(enter_ca) = self.ti_cache.enter_ca.as_ref() {
+                    self.output_buffer.push_str(enter_ca);
+                }
+            }
+            Viewport::Inline { height } => self.init_inline(height)?,
         }
         if let Some(enter_xmit) = self.ti_cache.enter_xmit.as_ref() {
             self.output_buffer.push_str(enter_xmit);
         }
         self.output_buffer.push_str(&self.ti_cache.hide_cursor);
-        self.output_buffer.push_str(&self.ti_cache.clear);
+        if self.viewport == Viewport::Fullscreen {
+            self.output_buffer.push_str(&self.ti_cache.clear);
+        }
         self.output_buffer
             .push_str(&self.ti_cache.enable_mouse_reporting);
-        self.flush_buffer()
+        self.output_buffer
+            .push_str(&self.ti_cache.enable_focus_reporting);
+        self.output_buffer.push_str(bracketed_paste::ENABLE);
+        self.output_buffer.push_str(kitty_keyboard::QUERY);
+        self.flush_buffer()?;
+        if self.query_kitty_keyboard()? {
+            self.kitty_keyboard = true;
+            self.output_buffer
+                .push_str(kitty_keyboard::PUSH_DISAMBIGUATE);
+            self.flush_buffer()?;
+        }
+        if self.should_query_colour_support() {
+            self.output_buffer.push_str(colour_support::QUERY);
+            self.flush_buffer()?;
+            if self.query_colour_support()? {
+                self.ti_cache.colour_support = ColourSupport::TrueColour;
+            }
+        }
+        self.update_abnormal_exit_teardown()
     }
 
-    fn teardown(&mut self) -> Result<()> {
-        self.output_buffer
-            .push_str(&self.ti_cache.disable_mouse_reporting);
-        if let Some(exit_ca) = self.ti_cache.exit_ca.as_ref() {
-            self.output_buffer.push_str(exit_ca);
+    /// The terminal is only asked about its colour support if it might support more colours
+    /// than its environment suggests. Terminals without colour (including those whose user has
+    /// asked for no colour with `NO_COLOR`) are left alone.
+    fn should_query_colour_support(&self) -> bool {
+        matches!(
+            self.ti_cache.colour_support,
+            ColourSupport::Palette256 | ColourSupport::Ansi16
+        )
+    }
+
+    /// Make room for an inline viewport below the cursor by printing newlines (which scrolls the
+    /// terminal if the cursor is near the bottom), then find out which row the viewport begins on
+    fn init_inline(&mut self, height: u32) -> Result<()> {
+        let height = cmp::min(height, self.low_level.size()?.height()).max(1);
+        self.output_buffer.push('\r');
+        for _ in 1..height {
+            self.output_buffer.push('\n');
+        }
+        if height > 1 {
+            self.output_buffer
+                .push_str(&format!("\x1B[{}A", height - 1));
+        }
+        self.output_buffer.push_str(cursor_position::QUERY);
+        self.flush_buffer()?;
+        self.origin_row = match self.query_cursor_position()? {
+            Some(position) => position.y,
+            // assume the viewport is at the bottom of the terminal
+            None => (self.low_level.size()?.height() - height) as i32,
+        };
+        self.cursor_row = 0;
+        self.anchored_terminal_size = self.low_level.size()?;
+        Ok(())
+    }
+
+    /// Find an inline viewport again if the terminal changed size since it was last found,
+    /// returning whether it was (in which case its contents must be redrawn). Terminals may
+    /// reflow their contents when their width changes, or scroll them when their height shrinks,
+    /// which moves the viewport. The cursor moves along with the contents, so the viewport is
+    /// found relative to the cursor, then the terminal is scrolled if the viewport no longer fits
+    /// below its origin.
+    pub fn reanchor_if_resized(&mut self) -> Result<bool> {
+        let Viewport::Inline { height } = self.viewport else {
+            return Ok(false);
+        };
+        let terminal_size = self.low_level.size()?;
+        if terminal_size == self.anchored_terminal_size {
+            return Ok(false);
+        }
+        self.anchored_terminal_size = terminal_size;
+        let height = cmp::min(height, terminal_size.height()).max(1) as i32;
+        let terminal_height = terminal_size.height() as i32;
+        self.output_buffer.push_str(cursor_position::QUERY);
+        self.flush_buffer()?;
+        let mut origin_row = match self.query_cursor_position()? {
+            Some(position) => (position.y - self.cursor_row).max(0),
+            // assume the viewport stayed where it was if it still fits in the terminal
+            None => cmp::min(self.origin_row, terminal_height - height).max(0),
+        };
+        let overflow = origin_row + height - terminal_height;
+        if overflow > 0 {
+            self.origin_row = 0;
+            self.set_cursor(ICoord::new(0, terminal_height - 1))?;
+            for _ in 0..overflow {
+                self.output_buffer.push('\n');
+            }
+            self.flush_buffer()?;
+            origin_row -= overflow;
+            self.cursor_row = terminal_height - 1 - origin_row;
+        }
+        self.origin_row = origin_row;
+        self.update_abnormal_exit_teardown()?;
+        Ok(true)
+    }
+
+    /// Read input until `extract` finds the complete response to a query, giving up after
+    /// `QUERY_TIMEOUT`. `extract` is called each time more input has been read, and should remove
+    /// any responses from the input buffer, leaving other input (e.g. keys pressed while waiting
+    /// for the response) in place. Incomplete responses should be left in the buffer so they can
+    /// be recognised once the remainder has been read.
+    fn await_query_response<T>(
+        &mut self,
+        mut extract: impl FnMut(&mut Vec<u8>) -> Option<T>,
+    ) -> Result<Option<T>> {
+        let deadline = Instant::now() + QUERY_TIMEOUT;
+        loop {
+            self.low_level.read_polling(&mut self.input_buffer)?;
+            if let Some(response) = extract(&mut self.input_buffer) {
+                return Ok(Some(response));
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            self.low_level.wait_for_input(Some(deadline - now))?;
+        }
+    }
+
+    /// Wait for the terminal to answer `cursor_position::QUERY`
+    fn query_cursor_position(&mut self) -> Result<Option<ICoord>> {
+        self.await_query_response(cursor_position::extract_response)
+    }
+
+    /// Wait for the terminal to answer `kitty_keyboard::QUERY`, returning whether it supports
+    /// the kitty keyboard protocol
+    fn query_kitty_keyboard(&mut self) -> Result<bool> {
+        let mut supported = false;
+        self.await_query_response(|buffer| {
+            let response = kitty_keyboard::extract_query_responses(buffer);
+            supported |= response.supported;
+            response.complete.then_some(())
+        })?;
+        Ok(supported)
+    }
+
+    /// Wait for the terminal to answer `colour_support::QUERY`, returning whether it supports
+    /// 24-bit colour
+    fn query_colour_support(&mut self) -> Result<bool> {
+        let mut true_colour = false;
+        self.await_query_response(|buffer| {
+            let response = colour_support::extract_query_responses(buffer);
+            true_colour |= response.true_colour;
+            response.complete.then_some(())
+        })?;
+        Ok(true_colour)
+    }
+
+    /// The escape sequences which undo `init`, leaving the terminal in its original state
+    fn teardown_sequence(&mut self) -> Result<String> {
+        let mut sequence = String::new();
+        if self.kitty_keyboard {
+            if self.key_repeat_and_release {
+                sequence.push_str(kitty_keyboard::POP);
+            }
+            sequence.push_str(kitty_keyboard::POP);
+        }
+        sequence.push_str(&self.ti_cache.disable_mouse_reporting);
+        sequence.push_str(&self.ti_cache.disable_focus_reporting);
+        sequence.push_str(bracketed_paste::DISABLE);
+        match self.viewport {
+            Viewport::Fullscreen => {
+                if let Some(exit_ca) = self.ti_cache.exit_ca.as_ref() {
+                    sequence.push_str(exit_ca);
+                }
+            }
+            Viewport::Inline { .. } => {
+                // leave the cursor at the start of the line following the viewport
+                let size = self.size()?;
+                sequence.push_str(&self.ti_cache.reset);
+                sequence
+                    .push_str(&self.set_cursor_command(ICoord::new(0, size.height() as i32 - 1))?);
+                sequence.push_str("\r\n");
+            }
         }
         if let Some(exit_xmit) = self.ti_cache.exit_xmit.as_ref() {
-            self.output_buffer.push_str(exit_xmit);
+            sequence.push_str(exit_xmit);
         }
-        self.output_buffer.push_str(&self.ti_cache.show_cursor);
-        self.output_buffer.push_str(&self.ti_cache.reset);
+        if self.cursor_shape.is_some() {
+            sequence.push_str(cursor_shape::RESET);
+        }
+        sequence.push_str(&self.ti_cache.show_cursor);
+        sequence.push_str(&self.ti_cache.reset);
+        Ok(sequence)
+    }
+
+    /// Keep the sequences sent to the terminal if the process panics or is killed consistent
+    /// with the current state of the terminal. Call this after any change which affects
+    /// `teardown_sequence`.
+    fn update_abnormal_exit_teardown(&mut self) -> Result<()> {
+        let sequence = self.teardown_sequence()?;
+        self.low_level.set_abnormal_exit_teardown(&sequence);
+        Ok(())
+    }
+
+    fn teardown(&mut self) -> Result<()> {
+        let sequence = self.teardown_sequence()?;
+        self.output_buffer.push_str(&sequence);
         self.flush_buffer()
     }
 
+    pub fn viewport(&self) -> Viewport {
+        self.viewport
+    }
+
     pub fn size(&self) -> Result<UCoord> {
-        self.low_level.size()
+        let size = self.low_level.size()?;
+        Ok(match self.viewport {
+            Viewport::Fullscreen => size,
+            Viewport::Inline { height } => {
+                let available_height = (size.height() as i32 - self.origin_row).max(1) as u32;
+                UCoord::new(size.width(), cmp::min(height, available_height))
+            }
+        })
+    }
+
+    pub fn size_may_have_changed(&mut self) -> bool {
+        let size_may_have_changed = self.low_level.size_may_have_changed();
+        if size_may_have_changed && matches!(self.viewport, Viewport::Inline { .. }) {
+            // the position of the cursor after tearing down an inline viewport depends on the
+            // size of the terminal
+            let _ = self.update_abnormal_exit_teardown();
+        }
+        size_may_have_changed
+    }
+
+    /// Block until there is input to read, the terminal may have been resized, or the timeout
+    /// (if any) elapses
+    pub fn wait_for_input(&mut self, timeout: Option<Duration>) -> Result<()> {
+        self.low_level.wait_for_input(timeout)
+    }
+
+    /// Whether the terminal supports the kitty keyboard protocol
+    pub fn kitty_keyboard(&self) -> bool {
+        self.kitty_keyboard
+    }
+
+    /// The colours which `col_encode::Auto` assumes the terminal can display
+    pub fn colour_support(&self) -> ColourSupport {
+        self.ti_cache.colour_support
+    }
+
+    /// Ask the terminal to report key repeats and releases using the kitty keyboard protocol.
+    /// This has no effect on terminals which don't support the protocol.
+    pub fn set_key_repeat_and_release(&mut self, key_repeat_and_release: bool) -> Result<()> {
+        if self.kitty_keyboard && key_repeat_and_release != self.key_repeat_and_release {
+            self.output_buffer.push_str(if key_repeat_and_release {
+                kitty_keyboard::PUSH_REPEAT_AND_RELEASE
+            } else {
+                kitty_keyboard::POP
+            });
+            self.key_repeat_and_release = key_repeat_and_release;
+            self.flush_buffer()?;
+            self.update_abnormal_exit_teardown()?;
+        }
+        Ok(())
+    }
+
+    /// Return the terminal to its original state and stop the process as if the user had pressed
+    /// ctrl+z (which is otherwise delivered as input while the terminal is in raw mode), then
+    /// initialise the terminal again when the process is continued (e.g. with `fg`). Returns
+    /// false without doing anything if the terminal doesn't support suspending the process.
+    pub fn suspend(&mut self) -> Result<bool> {
+        if !self.low_level.can_suspend_process() {
+            return Ok(false);
+        }
+        self.teardown()?;
+        self.low_level.suspend_process()?;
+        self.reinit()?;
+        Ok(true)
+    }
+
+    /// Initialise the terminal again if it was restored to its original state by a panic which
+    /// was caught. Returns true if it was.
+    pub fn resume_after_caught_panic(&mut self) -> Result<bool> {
+        if !self.low_level.take_restored_after_panic()? {
+            return Ok(false);
+        }
+        self.reinit()?;
+        Ok(true)
+    }
+
+    /// Initialise a terminal which was returned to its original state, restoring any settings
+    /// changed since it was first initialised
+    fn reinit(&mut self) -> Result<()> {
+        let key_repeat_and_release = self.key_repeat_and_release;
+        self.kitty_keyboard = false;
+        self.key_repeat_and_release = false;
+        self.cursor_visible = false;
+        self.cursor_shape = None;
+        self.init()?;
+        self.set_key_repeat_and_release(key_repeat_and_release)
     }
 
+    /// Move the cursor to a position relative to the top-left corner of the viewport
     pub fn set_cursor(&mut self, coord: ICoord) -> Result<()> {
-        let params = &[Param::Number(coord.y), Param::Number(coord.x)];
+        let command = self.set_cursor_command(coord)?;
+        self.output_buffer.push_str(&command);
+        self.cursor_row = coord.y;
+        Ok(())
+    }
+
+    fn set_cursor_command(&mut self, coord: ICoord) -> Result<String> {
+        let params = &[
+            Param::Number(coord.y + self.origin_row),
+            Param::Number(coord.x),
+        ];
         let command = parm::expand(
             self.ti_cache.set_cursor.as_bytes(),
             params,
             &mut self.ti_cache.vars,
         )?;
-        let command_slice = ::std::str::from_utf8(&command)?;
-        self.output_buffer.push_str(command_slice);
+        Ok(String::from_utf8(command).map_err(|e| e.utf8_error())?)
+    }
+
+    pub fn show_cursor(&mut self) {
+        if !self.cursor_visible {
+            self.output_buffer.push_str(&self.ti_cache.show_cursor);
+            self.cursor_visible = true;
+        }
+    }
+
+    pub fn hide_cursor(&mut self) {
+        if self.cursor_visible {
+            self.output_buffer.push_str(&self.ti_cache.hide_cursor);
+            self.cursor_visible = false;
+        }
+    }
+
+    pub fn set_cursor_shape(&mut self, shape: CursorShape, blink: bool) -> Result<()> {
+        if self.cursor_shape != Some((shape, blink)) {
+            self.output_buffer.push_str(cursor_shape::set(shape, blink));
+            let first_change = self.cursor_shape.is_none();
+            self.cursor_shape = Some((shape, blink));
+            if first_change {
+                // the default shape must now be restored on teardown
+                self.update_abnormal_exit_teardown()?;
+            }
+        }
         Ok(())
     }
 
-    pub fn set_foreground_colour<E>(&mut self, rgb24: Rgb24)
+    /// Append the escape sequence which sets the foreground colour to `buffer`, rather than to
+    /// the output buffer, so it can be compared with the previous one before being sent
+    pub fn encode_foreground_colour<E>(&self, rgb24: Rgb24, buffer: &mut String)
+    where
+        E: ColEncode,
+    {
+        E::encode_foreground(buffer, rgb24, &self.ti_cache);
+    }
+
+    /// Append the escape sequence which sets the background colour to `buffer`, rather than to
+    /// the output buffer, so it can be compared with the previous one before being sent
+    pub fn encode_background_colour<E>(&self, rgb24: Rgb24, buffer: &mut String)
     where
         E: ColEncode,
     {
-        E::encode_foreground(&mut self.output_buffer, rgb24, &self.ti_cache);
+        E::encode_background(buffer, rgb24, &self.ti_cache);
     }
 
-    pub fn set_background_colour<E>(&mut self, rgb24: Rgb24)
+    /// The colour which should be encoded for a cell at `coord` of the given colour
+    pub fn dither<E>(&self, rgb24: Rgb24, coord: ICoord) -> Rgb24
     where
         E: ColEncode,
     {
-        E::encode_background(&mut self.output_buffer, rgb24, &self.ti_cache);
+        E::dither(rgb24, coord, &self.ti_cache)
     }
 
     pub fn set_bold(&mut self) {
         self.output_buffer.push_str(&self.ti_cache.bold);
     }
 
-    pub fn set_underline(&mut self) {
+    pub fn set_dim(&mut self) {
+        if let Some(dim) = self.ti_cache.dim.as_ref() {
+            self.output_buffer.push_str(dim);
+        }
+    }
+
+    pub fn set_italic(&mut self) {
+        if let Some(italic) = self.ti_cache.italic.as_ref() {
+            self.output_buffer.push_str(italic);
+        }
+    }
+
+    /// Underline with the given style. Terminals which don't support styled underlines draw a
+    /// regular underline.
+    pub fn set_underline(&mut self, underline_style: UnderlineStyle) {
+        // the plain underline sequence comes first so that terminals which don't support the
+        // others still underline
         self.output_buffer.push_str(&self.ti_cache.underline);
+        let styled = match underline_style {
+            UnderlineStyle::Single => return,
+            UnderlineStyle::Double => &self.ti_cache.double_underline,
+            UnderlineStyle::Curly => &self.ti_cache.curly_underline,
+            UnderlineStyle::Dotted => &self.ti_cache.dotted_underline,
+            UnderlineStyle::Dashed => &self.ti_cache.dashed_underline,
+        };
+        self.output_buffer.push_str(styled);
+    }
+
+    /// Set the colour of underlines, if the terminal supports coloured underlines and enough
+    /// colours for it to make sense
+    pub fn set_underline_colour(&mut self, Rgb24 { r, g, b }: Rgb24) {
+        match self.ti_cache.colour_support {
+            ColourSupport::TrueColour => {
+                self.output_buffer
+                    .push_str(&format!("\x1B[58:2::{}:{}:{}m", r, g, b));
+            }
+            ColourSupport::Palette256 => {
+                let code = Palette::Palette256.nearest_code(Rgb24 { r, g, b });
+                self.output_buffer.push_str(&format!("\x1B[58:5:{}m", code));
+            }
+            ColourSupport::Ansi16 | ColourSupport::NoColour => (),
+        }
+    }
+
+    pub fn set_blink(&mut self) {
+        if let Some(blink) = self.ti_cache.blink.as_ref() {
+            self.output_buffer.push_str(blink);
+        }
+    }
+
+    pub fn set_reverse(&mut self) {
+        if let Some(reverse) = self.ti_cache.reverse.as_ref() {
+            self.output_buffer.push_str(reverse);
+        }
+    }
+
+    pub fn set_strikethrough(&mut self) {
+        self.output_buffer.push_str(&self.ti_cache.strikethrough);
     }
 
     pub fn clear_underline(&mut self) {
@@ -204,12 +739,23 @@ impl AnsiTerminal {
     }
 
     fn drain_input_into_ring(&mut self) -> Result<()> {
-        Self::populate_input_ring(
+        let num_unconsumed = Self::populate_input_ring(
             &mut self.input_ring,
             &self.ti_cache.escape_sequence_prefix_tree,
             &self.input_buffer,
         )?;
-        self.input_buffer.clear();
+        // keep the start of any paste whose end hasn't been read yet
+        let num_consumed = self.input_buffer.len() - num_unconsumed;
+        self.input_buffer.drain(..num_consumed);
+        if self.origin_row != 0 {
+            // the terminal reports mouse coordinates relative to the top-left corner of the screen
+            let origin = ICoord::new(0, self.origin_row);
+            for event in self.input_ring.iter_mut() {
+                if let TerminalEvent::Input(Input::Mouse(mouse_input)) = event {
+                    *mouse_input = mouse_input.relative_to_coord(origin);
+                }
+            }
+        }
         Ok(())
     }
 
@@ -222,32 +768,82 @@ impl AnsiTerminal {
         }
     }
 
+    /// Interpret a character read from the terminal. Terminals send ctrl+letter as an ascii
+    /// control character. ETX (ctrl+c) is left alone, as it's used to indicate that the
+    /// application should close, as are the control characters with dedicated keys (tab and
+    /// return).
+    fn char_key_event(ch: char) -> KeyEvent {
+        match ch {
+            '\u{1}'..='\u{1a}' if ch != '\u{3}' && ch != '\t' && ch != '\r' => {
+                let letter = (b'a' + (ch as u8 - 1)) as char;
+                KeyEvent::new(KeyboardInput::Char(letter), KeyModifiers::CTRL)
+            }
+            _ => KeyEvent::unmodified(KeyboardInput::Char(ch)),
+        }
+    }
+
+    /// Parse the inputs described by `slice` into `input_ring`. Returns the number of bytes at the
+    /// end of `slice` which weren't consumed because they are part of an incomplete paste.
     fn populate_input_ring(
-        input_ring: &mut VecDeque<Input>,
+        input_ring: &mut VecDeque<TerminalEvent>,
         prefix_tree: &BytePrefixTree<TerminalInput>,
         slice: &[u8],
-    ) -> Result<()> {
+    ) -> Result<usize> {
+        match bracketed_paste::parse(slice) {
+            bracketed_paste::Parsed::Complete(text, rest) => {
+                input_ring.push_back(TerminalEvent::Paste(text));
+                return Self::populate_input_ring(input_ring, prefix_tree, rest);
+            }
+            bracketed_paste::Parsed::Incomplete => return Ok(slice.len()),
+            bracketed_paste::Parsed::NotPaste => (),
+        }
         let (term_input, rest) = match prefix_tree.get_longest(slice) {
-            None => {
-                // slice does not begin with an escape sequence - chip off the start and try again
-                let s = if let Ok(s) = ::std::str::from_utf8(slice) {
-                    s
-                } else {
-                    return Ok(());
-                };
-                if let Some((ch, rest)) = Self::chip_char(s) {
-                    (Some(TerminalInput::Char(ch)), Some(rest.as_bytes()))
-                } else {
-                    (None, None)
+            None => match csi_key::parse(slice).or_else(|| sgr_mouse::parse(slice)) {
+                Some((input, rest)) => (input.map(TerminalInput::Literal), Some(rest)),
+                None => {
+                    // slice does not begin with an escape sequence - chip off the start and try again
+                    let s = if let Ok(s) = ::std::str::from_utf8(slice) {
+                        s
+                    } else {
+                        return Ok(0);
+                    };
+                    match Self::chip_char(s) {
+                        // terminals send alt+key as an escape character followed by the key
+                        Some((ch, rest)) if KeyboardInput::Char(ch) == keys::ESCAPE => {
+                            match Self::chip_char(rest) {
+                                Some((next, rest)) if KeyboardInput::Char(next) != keys::ESCAPE => {
+                                    let KeyEvent { key, modifiers, .. } =
+                                        Self::char_key_event(next);
+                                    let key_event =
+                                        KeyEvent::new(key, modifiers | KeyModifiers::ALT);
+                                    (
+                                        Some(TerminalInput::Literal(key_event.input())),
+                                        Some(rest.as_bytes()),
+                                    )
+                                }
+                                _ => (
+                                    Some(TerminalInput::Literal(Input::Keyboard(keys::ESCAPE))),
+                                    Some(rest.as_bytes()),
+                                ),
+                            }
+                        }
+                        Some((ch, rest)) => (
+                            Some(TerminalInput::Literal(Self::char_key_event(ch).input())),
+                            Some(rest.as_bytes()),
+                        ),
+                        None => (None, None),
+                    }
                 }
-            }
+            },
             Some(Found::Exact(input)) => (Some(*input), None),
             Some(Found::WithRemaining(input, remaining)) => (Some(*input), Some(remaining)),
         };
         let rest = if let Some(input) = term_input {
-            let (input, rest) = match input {
-                TerminalInput::Char(ch) => (Some(Input::Keyboard(KeyboardInput::Char(ch))), rest),
-                TerminalInput::Literal(input) => (Some(input), rest),
+            let (event, rest) = match input {
+                TerminalInput::Literal(input) => (Some(TerminalEvent::Input(input)), rest),
+                TerminalInput::Lifecycle(lifecycle) => {
+                    (Some(TerminalEvent::Lifecycle(lifecycle)), rest)
+                }
                 TerminalInput::MousePrefix(prefix) => {
                     if let Some(rest) = rest {
                         if rest.len() >= 2 {
@@ -270,7 +866,7 @@ impl AnsiTerminal {
                                     Input::Mouse(MouseInput::MouseScroll { direction, coord })
                                 }
                             };
-                            (Some(input), Some(&rest[2..]))
+                            (Some(TerminalEvent::Input(input)), Some(&rest[2..]))
                         } else {
                             (None, Some(rest))
                         }
@@ -279,8 +875,8 @@ impl AnsiTerminal {
                     }
                 }
             };
-            if let Some(input) = input {
-                input_ring.push_back(input);
+            if let Some(event) = event {
+                input_ring.push_back(event);
             }
             rest
         } else {
@@ -288,14 +884,14 @@ impl AnsiTerminal {
         };
 
         if let Some(rest) = rest {
-            Self::populate_input_ring(input_ring, prefix_tree, rest)?;
+            return Self::populate_input_ring(input_ring, prefix_tree, rest);
         }
 
-        Ok(())
+        Ok(0)
     }
 
-    pub fn add_char_to_buffer(&mut self, ch: char) {
-        self.output_buffer.push(ch);
+    pub fn add_str_to_buffer(&mut self, s: &str) {
+        self.output_buffer.push_str(s);
     }
 
     pub fn flush_buffer(&mut self) -> Result<()> {
@@ -311,3 +907,145 @@ impl Drop for AnsiTerminal {
             .expect("Failed to reset terminal to original settings");
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::terminal::term_info_cache::modified_key_sequences;
+    use chargrid_input::KeyEventKind;
+
+    fn parse(bytes: &[u8]) -> Vec<Input> {
+        let mut prefix_tree = BytePrefixTree::new();
+        for (seq, input) in modified_key_sequences() {
+            prefix_tree.insert(&seq, input);
+        }
+        let mut input_ring = VecDeque::new();
+        AnsiTerminal::populate_input_ring(&mut input_ring, &prefix_tree, bytes).unwrap();
+        input_ring
+            .into_iter()
+            .map(|event| match event {
+                TerminalEvent::Input(input) => input,
+                other => panic!("unexpected event: {:?}", other),
+            })
+            .collect()
+    }
+
+    fn key(key: KeyboardInput, modifiers: KeyModifiers) -> Input {
+        KeyEvent::new(key, modifiers).input()
+    }
+
+    #[test]
+    fn modified_keys() {
+        assert_eq!(
+            parse(b"\x1B[1;5A\x1B[1;2D\x1B[6;3~\x1B[15;7~\x1B[Z"),
+            vec![
+                key(KeyboardInput::Up, KeyModifiers::CTRL),
+                key(KeyboardInput::Left, KeyModifiers::SHIFT),
+                key(KeyboardInput::PageDown, KeyModifiers::ALT),
+                key(
+                    KeyboardInput::Function(5),
+                    KeyModifiers::CTRL | KeyModifiers::ALT
+                ),
+                key(keys::TAB, KeyModifiers::SHIFT),
+            ]
+        );
+    }
+
+    #[test]
+    fn key_event_types() {
+        assert_eq!(
+            parse(b"\x1B[1;5Aa\x1B[97;1:3u\x1B[Z"),
+            vec![
+                key(KeyboardInput::Up, KeyModifiers::CTRL),
+                Input::Keyboard(KeyboardInput::Char('a')),
+                KeyEvent::unmodified(KeyboardInput::Char('a'))
+                    .with_kind(KeyEventKind::Release)
+                    .input(),
+                key(keys::TAB, KeyModifiers::SHIFT),
+            ]
+        );
+    }
+
+    #[test]
+    fn disambiguated_keys() {
+        assert_eq!(
+            parse(b"\x1B[27u\x1B[105;5u\t\x1B[97;3u"),
+            vec![
+                Input::Keyboard(keys::ESCAPE),
+                key(KeyboardInput::Char('i'), KeyModifiers::CTRL),
+                Input::Keyboard(keys::TAB),
+                key(KeyboardInput::Char('a'), KeyModifiers::ALT),
+            ]
+        );
+    }
+
+    #[test]
+    fn paste() {
+        let mut input_ring = VecDeque::new();
+        AnsiTerminal::populate_input_ring(
+            &mut input_ring,
+            &BytePrefixTree::new(),
+            b"a\x1B[200~b\rc\x1B[201~d",
+        )
+        .unwrap();
+        assert_eq!(
+            input_ring.into_iter().collect::<Vec<_>>(),
+            vec![
+                TerminalEvent::Input(Input::Keyboard(KeyboardInput::Char('a'))),
+                TerminalEvent::Paste("b\nc".to_string()),
+                TerminalEvent::Input(Input::Keyboard(KeyboardInput::Char('d'))),
+            ]
+        );
+        let mut input_ring = VecDeque::new();
+        let num_unconsumed = AnsiTerminal::populate_input_ring(
+            &mut input_ring,
+            &BytePrefixTree::new(),
+            b"a\x1B[200~bc",
+        )
+        .unwrap();
+        assert_eq!(num_unconsumed, 8);
+        assert_eq!(
+            input_ring.into_iter().collect::<Vec<_>>(),
+            vec![TerminalEvent::Input(Input::Keyboard(KeyboardInput::Char(
+                'a'
+            )))]
+        );
+    }
+
+    #[test]
+    fn focus() {
+        let mut prefix_tree = BytePrefixTree::new();
+        prefix_tree.insert(b"\x1B[I", TerminalInput::Lifecycle(Lifecycle::FocusGained));
+        prefix_tree.insert(b"\x1B[O", TerminalInput::Lifecycle(Lifecycle::FocusLost));
+        let mut input_ring = VecDeque::new();
+        AnsiTerminal::populate_input_ring(&mut input_ring, &prefix_tree, b"\x1B[Oa\x1B[I").unwrap();
+        assert_eq!(
+            input_ring.into_iter().collect::<Vec<_>>(),
+            vec![
+                TerminalEvent::Lifecycle(Lifecycle::FocusLost),
+                TerminalEvent::Input(Input::Keyboard(KeyboardInput::Char('a'))),
+                TerminalEvent::Lifecycle(Lifecycle::FocusGained),
+            ]
+        );
+    }
+
+    #[test]
+    fn control_and_alt_chars() {
+        assert_eq!(
+            parse(b"\x13\x03a\rx\x1Bs\x1B\x13\x1B"),
+            vec![
+                key(KeyboardInput::Char('s'), KeyModifiers::CTRL),
+                Input::Keyboard(keys::ETX),
+                Input::Keyboard(KeyboardInput::Char('a')),
+                Input::Keyboard(keys::RETURN),
+                Input::Keyboard(KeyboardInput::Char('x')),
+                key(KeyboardInput::Char('s'), KeyModifiers::ALT),
+                key(
+                    KeyboardInput::Char('s'),
+                    KeyModifiers::CTRL | KeyModifiers::ALT
+                ),
+                Input::Keyboard(keys::ESCAPE),
+            ]
+        );
+    }
+}
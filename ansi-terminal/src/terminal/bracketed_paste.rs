@@ -0,0 +1,59 @@
+//! Parser for text pasted while bracketed paste mode is enabled. The terminal surrounds pasted
+//! text with "ESC [ 200 ~" and "ESC [ 201 ~" rather than sending it as though it was typed, so
+//! a paste can be delivered as a single input, and newlines within it can't be mistaken for the
+//! return key.
+
+pub const ENABLE: &str = "\x1B[?2004h";
+pub const DISABLE: &str = "\x1B[?2004l";
+
+const START: &[u8] = b"\x1B[200~";
+const END: &[u8] = b"\x1B[201~";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Parsed<'a> {
+    /// The bytes don't begin with pasted text
+    NotPaste,
+    /// The bytes begin with the start of pasted text, but the end hasn't been read yet
+    Incomplete,
+    /// The pasted text, along with the bytes following it
+    Complete(String, &'a [u8]),
+}
+
+/// Terminals send line breaks in pasted text as carriage returns
+fn normalize_line_breaks(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+pub fn parse(bytes: &[u8]) -> Parsed<'_> {
+    let Some(after_start) = bytes.strip_prefix(START) else {
+        return Parsed::NotPaste;
+    };
+    match after_start
+        .windows(END.len())
+        .position(|window| window == END)
+    {
+        Some(text_len) => {
+            let text = String::from_utf8_lossy(&after_start[..text_len]);
+            Parsed::Complete(
+                normalize_line_breaks(&text),
+                &after_start[(text_len + END.len())..],
+            )
+        }
+        None => Parsed::Incomplete,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn paste() {
+        assert_eq!(
+            parse(b"\x1B[200~hello\r\nworld\rfoo\x1B[201~rest"),
+            Parsed::Complete("hello\nworld\nfoo".to_string(), &b"rest"[..])
+        );
+        assert_eq!(parse(b"\x1B[200~hello\x1B[20"), Parsed::Incomplete);
+        assert_eq!(parse(b"hello"), Parsed::NotPaste);
+    }
+}
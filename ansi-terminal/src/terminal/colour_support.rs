@@ -0,0 +1,186 @@
+//! Detection of the colours supported by the terminal, for use by `col_encode::Auto`. The
+//! environment (`NO_COLOR`, `COLORTERM` and `TERM`) and the terminfo database give a first
+//! guess. Since many terminals support 24-bit colour without advertising it (particularly over
+//! ssh, where `COLORTERM` isn't forwarded), the terminal can also be asked directly:
+//! - DECRQSS ("ESC P $ q m ESC \") reports the current SGR attributes, so setting a 24-bit
+//!   background colour beforehand and checking whether it's reported back reveals whether the
+//!   terminal understood it
+//! - XTGETTCAP ("ESC P + q <hex name> ESC \") asks for the terminal's own terminfo capabilities,
+//!   and the "RGB" capability indicates 24-bit colour support
+//!
+//! These are followed by a request for the primary device attributes which every terminal
+//! answers, so that terminals which ignore the other requests needn't be waited on.
+
+/// The colours a terminal can display, from most to fewest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColourSupport {
+    /// Arbitrary 24-bit colours
+    TrueColour,
+    /// The 256 colour palette of xterm
+    Palette256,
+    /// The 16 standard ansi colours
+    Ansi16,
+    NoColour,
+}
+
+/// Colour depth reported by terminfo for terminals supporting 24-bit colour (e.g. xterm-direct)
+const TERMINFO_TRUE_COLOUR: u32 = 1 << 24;
+
+impl ColourSupport {
+    /// Make a first guess based on environment variables (looked up with `env`) and the number
+    /// of colours reported by terminfo. `NO_COLOR` (https://no-color.org) takes precedence over
+    /// everything else.
+    pub fn from_environment<F>(env: F, terminfo_colours: Option<u32>) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        if env("NO_COLOR").is_some_and(|no_color| !no_color.is_empty()) {
+            return Self::NoColour;
+        }
+        let term = env("TERM").unwrap_or_default();
+        if term == "dumb" {
+            return Self::NoColour;
+        }
+        if matches!(env("COLORTERM").as_deref(), Some("truecolor" | "24bit"))
+            || term.ends_with("-direct")
+            || term.ends_with("-truecolor")
+        {
+            return Self::TrueColour;
+        }
+        match terminfo_colours {
+            Some(colours) if colours >= TERMINFO_TRUE_COLOUR => Self::TrueColour,
+            Some(colours) if colours >= 256 => Self::Palette256,
+            Some(colours) if colours >= 8 => Self::Ansi16,
+            Some(_) => Self::NoColour,
+            // assume that a terminal whose terminfo doesn't say otherwise has the ansi colours
+            None => Self::Ansi16,
+        }
+    }
+}
+
+/// Set a 24-bit background colour, ask for the current SGR attributes with DECRQSS and for the
+/// "RGB" capability with XTGETTCAP, then reset the SGR attributes and request the primary device
+/// attributes
+pub const QUERY: &str = "\x1B[48;2;1;2;3m\x1BP$qm\x1B\\\x1BP+q524742\x1B\\\x1B[m\x1B[c";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryResponse {
+    /// The terminal reported the 24-bit background colour, or its "RGB" capability
+    pub true_colour: bool,
+    /// The terminal reported its device attributes, so no further responses are coming
+    pub complete: bool,
+}
+
+/// Whether the parameters of a response to DECRQSS include the 24-bit background colour set by
+/// `QUERY`. Terminals separate the parameters of a colour with either ';' or ':' (optionally with
+/// an empty colour space identifier).
+fn reports_true_colour_background(params: &[u8]) -> bool {
+    [&b"48;2;1;2;3"[..], &b"48:2:1:2:3"[..], &b"48:2::1:2:3"[..]]
+        .iter()
+        .any(|expected| params.windows(expected.len()).any(|w| w == *expected))
+}
+
+/// Remove any responses to `QUERY` from `buffer`
+pub fn extract_query_responses(buffer: &mut Vec<u8>) -> QueryResponse {
+    const DCS: &[u8] = b"\x1BP";
+    const ST: &[u8] = b"\x1B\\";
+    const DEVICE_ATTRIBUTES_PREFIX: &[u8] = b"\x1B[?";
+    let mut response = QueryResponse::default();
+    let mut i = 0;
+    while i < buffer.len() {
+        if let Some(after_prefix) = buffer[i..].strip_prefix(DCS)
+            && let Some(payload_len) = after_prefix
+                .windows(ST.len())
+                .position(|window| window == ST)
+        {
+            let payload = &after_prefix[..payload_len];
+            if let Some(params) = payload.strip_prefix(b"1$r") {
+                response.true_colour |= reports_true_colour_background(params);
+            } else if payload.starts_with(b"1+r524742") {
+                response.true_colour = true;
+            }
+            buffer.drain(i..(i + DCS.len() + payload_len + ST.len()));
+            continue;
+        }
+        if let Some(after_prefix) = buffer[i..].strip_prefix(DEVICE_ATTRIBUTES_PREFIX)
+            && let Some(params_len) = after_prefix
+                .iter()
+                .position(|&b| !(b.is_ascii_digit() || b == b';'))
+            && after_prefix[params_len] == b'c'
+        {
+            response.complete = true;
+            buffer.drain(i..(i + DEVICE_ATTRIBUTES_PREFIX.len() + params_len + 1));
+            continue;
+        }
+        i += 1;
+    }
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn from_environment(vars: &[(&str, &str)], terminfo_colours: Option<u32>) -> ColourSupport {
+        let vars = vars
+            .iter()
+            .map(|&(name, value)| (name.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+        ColourSupport::from_environment(|name| vars.get(name).cloned(), terminfo_colours)
+    }
+
+    #[test]
+    fn environment() {
+        use ColourSupport::*;
+        let xterm = ("TERM", "xterm-256color");
+        assert_eq!(from_environment(&[xterm], Some(256)), Palette256);
+        assert_eq!(
+            from_environment(&[xterm, ("COLORTERM", "truecolor")], Some(256)),
+            TrueColour
+        );
+        assert_eq!(
+            from_environment(
+                &[xterm, ("COLORTERM", "truecolor"), ("NO_COLOR", "1")],
+                None
+            ),
+            NoColour
+        );
+        assert_eq!(
+            from_environment(&[("TERM", "xterm-direct")], Some(1 << 24)),
+            TrueColour
+        );
+        assert_eq!(from_environment(&[("TERM", "linux")], Some(8)), Ansi16);
+        assert_eq!(from_environment(&[("TERM", "dumb")], None), NoColour);
+    }
+
+    #[test]
+    fn query_responses() {
+        let mut buffer = b"a\x1BP1$r0;48:2::1:2:3m\x1B\\\x1BP0+r524742\x1B\\\x1B[?62cb".to_vec();
+        assert_eq!(
+            extract_query_responses(&mut buffer),
+            QueryResponse {
+                true_colour: true,
+                complete: true,
+            }
+        );
+        assert_eq!(buffer, b"ab");
+        let mut buffer = b"\x1BP1$r0;48;5;16m\x1B\\\x1B[?62c\x1BP1$r".to_vec();
+        assert_eq!(
+            extract_query_responses(&mut buffer),
+            QueryResponse {
+                true_colour: false,
+                complete: true,
+            }
+        );
+        assert_eq!(buffer, b"\x1BP1$r");
+        let mut buffer = b"\x1BP1+r524742=382F382F38\x1B\\".to_vec();
+        assert_eq!(
+            extract_query_responses(&mut buffer),
+            QueryResponse {
+                true_colour: true,
+                complete: false,
+            }
+        );
+    }
+}
@@ -0,0 +1,260 @@
+//! Parser for parameterised key escape sequences of the form "ESC [ params final" which aren't
+//! among the fixed sequences of `term_info_cache::modified_key_sequences`. This covers the
+//! "CSI u" sequences of the kitty keyboard protocol, and xterm-style sequences for keys pressed
+//! with modifiers (e.g. "ESC[1;5:3A" for releasing ctrl+up) when they include a key event type.
+
+use super::term_info_cache::csi_modifiers;
+use chargrid_input::{Input, KeyEvent, KeyEventKind, KeyModifiers, KeyboardInput, keys};
+
+const CSI: &[u8] = b"\x1B[";
+
+fn event_kind(param: u32) -> KeyEventKind {
+    match param {
+        2 => KeyEventKind::Repeat,
+        3 => KeyEventKind::Release,
+        _ => KeyEventKind::Press,
+    }
+}
+
+/// Key codes used in "CSI u" sequences. Most keys are identified by their unicode code point,
+/// and keys without one are assigned codes in the private use area.
+fn key_from_code(code: u32) -> Option<KeyboardInput> {
+    let key = match code {
+        9 => keys::TAB,
+        13 | 57414 => keys::RETURN,
+        27 => keys::ESCAPE,
+        127 => keys::BACKSPACE,
+        57376..=57398 => KeyboardInput::Function((code - 57376 + 13) as u8),
+        57399..=57408 => KeyboardInput::Char(char::from_digit(code - 57399, 10)?),
+        57409 => KeyboardInput::Char('.'),
+        57410 => KeyboardInput::Char('/'),
+        57411 => KeyboardInput::Char('*'),
+        57412 => KeyboardInput::Char('-'),
+        57413 => KeyboardInput::Char('+'),
+        57415 => KeyboardInput::Char('='),
+        57417 => KeyboardInput::Left,
+        57418 => KeyboardInput::Right,
+        57419 => KeyboardInput::Up,
+        57420 => KeyboardInput::Down,
+        57421 => KeyboardInput::PageUp,
+        57422 => KeyboardInput::PageDown,
+        57423 => KeyboardInput::Home,
+        57424 => KeyboardInput::End,
+        57426 => KeyboardInput::Delete,
+        // the remainder of the private use area is for keys chargrid doesn't represent, such as
+        // the modifier keys themselves
+        57344..=63743 => return None,
+        _ => KeyboardInput::Char(char::from_u32(code)?),
+    };
+    Some(key)
+}
+
+fn key_from_tilde_number(number: u32) -> Option<KeyboardInput> {
+    let key = match number {
+        1 | 7 => KeyboardInput::Home,
+        3 => KeyboardInput::Delete,
+        4 | 8 => KeyboardInput::End,
+        5 => KeyboardInput::PageUp,
+        6 => KeyboardInput::PageDown,
+        11..=15 => KeyboardInput::Function((number - 10) as u8),
+        17..=21 => KeyboardInput::Function((number - 11) as u8),
+        23..=26 => KeyboardInput::Function((number - 12) as u8),
+        28 | 29 => KeyboardInput::Function((number - 13) as u8),
+        31..=34 => KeyboardInput::Function((number - 14) as u8),
+        _ => return None,
+    };
+    Some(key)
+}
+
+fn key_from_letter(letter: u8) -> Option<KeyboardInput> {
+    let key = match letter {
+        b'A' => KeyboardInput::Up,
+        b'B' => KeyboardInput::Down,
+        b'C' => KeyboardInput::Right,
+        b'D' => KeyboardInput::Left,
+        b'H' => KeyboardInput::Home,
+        b'F' => KeyboardInput::End,
+        b'P' => KeyboardInput::Function(1),
+        b'Q' => KeyboardInput::Function(2),
+        b'R' => KeyboardInput::Function(3),
+        b'S' => KeyboardInput::Function(4),
+        b'Z' => keys::TAB,
+        _ => return None,
+    };
+    Some(key)
+}
+
+fn is_final_byte(byte: u8) -> bool {
+    b"u~ABCDEFHPQRSZ".contains(&byte)
+}
+
+/// Each parameter is a list of sub-parameters separated by ':', any of which may be omitted
+fn parse_params(bytes: &[u8]) -> Vec<Vec<Option<u32>>> {
+    bytes
+        .split(|&b| b == b';')
+        .map(|param| {
+            param
+                .split(|&b| b == b':')
+                .map(|sub_param| std::str::from_utf8(sub_param).ok()?.parse().ok())
+                .collect()
+        })
+        .collect()
+}
+
+/// If `bytes` begins with a key sequence, returns the input it describes (or `None` if it
+/// describes a key which chargrid doesn't represent) along with the bytes following the sequence.
+/// Returns `None` if `bytes` doesn't begin with a key sequence.
+pub fn parse(bytes: &[u8]) -> Option<(Option<Input>, &[u8])> {
+    let after_csi = bytes.strip_prefix(CSI)?;
+    let params_len = after_csi
+        .iter()
+        .position(|&b| !(b.is_ascii_digit() || b == b';' || b == b':'))?;
+    let (params, rest) = after_csi.split_at(params_len);
+    let (&final_byte, rest) = rest.split_first()?;
+    if !is_final_byte(final_byte) {
+        return None;
+    }
+    let params = parse_params(params);
+    let param = |index: usize, sub_index: usize| {
+        params
+            .get(index)
+            .and_then(|param| param.get(sub_index).cloned())
+            .flatten()
+    };
+    let mut modifiers = csi_modifiers(param(1, 0).unwrap_or(1));
+    let kind = event_kind(param(1, 1).unwrap_or(1));
+    let key = match final_byte {
+        b'u' => {
+            // with shift held, prefer the shifted key if the terminal reports it
+            let code = param(0, 1).filter(|_| modifiers.shift()).or(param(0, 0));
+            key_from_code(code.unwrap_or(0)).map(|key| match key {
+                KeyboardInput::Char(ch) if modifiers.shift() => {
+                    KeyboardInput::Char(ch.to_ascii_uppercase())
+                }
+                other => other,
+            })
+        }
+        b'~' => key_from_tilde_number(param(0, 0).unwrap_or(0)),
+        b'Z' => {
+            modifiers |= KeyModifiers::SHIFT;
+            key_from_letter(final_byte)
+        }
+        _ => key_from_letter(final_byte),
+    };
+    let input = key.map(|key| {
+        let key_event = KeyEvent::new(key, modifiers).with_kind(kind);
+        // ctrl+c is reported as ETX as it would be without the kitty keyboard protocol, so it
+        // can still be used to close applications
+        if key_event == KeyEvent::new(KeyboardInput::Char('c'), KeyModifiers::CTRL) {
+            Input::Keyboard(keys::ETX)
+        } else {
+            key_event.input()
+        }
+    });
+    Some((input, rest))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(key: KeyboardInput, modifiers: KeyModifiers, kind: KeyEventKind) -> Option<Input> {
+        Some(KeyEvent::new(key, modifiers).with_kind(kind).input())
+    }
+
+    #[test]
+    fn xterm_modifiers() {
+        use KeyEventKind::Press;
+        assert_eq!(
+            parse(b"\x1B[1;5Arest"),
+            Some((
+                key(KeyboardInput::Up, KeyModifiers::CTRL, Press),
+                &b"rest"[..]
+            ))
+        );
+        assert_eq!(
+            parse(b"\x1B[1;2D"),
+            Some((
+                key(KeyboardInput::Left, KeyModifiers::SHIFT, Press),
+                &b""[..]
+            ))
+        );
+        assert_eq!(
+            parse(b"\x1B[6;3~"),
+            Some((
+                key(KeyboardInput::PageDown, KeyModifiers::ALT, Press),
+                &b""[..]
+            ))
+        );
+        assert_eq!(
+            parse(b"\x1B[15;7~"),
+            Some((
+                key(
+                    KeyboardInput::Function(5),
+                    KeyModifiers::CTRL | KeyModifiers::ALT,
+                    Press
+                ),
+                &b""[..]
+            ))
+        );
+        assert_eq!(
+            parse(b"\x1B[Z"),
+            Some((key(keys::TAB, KeyModifiers::SHIFT, Press), &b""[..]))
+        );
+    }
+
+    #[test]
+    fn event_types() {
+        assert_eq!(
+            parse(b"\x1B[97;1:3u"),
+            Some((
+                key(
+                    KeyboardInput::Char('a'),
+                    KeyModifiers::NONE,
+                    KeyEventKind::Release
+                ),
+                &b""[..]
+            ))
+        );
+        assert_eq!(
+            parse(b"\x1B[97:65;2:2u"),
+            Some((
+                key(
+                    KeyboardInput::Char('A'),
+                    KeyModifiers::NONE,
+                    KeyEventKind::Repeat
+                ),
+                &b""[..]
+            ))
+        );
+        assert_eq!(
+            parse(b"\x1B[1;1:3B"),
+            Some((
+                key(
+                    KeyboardInput::Down,
+                    KeyModifiers::NONE,
+                    KeyEventKind::Release
+                ),
+                &b""[..]
+            ))
+        );
+        assert_eq!(
+            parse(b"\x1B[97u"),
+            Some((Some(Input::Keyboard(KeyboardInput::Char('a'))), &b""[..]))
+        );
+        assert_eq!(
+            parse(b"\x1B[99;5u"),
+            Some((Some(Input::Keyboard(keys::ETX)), &b""[..]))
+        );
+        // left shift
+        assert_eq!(parse(b"\x1B[57441;2u"), Some((None, &b""[..])));
+    }
+
+    #[test]
+    fn not_key_sequences() {
+        assert_eq!(parse(b"\x1B[M abc"), None);
+        assert_eq!(parse(b"\x1B[<0;1;2M"), None);
+        assert_eq!(parse(b"\x1B[1;5"), None);
+        assert_eq!(parse(b"abc"), None);
+    }
+}
@@ -0,0 +1,54 @@
+//! Query of the cursor position, used to find where an inline viewport begins. The terminal
+//! answers "ESC [ 6 n" with "ESC [ row ; column R" where row and column count from 1.
+
+use chargrid_runtime::ICoord;
+
+pub const QUERY: &str = "\x1B[6n";
+
+/// Remove the first response to `QUERY` from `buffer`, returning the reported position (with
+/// coordinates counting from 0)
+pub fn extract_response(buffer: &mut Vec<u8>) -> Option<ICoord> {
+    const PREFIX: &[u8] = b"\x1B[";
+    for i in 0..buffer.len() {
+        let Some(after_prefix) = buffer[i..].strip_prefix(PREFIX) else {
+            continue;
+        };
+        let Some(params_len) = after_prefix
+            .iter()
+            .position(|&b| !(b.is_ascii_digit() || b == b';'))
+        else {
+            continue;
+        };
+        if after_prefix[params_len] != b'R' {
+            continue;
+        }
+        let params = std::str::from_utf8(&after_prefix[..params_len])
+            .ok()?
+            .split(';')
+            .map(|param| param.parse::<i32>().ok())
+            .collect::<Option<Vec<_>>>();
+        if let Some(&[row, column]) = params.as_deref()
+            && row > 0
+            && column > 0
+        {
+            buffer.drain(i..(i + PREFIX.len() + params_len + 1));
+            return Some(ICoord::new(column - 1, row - 1));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn response() {
+        let mut buffer = b"a\x1B[A\x1B[12;1Rb".to_vec();
+        assert_eq!(extract_response(&mut buffer), Some(ICoord::new(0, 11)));
+        assert_eq!(buffer, b"a\x1B[Ab");
+        let mut buffer = b"\x1B[12;".to_vec();
+        assert_eq!(extract_response(&mut buffer), None);
+        assert_eq!(buffer, b"\x1B[12;");
+    }
+}
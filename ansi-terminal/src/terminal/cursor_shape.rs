@@ -0,0 +1,18 @@
+//! Control of the shape of the cursor with DECSCUSR ("ESC [ n SP q"), which is supported by most
+//! terminals emulating xterm
+
+use chargrid_runtime::CursorShape;
+
+/// Restore the terminal's default cursor shape
+pub const RESET: &str = "\x1B[0 q";
+
+pub fn set(shape: CursorShape, blink: bool) -> &'static str {
+    match (shape, blink) {
+        (CursorShape::Block, true) => "\x1B[1 q",
+        (CursorShape::Block, false) => "\x1B[2 q",
+        (CursorShape::Underline, true) => "\x1B[3 q",
+        (CursorShape::Underline, false) => "\x1B[4 q",
+        (CursorShape::Bar, true) => "\x1B[5 q",
+        (CursorShape::Bar, false) => "\x1B[6 q",
+    }
+}
@@ -0,0 +1,163 @@
+//! The channel over which escape sequences are sent to and input is read from a terminal. This is
+//! usually the controlling terminal of the process (see `low_level`), but can be any reader and
+//! writer, such as a pty, a network connection, or a test harness.
+
+use crate::error::Result;
+use chargrid_runtime::UCoord;
+use std::io::{self, Read, Write};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+const READ_CHUNK_SIZE: usize = 1024;
+
+pub trait TerminalIo {
+    fn send(&mut self, data: &str) -> io::Result<()>;
+
+    /// Append any input which is available to `buf` without blocking
+    fn read_polling(&mut self, buf: &mut Vec<u8>) -> Result<()>;
+
+    /// Block until there is input to read, the terminal may have been resized, or the timeout
+    /// (if any) elapses
+    fn wait_for_input(&mut self, timeout: Option<Duration>) -> Result<()>;
+
+    fn size(&self) -> Result<UCoord>;
+
+    /// Returns true if the terminal may have been resized since this was last called
+    fn size_may_have_changed(&mut self) -> bool;
+
+    /// Set the escape sequences which restore the terminal to its original state, to be sent if
+    /// the process panics or is terminated by a signal before the terminal is dropped. This is
+    /// only possible for the controlling terminal of the process, so does nothing by default.
+    fn set_abnormal_exit_teardown(&mut self, _teardown: &str) {}
+
+    /// Returns true if the terminal was restored to its original state by a panic which was then
+    /// caught rather than ending the program, after putting the terminal back into raw mode. The
+    /// escape sequences which initialise the terminal must then be sent again.
+    fn take_restored_after_panic(&mut self) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Whether `suspend_process` is supported. Suspending only makes sense when the terminal is
+    /// the controlling terminal of the process, so this is false by default.
+    fn can_suspend_process(&self) -> bool {
+        false
+    }
+
+    /// Restore the original terminal settings and stop the process with SIGTSTP (returning
+    /// control of the terminal to the shell), then put the terminal back into raw mode once the
+    /// process is continued. Escape sequences are the caller's responsibility.
+    fn suspend_process(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Communicates with a terminal over an arbitrary reader and writer. Since readers generally
+/// block until input is available, the reader is read from a background thread which forwards
+/// input over a channel. A read can't be interrupted, so the thread may outlive this: it stops
+/// when the reader reaches the end of its input or fails, or when it next reads some input after
+/// this has been dropped, and it drops the reader when it stops.
+pub struct ReadWriteIo {
+    input: mpsc::Receiver<Vec<u8>>,
+    /// Taken when this is dropped
+    reader_thread: Option<thread::JoinHandle<()>>,
+    output: Box<dyn Write>,
+    size: Box<dyn Fn() -> UCoord>,
+    /// Input received while waiting for input which hasn't been read yet
+    pending: Vec<u8>,
+}
+
+impl ReadWriteIo {
+    pub fn new<R, W, S>(mut reader: R, writer: W, size: S) -> Self
+    where
+        R: 'static + Read + Send,
+        W: 'static + Write,
+        S: 'static + Fn() -> UCoord,
+    {
+        let (sender, input) = mpsc::channel();
+        let reader_thread = thread::spawn(move || {
+            let mut chunk = [0; READ_CHUNK_SIZE];
+            loop {
+                match reader.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        if sender.send(chunk[..n].to_vec()).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                    Err(_) => break,
+                }
+            }
+        });
+        Self {
+            input,
+            reader_thread: Some(reader_thread),
+            output: Box::new(writer),
+            size: Box::new(size),
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl Drop for ReadWriteIo {
+    fn drop(&mut self) {
+        // Joining a thread which is still blocked reading would block until the reader produces
+        // more input, so such a thread is left to stop by itself (see `ReadWriteIo`).
+        if let Some(reader_thread) = self.reader_thread.take()
+            && reader_thread.is_finished()
+        {
+            let _ = reader_thread.join();
+        }
+    }
+}
+
+impl TerminalIo for ReadWriteIo {
+    fn send(&mut self, data: &str) -> io::Result<()> {
+        self.output.write_all(data.as_bytes())?;
+        self.output.flush()
+    }
+
+    fn read_polling(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+        buf.append(&mut self.pending);
+        while let Ok(chunk) = self.input.try_recv() {
+            buf.extend_from_slice(&chunk);
+        }
+        Ok(())
+    }
+
+    fn wait_for_input(&mut self, timeout: Option<Duration>) -> Result<()> {
+        if !self.pending.is_empty() {
+            return Ok(());
+        }
+        match timeout {
+            Some(timeout) => match self.input.recv_timeout(timeout) {
+                Ok(chunk) => self.pending = chunk,
+                Err(mpsc::RecvTimeoutError::Timeout) => (),
+                // the reader has closed so no more input will arrive
+                Err(mpsc::RecvTimeoutError::Disconnected) => thread::sleep(timeout),
+            },
+            None => match self.input.recv() {
+                Ok(chunk) => self.pending = chunk,
+                Err(mpsc::RecvError) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "terminal input was closed",
+                    )
+                    .into());
+                }
+            },
+        }
+        Ok(())
+    }
+
+    fn size(&self) -> Result<UCoord> {
+        Ok((self.size)())
+    }
+
+    /// There's no way to be notified when the size reported by the callback changes, so it's
+    /// checked every time
+    fn size_may_have_changed(&mut self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,79 @@
+//! Negotiation of the kitty keyboard protocol. Terminals which support the protocol maintain a
+//! stack of "progressive enhancement" flags controlling how keys are reported. Support is
+//! detected by querying the current flags, followed by a request for the primary device
+//! attributes which every terminal answers. If the device attributes arrive without a reply to
+//! the flags query, the terminal doesn't support the protocol.
+
+/// Query the current flags, then request the primary device attributes
+pub const QUERY: &str = "\x1B[?u\x1B[c";
+
+/// Report keys which would otherwise be ambiguous (such as escape, alt+key, and ctrl+i) as
+/// escape sequences. Keys which produce text are still sent as text.
+pub const PUSH_DISAMBIGUATE: &str = "\x1B[>1u";
+
+/// Report key repeats and releases. This requires all keys to be reported as escape sequences
+/// (including those which produce text), and shifted keys to be reported alongside base keys so
+/// that shift+a is reported as 'A'.
+pub const PUSH_REPEAT_AND_RELEASE: &str = "\x1B[>15u";
+
+/// Restore the flags which were in effect before the most recent push
+pub const POP: &str = "\x1B[<u";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryResponse {
+    /// The terminal reported its current flags, so it supports the protocol
+    pub supported: bool,
+    /// The terminal reported its device attributes, so no further responses are coming
+    pub complete: bool,
+}
+
+/// Remove any responses to `QUERY` from `buffer`
+pub fn extract_query_responses(buffer: &mut Vec<u8>) -> QueryResponse {
+    const PREFIX: &[u8] = b"\x1B[?";
+    let mut response = QueryResponse::default();
+    let mut i = 0;
+    while i < buffer.len() {
+        if let Some(after_prefix) = buffer[i..].strip_prefix(PREFIX)
+            && let Some(params_len) = after_prefix
+                .iter()
+                .position(|&b| !(b.is_ascii_digit() || b == b';'))
+        {
+            let final_byte = after_prefix[params_len];
+            if final_byte == b'u' || final_byte == b'c' {
+                response.supported |= final_byte == b'u';
+                response.complete |= final_byte == b'c';
+                buffer.drain(i..(i + PREFIX.len() + params_len + 1));
+                continue;
+            }
+        }
+        i += 1;
+    }
+    response
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn query_responses() {
+        let mut buffer = b"a\x1B[?1u\x1B[?62;22cb".to_vec();
+        assert_eq!(
+            extract_query_responses(&mut buffer),
+            QueryResponse {
+                supported: true,
+                complete: true,
+            }
+        );
+        assert_eq!(buffer, b"ab");
+        let mut buffer = b"\x1B[?62;22c\x1B[A\x1B[?6".to_vec();
+        assert_eq!(
+            extract_query_responses(&mut buffer),
+            QueryResponse {
+                supported: false,
+                complete: true,
+            }
+        );
+        assert_eq!(buffer, b"\x1B[A\x1B[?6");
+    }
+}
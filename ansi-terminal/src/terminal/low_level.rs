@@ -1,9 +1,93 @@
+use super::io::TerminalIo;
+use super::restore::RestoreOnAbnormalExit;
 use crate::error::{Error, Result};
 use chargrid_runtime::UCoord;
 use std::fs::{File, OpenOptions};
 use std::io::{self, Read, Write};
 use std::mem::MaybeUninit;
 use std::os::unix::io::{AsRawFd, RawFd};
+use std::ptr;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::time::Duration;
+
+/// The write end of the pipe to which a byte is written each time SIGWINCH is received, or -1
+static RESIZE_PIPE_WRITE_FD: AtomicI32 = AtomicI32::new(-1);
+
+extern "C" fn handle_sigwinch(_signal: libc::c_int) {
+    let fd = RESIZE_PIPE_WRITE_FD.load(Ordering::Relaxed);
+    if fd >= 0 {
+        // if the pipe is full then there's already a pending notification
+        unsafe { libc::write(fd, [0u8].as_ptr() as *const libc::c_void, 1) };
+    }
+}
+
+/// Notification of SIGWINCH (sent when the terminal is resized) using the self-pipe trick, so
+/// that waiting for input on the terminal can also be woken by a resize without racing with the
+/// signal handler
+struct ResizeSignal {
+    read_fd: RawFd,
+    write_fd: RawFd,
+    original_action: libc::sigaction,
+}
+
+impl ResizeSignal {
+    fn new() -> Result<Self> {
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(Error::last_os_error());
+        }
+        let [read_fd, write_fd] = fds;
+        for fd in fds {
+            unsafe {
+                libc::fcntl(fd, libc::F_SETFL, libc::O_NONBLOCK);
+                libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+            }
+        }
+        RESIZE_PIPE_WRITE_FD.store(write_fd, Ordering::Relaxed);
+        let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+        action.sa_sigaction = handle_sigwinch as *const () as libc::sighandler_t;
+        let mut original_action = MaybeUninit::uninit();
+        let res = unsafe { libc::sigaction(libc::SIGWINCH, &action, original_action.as_mut_ptr()) };
+        if res != 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(Self {
+            read_fd,
+            write_fd,
+            original_action: unsafe { original_action.assume_init() },
+        })
+    }
+
+    /// Returns true if SIGWINCH was received since this was last called
+    fn take(&mut self) -> bool {
+        let mut buf = [0u8; 64];
+        let mut received = false;
+        while unsafe {
+            libc::read(
+                self.read_fd,
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+            )
+        } > 0
+        {
+            received = true;
+        }
+        received
+    }
+}
+
+impl Drop for ResizeSignal {
+    fn drop(&mut self) {
+        unsafe {
+            libc::sigaction(libc::SIGWINCH, &self.original_action, ptr::null_mut());
+        }
+        RESIZE_PIPE_WRITE_FD.store(-1, Ordering::Relaxed);
+        unsafe {
+            libc::close(self.read_fd);
+            libc::close(self.write_fd);
+        }
+    }
+}
 
 #[repr(C)]
 struct WinSize {
@@ -17,6 +101,8 @@ pub struct LowLevel {
     tty_file: File,
     tty_fd: RawFd,
     original_termios: libc::termios,
+    resize_signal: ResizeSignal,
+    restore_on_abnormal_exit: RestoreOnAbnormalExit,
 }
 
 impl LowLevel {
@@ -53,14 +139,28 @@ impl LowLevel {
         let tty_file = OpenOptions::new().write(true).read(true).open("/dev/tty")?;
         let tty_fd = tty_file.as_raw_fd();
         let original_termios = Self::init_tty(tty_fd)?;
+        let resize_signal = ResizeSignal::new()?;
+        let restore_on_abnormal_exit = RestoreOnAbnormalExit::new(tty_fd, original_termios);
         Ok(Self {
             tty_file,
             original_termios,
             tty_fd,
+            resize_signal,
+            restore_on_abnormal_exit,
         })
     }
 
-    pub fn size(&self) -> Result<UCoord> {
+    fn teardown(&mut self) -> Result<()> {
+        let res = unsafe { libc::tcsetattr(self.tty_fd, libc::TCSAFLUSH, &self.original_termios) };
+        if res != 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl TerminalIo for LowLevel {
+    fn size(&self) -> Result<UCoord> {
         let mut win_size = WinSize {
             ws_row: 0,
             ws_col: 0,
@@ -78,20 +178,76 @@ impl LowLevel {
         }
     }
 
-    pub fn send(&mut self, data: &str) -> io::Result<()> {
+    fn send(&mut self, data: &str) -> io::Result<()> {
+        if self.restore_on_abnormal_exit.restored() && std::thread::panicking() {
+            // the terminal was restored before printing a panic message, which any further
+            // output (such as the usual teardown while unwinding) would interfere with
+            return Ok(());
+        }
         self.tty_file.write_all(data.as_bytes())
     }
 
-    pub fn read_polling(&mut self, buf: &mut Vec<u8>) -> Result<()> {
+    fn read_polling(&mut self, buf: &mut Vec<u8>) -> Result<()> {
         self.tty_file.read_to_end(buf)?;
         Ok(())
     }
 
-    fn teardown(&mut self) -> Result<()> {
-        let res = unsafe { libc::tcsetattr(self.tty_fd, libc::TCSAFLUSH, &self.original_termios) };
-        if res != 0 {
+    fn wait_for_input(&mut self, timeout: Option<Duration>) -> Result<()> {
+        let mut poll_fds = [self.tty_fd, self.resize_signal.read_fd].map(|fd| libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        });
+        let timeout_ms = match timeout {
+            Some(timeout) => timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int,
+            None => -1,
+        };
+        let res = unsafe {
+            libc::poll(
+                poll_fds.as_mut_ptr(),
+                poll_fds.len() as libc::nfds_t,
+                timeout_ms,
+            )
+        };
+        if res < 0 {
+            let error = io::Error::last_os_error();
+            if error.kind() != io::ErrorKind::Interrupted {
+                return Err(error.into());
+            }
+        }
+        Ok(())
+    }
+
+    fn size_may_have_changed(&mut self) -> bool {
+        self.resize_signal.take()
+    }
+
+    fn set_abnormal_exit_teardown(&mut self, teardown: &str) {
+        self.restore_on_abnormal_exit.set_teardown(teardown);
+    }
+
+    fn take_restored_after_panic(&mut self) -> Result<bool> {
+        if !self.restore_on_abnormal_exit.restored() {
+            return Ok(false);
+        }
+        Self::init_tty(self.tty_fd)?;
+        self.restore_on_abnormal_exit.arm();
+        Ok(true)
+    }
+
+    fn can_suspend_process(&self) -> bool {
+        true
+    }
+
+    fn suspend_process(&mut self) -> Result<()> {
+        self.teardown()?;
+        // With the default action for SIGTSTP, this doesn't return until SIGCONT is received.
+        // If SIGTSTP is being ignored (e.g. the process isn't under job control) then it returns
+        // immediately.
+        if unsafe { libc::raise(libc::SIGTSTP) } != 0 {
             return Err(Error::last_os_error());
         }
+        Self::init_tty(self.tty_fd)?;
         Ok(())
     }
 }
@@ -102,3 +258,17 @@ impl Drop for LowLevel {
             .expect("Failed to reset terminal to original settings");
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resize_signal() {
+        let mut resize_signal = ResizeSignal::new().unwrap();
+        assert!(!resize_signal.take());
+        unsafe { libc::raise(libc::SIGWINCH) };
+        assert!(resize_signal.take());
+        assert!(!resize_signal.take());
+    }
+}
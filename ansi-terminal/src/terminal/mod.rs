@@ -1,20 +1,39 @@
 use crate::error::Result;
 use chargrid_input::*;
-use chargrid_runtime::{FrameBuffer, FrameBufferCell, Rgba32, UCoord};
+use chargrid_runtime::{
+    Cursor, FrameBuffer, FrameBufferCell, Grapheme, Rgba32, UCoord, UnderlineStyle, rgb_int::Rgb24,
+};
+use io::ReadWriteIo;
+use std::io::{Read, Write};
+use std::mem;
+use std::time::Duration;
+use term_info_cache::TermInfoCache;
 
 mod ansi_colour_codes;
 mod ansi_terminal;
+mod bracketed_paste;
 mod byte_prefix_tree;
+mod colour_support;
+mod csi_key;
+mod cursor_position;
+mod cursor_shape;
+mod io;
+mod kitty_keyboard;
+mod sgr_mouse;
 mod term_info_cache;
 
 #[cfg(unix)]
 mod low_level;
+#[cfg(unix)]
+mod restore;
 
 #[cfg(not(unix))]
 mod low_level {
+    use super::io::TerminalIo;
     use crate::error::{Error, Result};
     use chargrid_runtime::UCoord;
     use std::io;
+    use std::time::Duration;
 
     pub struct LowLevel {}
 
@@ -22,31 +41,122 @@ mod low_level {
         pub fn new() -> Result<Self> {
             Err(Error::NonUnixOS)
         }
+    }
 
-        pub fn send(&mut self, _data: &str) -> io::Result<()> {
+    impl TerminalIo for LowLevel {
+        fn send(&mut self, _data: &str) -> io::Result<()> {
             panic!("Unimplemented on non-unix OS")
         }
 
-        pub fn read_polling(&mut self, _buf: &mut Vec<u8>) -> Result<()> {
+        fn read_polling(&mut self, _buf: &mut Vec<u8>) -> Result<()> {
             panic!("Unimplemented on non-unix OS")
         }
 
-        pub fn size(&self) -> Result<UCoord> {
+        fn wait_for_input(&mut self, _timeout: Option<Duration>) -> Result<()> {
+            panic!("Unimplemented on non-unix OS")
+        }
+
+        fn size(&self) -> Result<UCoord> {
+            panic!("Unimplemented on non-unix OS")
+        }
+
+        fn size_may_have_changed(&mut self) -> bool {
+            panic!("Unimplemented on non-unix OS")
+        }
+
+        fn suspend_process(&mut self) -> Result<()> {
             panic!("Unimplemented on non-unix OS")
         }
     }
 }
 
-pub use self::ansi_terminal::{AnsiTerminal, ColEncode, DrainInput, col_encode};
+pub use self::ansi_terminal::{
+    AnsiTerminal, ColEncode, DrainInput, TerminalEvent, Viewport, col_encode,
+};
+pub use self::colour_support::ColourSupport;
+#[cfg(unix)]
+pub use self::restore::restore_terminal;
+
+/// The attributes of a cell other than its character and colours
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Attributes {
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: Option<UnderlineStyle>,
+    underline_colour: Option<Rgba32>,
+    blink: bool,
+    reverse: bool,
+    strikethrough: bool,
+}
+
+impl Attributes {
+    fn of_cell(cell: &FrameBufferCell) -> Self {
+        Self {
+            bold: cell.bold,
+            dim: cell.dim,
+            italic: cell.italic,
+            underline: cell.underline.then_some(cell.underline_style),
+            underline_colour: cell.underline_colour.filter(|_| cell.underline),
+            blink: cell.blink,
+            reverse: cell.reverse,
+            strikethrough: cell.strikethrough,
+        }
+    }
+
+    /// Returns true if changing from `self` to `to` only involves turning on attributes. Most
+    /// attributes can only be turned off by resetting all attributes (including colours).
+    fn can_change_without_reset(self, to: Self) -> bool {
+        (!self.bold || to.bold)
+            && (!self.dim || to.dim)
+            && (!self.italic || to.italic)
+            && (self.underline.is_none() || self.underline == to.underline)
+            && (self.underline_colour.is_none() || self.underline_colour == to.underline_colour)
+            && (!self.blink || to.blink)
+            && (!self.reverse || to.reverse)
+            && (!self.strikethrough || to.strikethrough)
+    }
+
+    /// Turn on the attributes which are in `to` but not in `from`
+    fn turn_on(from: Self, to: Self, ansi: &mut AnsiTerminal) {
+        if to.bold && !from.bold {
+            ansi.set_bold();
+        }
+        if to.dim && !from.dim {
+            ansi.set_dim();
+        }
+        if to.italic && !from.italic {
+            ansi.set_italic();
+        }
+        if let Some(underline_style) = to.underline
+            && from.underline.is_none()
+        {
+            ansi.set_underline(underline_style);
+        }
+        if let Some(underline_colour) = to.underline_colour
+            && from.underline_colour.is_none()
+        {
+            ansi.set_underline_colour(underline_colour.to_rgb24());
+        }
+        if to.blink && !from.blink {
+            ansi.set_blink();
+        }
+        if to.reverse && !from.reverse {
+            ansi.set_reverse();
+        }
+        if to.strikethrough && !from.strikethrough {
+            ansi.set_strikethrough();
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 struct OutputCell {
     dirty: bool,
-    ch: char,
+    ch: Grapheme,
     fg: Rgba32,
     bg: Rgba32,
-    bold: bool,
-    underline: bool,
+    attributes: Attributes,
 }
 
 impl OutputCell {
@@ -55,25 +165,22 @@ impl OutputCell {
             && self.ch == cell.character
             && self.fg == cell.foreground
             && self.bg == cell.background
-            && self.bold == cell.bold
-            && self.underline == cell.underline
+            && self.attributes == Attributes::of_cell(cell)
     }
     fn copy_fields(&mut self, cell: &FrameBufferCell) {
         self.dirty = false;
         self.ch = cell.character;
         self.fg = cell.foreground;
         self.bg = cell.background;
-        self.bold = cell.bold;
-        self.underline = cell.underline;
+        self.attributes = Attributes::of_cell(cell);
     }
     fn new() -> Self {
         Self {
             dirty: true,
-            ch: ' ',
+            ch: Grapheme::from_char(' '),
             fg: Rgba32::new_grey(0),
             bg: Rgba32::new_grey(0),
-            bold: false,
-            underline: false,
+            attributes: Attributes::default(),
         }
     }
 }
@@ -81,19 +188,54 @@ impl OutputCell {
 pub struct Terminal {
     ansi: AnsiTerminal,
     output_frame: grid_2d::Grid<OutputCell>,
+    /// The cursor displayed after drawing the previous frame
+    cursor: Option<Cursor>,
 }
 
 impl Terminal {
-    pub fn new() -> Result<Self> {
-        let ansi = AnsiTerminal::new()?;
+    pub fn new(viewport: Viewport) -> Result<Self> {
+        AnsiTerminal::new(viewport).and_then(Self::from_ansi)
+    }
+
+    /// Communicate with a terminal over a reader and writer rather than the controlling terminal
+    /// of the process. The type of the terminal and its colour support are determined by the
+    /// environment variables of the terminal (`TERM`, `COLORTERM` and `NO_COLOR`), which are
+    /// looked up with `env`.
+    pub fn from_read_write<R, W, S, E>(
+        reader: R,
+        writer: W,
+        size: S,
+        viewport: Viewport,
+        env: E,
+    ) -> Result<Self>
+    where
+        R: 'static + Read + Send,
+        W: 'static + Write,
+        S: 'static + Fn() -> UCoord,
+        E: Fn(&str) -> Option<String>,
+    {
+        AnsiTerminal::from_io(
+            Box::new(ReadWriteIo::new(reader, writer, size)),
+            TermInfoCache::from_environment(env)?,
+            viewport,
+        )
+        .and_then(Self::from_ansi)
+    }
+
+    fn from_ansi(ansi: AnsiTerminal) -> Result<Self> {
         let size = ansi.size()?;
         let output_frame = grid_2d::Grid::new_fn(size, |_| OutputCell::new());
-        Ok(Self { ansi, output_frame })
+        Ok(Self {
+            ansi,
+            output_frame,
+            cursor: None,
+        })
     }
 
     pub fn resize_if_necessary(&mut self) -> Result<UCoord> {
+        let reanchored = self.ansi.reanchor_if_resized()?;
         let size = self.ansi.size()?;
-        if size != self.output_frame.size() {
+        if reanchored || size != self.output_frame.size() {
             self.output_frame = grid_2d::Grid::new_fn(size, |_| OutputCell::new());
         }
         Ok(size)
@@ -103,62 +245,143 @@ impl Terminal {
         self.ansi.size()
     }
 
+    pub fn size_may_have_changed(&mut self) -> bool {
+        self.ansi.size_may_have_changed()
+    }
+
+    pub fn wait_for_input(&mut self, timeout: Option<Duration>) -> Result<()> {
+        self.ansi.wait_for_input(timeout)
+    }
+
+    pub fn kitty_keyboard(&self) -> bool {
+        self.ansi.kitty_keyboard()
+    }
+
+    pub fn colour_support(&self) -> ColourSupport {
+        self.ansi.colour_support()
+    }
+
+    pub fn set_key_repeat_and_release(&mut self, key_repeat_and_release: bool) -> Result<()> {
+        self.ansi.set_key_repeat_and_release(key_repeat_and_release)
+    }
+
+    /// Suspend the process (see `AnsiTerminal::suspend`). When it resumes, the next frame is
+    /// redrawn in its entirety, since the contents of the terminal may have changed in the
+    /// meantime.
+    pub fn suspend(&mut self) -> Result<()> {
+        if self.ansi.suspend()? {
+            self.invalidate_output_frame();
+        }
+        Ok(())
+    }
+
+    /// Redraw the next frame in its entirety
+    fn invalidate_output_frame(&mut self) {
+        for output_cell in self.output_frame.iter_mut() {
+            output_cell.dirty = true;
+        }
+        self.cursor = None;
+    }
+
+    /// Draw the cells which changed since the previous frame, then move the cursor to where the
+    /// frame requests it (or hide it). Nothing is sent if nothing changed, so as not to disturb
+    /// the blinking of the cursor. If the terminal was restored by a panic which the application
+    /// caught, it's initialised again and the entire frame is redrawn.
     pub fn draw_frame<E>(&mut self, frame: &mut FrameBuffer) -> Result<()>
     where
         E: ColEncode,
     {
-        self.ansi.set_cursor(ICoord::new(0, 0))?;
-        let mut bold = false;
-        let mut underline = false;
-        let mut fg = Rgba32::new_grey(0);
-        let mut bg = Rgba32::new_grey(0);
-        self.ansi.reset();
-        self.ansi.clear_underline();
-        self.ansi.set_foreground_colour::<E>(fg.to_rgb24());
-        self.ansi.set_background_colour::<E>(bg.to_rgb24());
+        if self.ansi.resume_after_caught_panic()? {
+            self.invalidate_output_frame();
+        }
+        let mut started = false;
+        let mut attributes = Attributes::default();
+        // the escape sequences which set the current colours, and space to encode new ones
+        let mut fg = String::new();
+        let mut bg = String::new();
+        let mut encoded = String::new();
         let mut must_move_cursor = false;
         for ((coord, cell), output_cell) in frame.enumerate().zip(self.output_frame.iter_mut()) {
             if output_cell.matches(cell) {
                 must_move_cursor = true;
                 continue;
             }
-            let reset = if cell.bold != bold {
-                if cell.bold {
-                    self.ansi.set_bold();
-                    bold = true;
-                    false
-                } else {
-                    self.ansi.reset();
-                    bold = false;
-                    true
-                }
-            } else {
+            if cell.is_continuation() {
+                // the terminal already advanced the cursor past this cell when drawing the wide
+                // character to its left
+                output_cell.copy_fields(cell);
+                continue;
+            }
+            if !started {
+                // hide the cursor while it moves around the terminal drawing cells
+                self.ansi.hide_cursor();
+                self.ansi.set_cursor(ICoord::new(0, 0))?;
+                self.ansi.reset();
+                self.ansi.clear_underline();
+                self.ansi
+                    .encode_foreground_colour::<E>(Rgb24::new_grey(0), &mut fg);
+                self.ansi
+                    .encode_background_colour::<E>(Rgb24::new_grey(0), &mut bg);
+                self.ansi.add_str_to_buffer(&fg);
+                self.ansi.add_str_to_buffer(&bg);
+                started = true;
+            }
+            let cell_attributes = Attributes::of_cell(cell);
+            let reset = if attributes.can_change_without_reset(cell_attributes) {
                 false
+            } else {
+                self.ansi.reset();
+                attributes = Attributes::default();
+                true
             };
-            if reset || cell.foreground != fg {
-                self.ansi
-                    .set_foreground_colour::<E>(cell.foreground.to_rgb24());
-                fg = cell.foreground;
+            if cell_attributes != attributes {
+                Attributes::turn_on(attributes, cell_attributes, &mut self.ansi);
+                attributes = cell_attributes;
             }
-            if reset || cell.background != bg {
-                self.ansi
-                    .set_background_colour::<E>(cell.background.to_rgb24());
-                bg = cell.background;
+            // dithering may give cells of the same colour different encodings depending on their
+            // position, and gives most cells distinct colours which still encode the same, so
+            // it's the encodings which are compared
+            let foreground = self.ansi.dither::<E>(cell.foreground.to_rgb24(), coord);
+            encoded.clear();
+            self.ansi
+                .encode_foreground_colour::<E>(foreground, &mut encoded);
+            if reset || encoded != fg {
+                self.ansi.add_str_to_buffer(&encoded);
+                mem::swap(&mut fg, &mut encoded);
             }
-            if reset || (cell.underline != underline) {
-                if cell.underline {
-                    self.ansi.set_underline();
-                } else {
-                    self.ansi.clear_underline();
-                }
-                underline = cell.underline;
+            let background = self.ansi.dither::<E>(cell.background.to_rgb24(), coord);
+            encoded.clear();
+            self.ansi
+                .encode_background_colour::<E>(background, &mut encoded);
+            if reset || encoded != bg {
+                self.ansi.add_str_to_buffer(&encoded);
+                mem::swap(&mut bg, &mut encoded);
             }
             if must_move_cursor {
                 self.ansi.set_cursor(coord)?;
                 must_move_cursor = false;
             }
             output_cell.copy_fields(cell);
-            self.ansi.add_char_to_buffer(cell.character);
+            self.ansi.add_str_to_buffer(cell.character.as_str());
+        }
+        let cursor = frame.cursor();
+        if started || cursor != self.cursor {
+            match cursor {
+                Some(cursor) => {
+                    self.ansi.set_cursor(cursor.coord)?;
+                    self.ansi.set_cursor_shape(cursor.shape, cursor.blink)?;
+                    self.ansi.show_cursor();
+                }
+                None => {
+                    self.ansi.hide_cursor();
+                    if started && matches!(self.ansi.viewport(), Viewport::Inline { .. }) {
+                        // keep track of which row of an inline viewport the cursor is on, which is
+                        // needed to find the viewport again if the terminal is resized
+                        self.ansi.set_cursor(ICoord::new(0, 0))?;
+                    }
+                }
+            }
+            self.cursor = cursor;
         }
         self.ansi.flush_buffer()?;
         Ok(())
@@ -168,3 +391,230 @@ impl Terminal {
         self.ansi.drain_input()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chargrid_core::{CursorShape, RenderCell};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::io::{self, Cursor};
+    use std::rc::Rc;
+    use term::terminfo::TermInfo;
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+    impl SharedBuffer {
+        fn take_string(&self) -> String {
+            String::from_utf8(std::mem::take(&mut *self.0.borrow_mut())).unwrap()
+        }
+    }
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn term_info() -> TermInfo {
+        let mut strings = HashMap::new();
+        for n in 1..=12 {
+            let name: &'static str = format!("kf{}", n).leak();
+            strings.insert(name, format!("\x1B[{}~", n + 10).into_bytes());
+        }
+        for (name, value) in [
+            ("setaf", "\x1B[38;5;%p1%dm"),
+            ("setab", "\x1B[48;5;%p1%dm"),
+            ("kcuu1", "\x1BOA"),
+            ("kcud1", "\x1BOB"),
+            ("kcuf1", "\x1BOC"),
+            ("kcub1", "\x1BOD"),
+            ("kpp", "\x1B[5~"),
+            ("knp", "\x1B[6~"),
+            ("khome", "\x1BOH"),
+            ("kend", "\x1BOF"),
+            ("kdch1", "\x1B[3~"),
+            ("cnorm", "\x1B[?25h"),
+            ("civis", "\x1B[?25l"),
+            ("sgr0", "\x1B[m"),
+            ("clear", "\x1B[H\x1B[2J"),
+            ("cup", "\x1B[%i%p1%d;%p2%dH"),
+            ("bold", "\x1B[1m"),
+            ("sitm", "\x1B[3m"),
+            ("smul", "\x1B[4m"),
+            ("rmul", "\x1B[24m"),
+        ] {
+            strings.insert(name, value.as_bytes().to_vec());
+        }
+        TermInfo {
+            names: vec!["test".to_string()],
+            bools: HashMap::new(),
+            numbers: HashMap::new(),
+            strings,
+        }
+    }
+
+    #[test]
+    fn draw_frame_output() {
+        let output = SharedBuffer::default();
+        // answer the kitty keyboard query with just the device attributes (i.e. unsupported)
+        let input = Cursor::new(b"\x1B[?62c".to_vec());
+        let io = ReadWriteIo::new(input, output.clone(), || UCoord::new(3, 1));
+        let ti_cache = TermInfoCache::from_term_info(&term_info(), |_| None).unwrap();
+        let ansi = AnsiTerminal::from_io(Box::new(io), ti_cache, Viewport::Fullscreen).unwrap();
+        let mut terminal = Terminal::from_ansi(ansi).unwrap();
+        output.take_string();
+        let mut frame_buffer = FrameBuffer::new(UCoord::new(3, 1));
+        for (x, character) in ['a', 'b'].into_iter().enumerate() {
+            frame_buffer.set_cell(
+                ICoord::new(x as i32, 0),
+                0,
+                RenderCell::BLANK.with_character(character),
+            );
+        }
+        terminal
+            .draw_frame::<col_encode::XtermTrueColour>(&mut frame_buffer)
+            .unwrap();
+        let preamble = "\x1B[1;1H\x1B[m\x1B[24m\x1B[38;2;0;0;0m\x1B[48;2;0;0;0m";
+        assert_eq!(
+            output.take_string(),
+            format!("{}\x1B[38;2;255;255;255mab ", preamble)
+        );
+        // cells which haven't changed since the last frame aren't redrawn
+        frame_buffer.set_cell(ICoord::new(2, 0), 0, RenderCell::BLANK.with_character('c'));
+        terminal
+            .draw_frame::<col_encode::XtermTrueColour>(&mut frame_buffer)
+            .unwrap();
+        assert_eq!(
+            output.take_string(),
+            format!("{}\x1B[38;2;255;255;255m\x1B[1;3Hc", preamble)
+        );
+        // nothing is sent if nothing changed
+        terminal
+            .draw_frame::<col_encode::XtermTrueColour>(&mut frame_buffer)
+            .unwrap();
+        assert_eq!(output.take_string(), "");
+        frame_buffer.set_cursor(chargrid_core::Cursor::new(
+            ICoord::new(1, 0),
+            CursorShape::Bar,
+        ));
+        terminal
+            .draw_frame::<col_encode::XtermTrueColour>(&mut frame_buffer)
+            .unwrap();
+        assert_eq!(output.take_string(), "\x1B[1;2H\x1B[6 q\x1B[?25h");
+    }
+
+    #[test]
+    fn draw_frame_attributes() {
+        let output = SharedBuffer::default();
+        let input = Cursor::new(b"\x1B[?62c".to_vec());
+        let io = ReadWriteIo::new(input, output.clone(), || UCoord::new(3, 1));
+        let ti_cache = TermInfoCache::from_term_info(&term_info(), |_| None).unwrap();
+        let ansi = AnsiTerminal::from_io(Box::new(io), ti_cache, Viewport::Fullscreen).unwrap();
+        let mut terminal = Terminal::from_ansi(ansi).unwrap();
+        output.take_string();
+        let mut frame_buffer = FrameBuffer::new(UCoord::new(3, 1));
+        let cells = [
+            RenderCell::BLANK.with_character('a').with_italic(true),
+            RenderCell::BLANK
+                .with_character('b')
+                .with_italic(true)
+                .with_underline(true)
+                .with_underline_style(UnderlineStyle::Curly),
+            RenderCell::BLANK
+                .with_character('c')
+                .with_strikethrough(true),
+        ];
+        for (x, render_cell) in cells.into_iter().enumerate() {
+            frame_buffer.set_cell(ICoord::new(x as i32, 0), 0, render_cell);
+        }
+        terminal
+            .draw_frame::<col_encode::XtermTrueColour>(&mut frame_buffer)
+            .unwrap();
+        let preamble = "\x1B[1;1H\x1B[m\x1B[24m\x1B[38;2;0;0;0m\x1B[48;2;0;0;0m";
+        let white = "\x1B[38;2;255;255;255m";
+        let black = "\x1B[48;2;0;0;0m";
+        // attributes are added to those already set where possible, but turning attributes off
+        // requires a reset
+        assert_eq!(
+            output.take_string(),
+            format!("{preamble}\x1B[3m{white}a\x1B[4m\x1B[4:3mb\x1B[m\x1B[9m{white}{black}c")
+        );
+    }
+
+    #[test]
+    fn draw_frame_dithered() {
+        let output = SharedBuffer::default();
+        let input = Cursor::new(b"\x1B[?62c".to_vec());
+        let io = ReadWriteIo::new(input, output.clone(), || UCoord::new(4, 1));
+        let ti_cache = TermInfoCache::from_term_info(&term_info(), |_| None).unwrap();
+        let ansi = AnsiTerminal::from_io(Box::new(io), ti_cache, Viewport::Fullscreen).unwrap();
+        let mut terminal = Terminal::from_ansi(ansi).unwrap();
+        output.take_string();
+        let mut frame_buffer = FrameBuffer::new(UCoord::new(4, 1));
+        for x in 0..4 {
+            frame_buffer.set_cell(
+                ICoord::new(x, 0),
+                0,
+                RenderCell::BLANK
+                    .with_character('a')
+                    .with_foreground(Rgba32::new_rgb(195, 0, 0)),
+            );
+        }
+        terminal
+            .draw_frame::<col_encode::Dithered<col_encode::FromTermInfoRgb>>(&mut frame_buffer)
+            .unwrap();
+        // the foreground lies between two levels of the colour cube, so alternates between them,
+        // but the black background is in the palette so is neither dithered nor sent again
+        assert_eq!(
+            output.take_string(),
+            "\x1B[1;1H\x1B[m\x1B[24m\x1B[38;5;16m\x1B[48;5;16m\x1B[38;5;124ma\x1B[38;5;160ma\x1B[38;5;124ma\x1B[38;5;160ma"
+        );
+    }
+
+    #[test]
+    fn inline_viewport_reanchored_on_resize() {
+        let output = SharedBuffer::default();
+        // the viewport starts on the fifth row, and after the resize the cursor (which is left
+        // at the start of the viewport) is on the second row
+        let input = Cursor::new(b"\x1B[5;1R\x1B[?62c\x1B[2;1R".to_vec());
+        let terminal_size = Rc::new(std::cell::Cell::new(UCoord::new(4, 10)));
+        let io = ReadWriteIo::new(input, output.clone(), {
+            let terminal_size = terminal_size.clone();
+            move || terminal_size.get()
+        });
+        let ti_cache = TermInfoCache::from_term_info(&term_info(), |_| None).unwrap();
+        let ansi =
+            AnsiTerminal::from_io(Box::new(io), ti_cache, Viewport::Inline { height: 2 }).unwrap();
+        let mut terminal = Terminal::from_ansi(ansi).unwrap();
+        let mut frame_buffer = FrameBuffer::new(terminal.resize_if_necessary().unwrap());
+        frame_buffer.set_cell(ICoord::new(0, 1), 0, RenderCell::BLANK.with_character('a'));
+        terminal
+            .draw_frame::<col_encode::XtermTrueColour>(&mut frame_buffer)
+            .unwrap();
+        output.take_string();
+        // reflowing the contents of the terminal moved the viewport
+        terminal_size.set(UCoord::new(2, 10));
+        let size = terminal.resize_if_necessary().unwrap();
+        assert_eq!(size, UCoord::new(2, 2));
+        assert_eq!(output.take_string(), cursor_position::QUERY);
+        frame_buffer.resize(size);
+        frame_buffer.set_cell(ICoord::new(0, 1), 0, RenderCell::BLANK.with_character('a'));
+        terminal
+            .draw_frame::<col_encode::XtermTrueColour>(&mut frame_buffer)
+            .unwrap();
+        assert!(output.take_string().starts_with("\x1B[2;1H"));
+        // with no answer to the query, the viewport is moved up to fit in the terminal
+        terminal_size.set(UCoord::new(2, 2));
+        assert_eq!(terminal.resize_if_necessary().unwrap(), UCoord::new(2, 2));
+        output.take_string();
+        terminal
+            .draw_frame::<col_encode::XtermTrueColour>(&mut frame_buffer)
+            .unwrap();
+        assert!(output.take_string().starts_with("\x1B[1;1H"));
+    }
+}
@@ -0,0 +1,283 @@
+//! Restoration of the terminal when the process exits without the terminal being dropped, i.e.
+//! when a component panics or the process receives a signal which would terminate it. Without
+//! this, the user is left with a terminal in raw mode with mouse reporting enabled and the cursor
+//! hidden. Restoring the terminal from a signal handler is limited to async-signal-safe
+//! operations, so the escape sequences to send are prepared in advance.
+
+use std::os::unix::io::RawFd;
+use std::panic;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{Mutex, Once};
+use std::thread::{self, ThreadId};
+
+/// Signals whose default action terminates the process
+const SIGNALS: [libc::c_int; 4] = [libc::SIGHUP, libc::SIGINT, libc::SIGQUIT, libc::SIGTERM];
+
+/// Everything needed to restore the terminal, prepared in advance
+struct State {
+    fd: RawFd,
+    original_termios: libc::termios,
+    teardown: Vec<u8>,
+}
+
+/// Whoever swaps a non-null pointer out of here is responsible for the state it points to
+static STATE: AtomicPtr<State> = AtomicPtr::new(ptr::null_mut());
+
+static INSTALL_PANIC_HOOK: Once = Once::new();
+
+/// The thread which created the `RestoreOnAbnormalExit`. Panics in other threads leave the
+/// terminal alone, since they don't stop the owning thread from using it.
+static OWNER_THREAD: Mutex<Option<ThreadId>> = Mutex::new(None);
+
+/// The handler which an application installed for each of `SIGNALS` before the terminal was
+/// initialised (a `sa_sigaction`), or `SIG_DFL` if there wasn't one
+static ORIGINAL_HANDLERS: [AtomicUsize; SIGNALS.len()] =
+    [const { AtomicUsize::new(libc::SIG_DFL) }; SIGNALS.len()];
+
+/// Whether each of `ORIGINAL_HANDLERS` takes the extra arguments passed to handlers installed
+/// with `SA_SIGINFO`
+static ORIGINAL_HANDLERS_SIGINFO: [AtomicBool; SIGNALS.len()] =
+    [const { AtomicBool::new(false) }; SIGNALS.len()];
+
+fn take_state() -> Option<Box<State>> {
+    let state = STATE.swap(ptr::null_mut(), Ordering::SeqCst);
+    if state.is_null() {
+        None
+    } else {
+        Some(unsafe { Box::from_raw(state) })
+    }
+}
+
+/// Send the teardown sequences and restore the original terminal settings. Only uses
+/// async-signal-safe functions.
+fn restore(state: &State) {
+    let mut remaining = state.teardown.as_slice();
+    while !remaining.is_empty() {
+        let res = unsafe {
+            libc::write(
+                state.fd,
+                remaining.as_ptr() as *const libc::c_void,
+                remaining.len(),
+            )
+        };
+        if res <= 0 {
+            break;
+        }
+        remaining = &remaining[res as usize..];
+    }
+    unsafe { libc::tcsetattr(state.fd, libc::TCSAFLUSH, &state.original_termios) };
+}
+
+extern "C" fn handle_signal(
+    signal: libc::c_int,
+    info: *mut libc::siginfo_t,
+    context: *mut libc::c_void,
+) {
+    if let Some(index) = SIGNALS.iter().position(|&s| s == signal) {
+        let handler = ORIGINAL_HANDLERS[index].load(Ordering::SeqCst);
+        if handler != libc::SIG_DFL {
+            // the application handles this signal itself, and is responsible for restoring the
+            // terminal (with `restore_terminal`) if it decides to exit
+            unsafe {
+                if ORIGINAL_HANDLERS_SIGINFO[index].load(Ordering::SeqCst) {
+                    let handler: extern "C" fn(
+                        libc::c_int,
+                        *mut libc::siginfo_t,
+                        *mut libc::c_void,
+                    ) = std::mem::transmute(handler);
+                    handler(signal, info, context);
+                } else {
+                    let handler: extern "C" fn(libc::c_int) = std::mem::transmute(handler);
+                    handler(signal);
+                }
+            }
+            return;
+        }
+    }
+    if let Some(state) = take_state() {
+        restore(&state);
+        // freeing memory isn't async-signal-safe, and the process is about to exit anyway
+        std::mem::forget(state);
+    }
+    // terminate the process in the way the signal would have if it wasn't handled
+    unsafe {
+        libc::signal(signal, libc::SIG_DFL);
+        libc::raise(signal);
+    }
+}
+
+/// Restore the terminal to the state it was in before it was initialised, for applications which
+/// handle signals such as SIGINT themselves and decide to exit from their handler (signals which
+/// an application handles are passed on to its handler without restoring the terminal). Only
+/// uses async-signal-safe functions, so may be called from a signal handler. Returns false if
+/// there was no terminal to restore. The terminal shouldn't be drawn to after calling this.
+pub fn restore_terminal() -> bool {
+    match take_state() {
+        Some(state) => {
+            restore(&state);
+            // freeing memory isn't async-signal-safe
+            std::mem::forget(state);
+            true
+        }
+        None => false,
+    }
+}
+
+fn is_owner_thread() -> bool {
+    let owner_thread = OWNER_THREAD
+        .lock()
+        .unwrap_or_else(|error| error.into_inner());
+    *owner_thread == Some(thread::current().id())
+}
+
+/// While this exists, the terminal is restored if the thread which created it panics or the
+/// process is terminated by a signal. Signals which the application already handles are passed on
+/// to its handlers instead (which can restore the terminal with `restore_terminal`), and signals
+/// which were being ignored still are. Only one terminal can be restored in this way at a time.
+/// Dropping this restores the original signal handlers (but not the panic hook, which does
+/// nothing while there is no terminal to restore).
+pub struct RestoreOnAbnormalExit {
+    fd: RawFd,
+    original_termios: libc::termios,
+    original_actions: Vec<(libc::c_int, libc::sigaction)>,
+    teardown: String,
+}
+
+impl RestoreOnAbnormalExit {
+    pub fn new(fd: RawFd, original_termios: libc::termios) -> Self {
+        INSTALL_PANIC_HOOK.call_once(|| {
+            let default_hook = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                // restore the terminal before the panic message is printed so it's legible
+                if is_owner_thread()
+                    && let Some(state) = take_state()
+                {
+                    restore(&state);
+                }
+                default_hook(info);
+            }));
+        });
+        *OWNER_THREAD
+            .lock()
+            .unwrap_or_else(|error| error.into_inner()) = Some(thread::current().id());
+        let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+        action.sa_sigaction = handle_signal as *const () as libc::sighandler_t;
+        action.sa_flags = libc::SA_SIGINFO;
+        let mut original_actions = Vec::new();
+        for (index, &signal) in SIGNALS.iter().enumerate() {
+            let mut original_action: libc::sigaction = unsafe { std::mem::zeroed() };
+            if unsafe { libc::sigaction(signal, ptr::null(), &mut original_action) } != 0
+                || original_action.sa_sigaction == libc::SIG_IGN
+            {
+                continue;
+            }
+            ORIGINAL_HANDLERS[index].store(original_action.sa_sigaction, Ordering::SeqCst);
+            ORIGINAL_HANDLERS_SIGINFO[index].store(
+                original_action.sa_flags & libc::SA_SIGINFO != 0,
+                Ordering::SeqCst,
+            );
+            if unsafe { libc::sigaction(signal, &action, ptr::null_mut()) } == 0 {
+                original_actions.push((signal, original_action));
+            }
+        }
+        let restore_on_abnormal_exit = Self {
+            fd,
+            original_termios,
+            original_actions,
+            teardown: String::new(),
+        };
+        restore_on_abnormal_exit.arm();
+        restore_on_abnormal_exit
+    }
+
+    /// Set the escape sequences to send to the terminal when restoring it
+    pub fn set_teardown(&mut self, teardown: &str) {
+        self.teardown = teardown.to_string();
+        self.arm();
+    }
+
+    /// Restore the terminal on abnormal exit (again, if it was already restored due to a panic
+    /// which turned out to be caught)
+    pub fn arm(&self) {
+        let state = Box::into_raw(Box::new(State {
+            fd: self.fd,
+            original_termios: self.original_termios,
+            teardown: self.teardown.as_bytes().to_vec(),
+        }));
+        let previous_state = STATE.swap(state, Ordering::SeqCst);
+        if !previous_state.is_null() {
+            drop(unsafe { Box::from_raw(previous_state) });
+        }
+    }
+
+    /// Returns true if the terminal has already been restored due to a panic
+    pub fn restored(&self) -> bool {
+        STATE.load(Ordering::SeqCst).is_null()
+    }
+}
+
+impl Drop for RestoreOnAbnormalExit {
+    fn drop(&mut self) {
+        for (signal, original_action) in self.original_actions.iter() {
+            unsafe { libc::sigaction(*signal, original_action, ptr::null_mut()) };
+        }
+        for handler in ORIGINAL_HANDLERS.iter() {
+            handler.store(libc::SIG_DFL, Ordering::SeqCst);
+        }
+        *OWNER_THREAD
+            .lock()
+            .unwrap_or_else(|error| error.into_inner()) = None;
+        take_state();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs::File;
+    use std::io::Read;
+    use std::os::unix::io::FromRawFd;
+
+    static APP_HANDLER_CALLED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn app_handler(_signal: libc::c_int) {
+        APP_HANDLER_CALLED.store(true, Ordering::SeqCst);
+        restore_terminal();
+    }
+
+    #[test]
+    fn restore_on_panic() {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let [read_fd, write_fd] = fds;
+        let termios = unsafe { std::mem::zeroed() };
+        unsafe { libc::signal(libc::SIGHUP, app_handler as *const () as libc::sighandler_t) };
+        let mut restore_on_abnormal_exit = RestoreOnAbnormalExit::new(write_fd, termios);
+        restore_on_abnormal_exit.set_teardown("teardown");
+        assert!(!restore_on_abnormal_exit.restored());
+        // panics in other threads don't affect the terminal
+        assert!(thread::spawn(|| panic!("test panic")).join().is_err());
+        assert!(!restore_on_abnormal_exit.restored());
+        assert!(std::panic::catch_unwind(|| panic!("test panic")).is_err());
+        assert!(restore_on_abnormal_exit.restored());
+        restore_on_abnormal_exit.arm();
+        assert!(!restore_on_abnormal_exit.restored());
+        // signals handled by the application are passed on to its handler, which restores the
+        // terminal itself
+        unsafe { libc::raise(libc::SIGHUP) };
+        assert!(APP_HANDLER_CALLED.load(Ordering::SeqCst));
+        assert!(restore_on_abnormal_exit.restored());
+        assert!(!restore_terminal());
+        drop(restore_on_abnormal_exit);
+        unsafe {
+            libc::signal(libc::SIGHUP, libc::SIG_DFL);
+            libc::close(write_fd);
+        }
+        let mut output = String::new();
+        unsafe { File::from_raw_fd(read_fd) }
+            .read_to_string(&mut output)
+            .unwrap();
+        assert_eq!(output, "teardownteardown");
+    }
+}
@@ -0,0 +1,145 @@
+//! Parser for mouse events in the SGR (1006) encoding, of the form "ESC [ < button ; x ; y M"
+//! for presses and motion, and "ESC [ < button ; x ; y m" for releases. Unlike the legacy
+//! encoding, coordinates are written in decimal so aren't limited to 223 columns, and releases
+//! report which button was released.
+
+use chargrid_input::{Input, MouseButton, MouseInput, ScrollDirection};
+use chargrid_runtime::ICoord;
+
+const PREFIX: &[u8] = b"\x1B[<";
+
+// bits of the button parameter other than the button itself
+const MOTION: u32 = 32;
+const WHEEL: u32 = 64;
+// shift, alt, and ctrl are reported in bits 4, 8, and 16, but mouse inputs don't have modifiers
+const BUTTON_MASK: u32 = 3;
+
+fn button(code: u32) -> Option<MouseButton> {
+    match code & BUTTON_MASK {
+        0 => Some(MouseButton::Left),
+        1 => Some(MouseButton::Middle),
+        2 => Some(MouseButton::Right),
+        _ => None,
+    }
+}
+
+fn scroll_direction(code: u32) -> ScrollDirection {
+    match code & BUTTON_MASK {
+        0 => ScrollDirection::Up,
+        1 => ScrollDirection::Down,
+        2 => ScrollDirection::Left,
+        _ => ScrollDirection::Right,
+    }
+}
+
+/// If `bytes` begins with a mouse sequence, returns the input it describes (or `None` if the
+/// sequence is malformed) along with the bytes following the sequence. Returns `None` if `bytes`
+/// doesn't begin with a complete mouse sequence.
+pub fn parse(bytes: &[u8]) -> Option<(Option<Input>, &[u8])> {
+    let after_prefix = bytes.strip_prefix(PREFIX)?;
+    let params_len = after_prefix
+        .iter()
+        .position(|&b| !(b.is_ascii_digit() || b == b';'))?;
+    let (params, rest) = after_prefix.split_at(params_len);
+    let (&final_byte, rest) = rest.split_first()?;
+    if final_byte != b'M' && final_byte != b'm' {
+        return None;
+    }
+    let params = std::str::from_utf8(params)
+        .ok()?
+        .split(';')
+        .map(|param| param.parse::<u32>().ok())
+        .collect::<Option<Vec<_>>>();
+    let input = match params.as_deref() {
+        Some(&[code, x, y]) if x > 0 && y > 0 => {
+            let coord = ICoord::new(x as i32 - 1, y as i32 - 1);
+            let mouse_input = if code & WHEEL != 0 {
+                Some(MouseInput::MouseScroll {
+                    direction: scroll_direction(code),
+                    coord,
+                })
+            } else if code & MOTION != 0 {
+                Some(MouseInput::MouseMove {
+                    button: button(code),
+                    coord,
+                })
+            } else if final_byte == b'm' {
+                button(code).map(|button| MouseInput::MouseRelease {
+                    button: Ok(button),
+                    coord,
+                })
+            } else {
+                button(code).map(|button| MouseInput::MousePress { button, coord })
+            };
+            mouse_input.map(Input::Mouse)
+        }
+        _ => None,
+    };
+    Some((input, rest))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn mouse(mouse_input: MouseInput) -> Option<Input> {
+        Some(Input::Mouse(mouse_input))
+    }
+
+    #[test]
+    fn mouse_sequences() {
+        assert_eq!(
+            parse(b"\x1B[<0;300;2Mrest"),
+            Some((
+                mouse(MouseInput::MousePress {
+                    button: MouseButton::Left,
+                    coord: ICoord::new(299, 1),
+                }),
+                &b"rest"[..]
+            ))
+        );
+        assert_eq!(
+            parse(b"\x1B[<2;1;1m"),
+            Some((
+                mouse(MouseInput::MouseRelease {
+                    button: Ok(MouseButton::Right),
+                    coord: ICoord::new(0, 0),
+                }),
+                &b""[..]
+            ))
+        );
+        assert_eq!(
+            parse(b"\x1B[<33;5;6M"),
+            Some((
+                mouse(MouseInput::MouseMove {
+                    button: Some(MouseButton::Middle),
+                    coord: ICoord::new(4, 5),
+                }),
+                &b""[..]
+            ))
+        );
+        assert_eq!(
+            parse(b"\x1B[<35;5;6M"),
+            Some((
+                mouse(MouseInput::MouseMove {
+                    button: None,
+                    coord: ICoord::new(4, 5),
+                }),
+                &b""[..]
+            ))
+        );
+        assert_eq!(
+            parse(b"\x1B[<81;5;6M"),
+            Some((
+                mouse(MouseInput::MouseScroll {
+                    direction: ScrollDirection::Down,
+                    coord: ICoord::new(4, 5),
+                }),
+                &b""[..]
+            ))
+        );
+        assert_eq!(parse(b"\x1B[<0;5M"), Some((None, &b""[..])));
+        assert_eq!(parse(b"\x1B[<0;5;6"), None);
+        assert_eq!(parse(b"\x1B[M !!"), None);
+    }
+}
@@ -1,18 +1,35 @@
 use super::byte_prefix_tree::BytePrefixTree;
+use super::colour_support::ColourSupport;
 use crate::error::{Error, Result};
-use chargrid_input::{Input, KeyboardInput, MouseButton, ScrollDirection};
+use chargrid_input::{
+    Input, KeyEvent, KeyModifiers, KeyboardInput, MouseButton, ScrollDirection, keys,
+};
+use chargrid_runtime::Lifecycle;
+use term::Error as TermError;
 use term::terminfo::TermInfo;
 use term::terminfo::parm::{self, Param, Variables};
 
 // XXX this might not be portable
 const ESCAPE: &[u8] = &[27];
-const ENABLE_MOUSE_REPORTING: &str = "[?1003h";
-const DISABLE_MOUSE_REPORTING: &str = "[?1003l";
+// report all mouse motion, using the SGR encoding where supported (see `sgr_mouse`)
+const ENABLE_MOUSE_REPORTING: &str = "[?1003;1006h";
+const DISABLE_MOUSE_REPORTING: &str = "[?1003;1006l";
+const ENABLE_FOCUS_REPORTING: &str = "[?1004h";
+const DISABLE_FOCUS_REPORTING: &str = "[?1004l";
+// The capabilities for strikethrough (smxx), styled underlines (Smulx) and underline colours
+// (Setulc) are extensions to terminfo which the terminfo parser doesn't read, so the sequences
+// understood by the terminals which support them are used directly. Terminals which don't support
+// them ignore them.
+const STRIKETHROUGH: &str = "[9m";
+const DOUBLE_UNDERLINE: &str = "[4:2m";
+const CURLY_UNDERLINE: &str = "[4:3m";
+const DOTTED_UNDERLINE: &str = "[4:4m";
+const DASHED_UNDERLINE: &str = "[4:5m";
 
 #[derive(Debug, Clone, Copy)]
 pub enum MousePrefix {
     Press(MouseButton),
-    // ansi terminals don't report which button was released
+    // the legacy mouse encoding doesn't report which button was released
     Release,
     // ansi terminals only report the last button pressed when reporting a mouse drag
     Move(Option<MouseButton>),
@@ -21,9 +38,64 @@ pub enum MousePrefix {
 
 #[derive(Debug, Clone, Copy)]
 pub enum TerminalInput {
-    Char(char),
     Literal(Input),
     MousePrefix(MousePrefix),
+    Lifecycle(Lifecycle),
+}
+
+/// Decode the modifier parameter of an xterm-style key sequence (e.g. the 5 in "ESC[1;5A")
+pub fn csi_modifiers(param: u32) -> KeyModifiers {
+    let bits = param.saturating_sub(1);
+    KeyModifiers::NONE
+        .with_shift_if(bits & 1 != 0)
+        .with_alt_if(bits & 2 != 0)
+        .with_ctrl_if(bits & 4 != 0)
+        .with_super_if(bits & 8 != 0)
+}
+
+/// Escape sequences sent by xterm-compatible terminals for keys pressed while holding modifiers.
+/// These aren't described by terminfo.
+pub fn modified_key_sequences() -> Vec<(Vec<u8>, TerminalInput)> {
+    let letter_keys = [
+        (b'A', KeyboardInput::Up),
+        (b'B', KeyboardInput::Down),
+        (b'C', KeyboardInput::Right),
+        (b'D', KeyboardInput::Left),
+        (b'H', KeyboardInput::Home),
+        (b'F', KeyboardInput::End),
+        (b'P', KeyboardInput::Function(1)),
+        (b'Q', KeyboardInput::Function(2)),
+        (b'R', KeyboardInput::Function(3)),
+        (b'S', KeyboardInput::Function(4)),
+    ];
+    let tilde_keys = [
+        (3, KeyboardInput::Delete),
+        (5, KeyboardInput::PageUp),
+        (6, KeyboardInput::PageDown),
+        (15, KeyboardInput::Function(5)),
+        (17, KeyboardInput::Function(6)),
+        (18, KeyboardInput::Function(7)),
+        (19, KeyboardInput::Function(8)),
+        (20, KeyboardInput::Function(9)),
+        (21, KeyboardInput::Function(10)),
+        (23, KeyboardInput::Function(11)),
+        (24, KeyboardInput::Function(12)),
+    ];
+    let input = |key, modifiers| TerminalInput::Literal(KeyEvent::new(key, modifiers).input());
+    let mut sequences = vec![(b"\x1B[Z".to_vec(), input(keys::TAB, KeyModifiers::SHIFT))];
+    for modifier_param in 2..=16 {
+        let modifiers = csi_modifiers(modifier_param);
+        for &(letter, key) in letter_keys.iter() {
+            let mut seq = format!("\x1B[1;{}", modifier_param).into_bytes();
+            seq.push(letter);
+            sequences.push((seq, input(key, modifiers)));
+        }
+        for &(number, key) in tilde_keys.iter() {
+            let seq = format!("\x1B[{};{}~", number, modifier_param).into_bytes();
+            sequences.push((seq, input(key, modifiers)));
+        }
+    }
+    sequences
 }
 
 pub struct TermInfoCache {
@@ -37,12 +109,25 @@ pub struct TermInfoCache {
     pub reset: String,
     pub set_cursor: String,
     pub bold: String,
+    pub dim: Option<String>,
+    pub italic: Option<String>,
     pub underline: String,
+    pub double_underline: String,
+    pub curly_underline: String,
+    pub dotted_underline: String,
+    pub dashed_underline: String,
     pub no_underline: String,
+    pub blink: Option<String>,
+    pub reverse: Option<String>,
+    pub strikethrough: String,
     pub enable_mouse_reporting: String,
     pub disable_mouse_reporting: String,
+    pub enable_focus_reporting: String,
+    pub disable_focus_reporting: String,
     pub fg_colours: Vec<String>,
     pub bg_colours: Vec<String>,
+    /// Guessed from the environment and terminfo, and possibly refined by querying the terminal
+    pub colour_support: ColourSupport,
     pub vars: Variables,
     pub escape_sequence_prefix_tree: BytePrefixTree<TerminalInput>,
 }
@@ -56,7 +141,25 @@ fn raw_cap(seq: &str) -> Result<String> {
 
 impl TermInfoCache {
     pub fn new() -> Result<Self> {
-        let term_info = TermInfo::from_env()?;
+        Self::from_term_info(&TermInfo::from_env()?, |name| std::env::var(name).ok())
+    }
+
+    /// Describe the terminal named by `TERM` in an environment other than that of the process,
+    /// whose variables are looked up with `env`
+    pub fn from_environment<F>(env: F) -> Result<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let term = env("TERM").ok_or(TermError::TermUnset)?;
+        Self::from_term_info(&TermInfo::from_name(&term)?, env)
+    }
+
+    /// Describe the terminal with the given terminfo entry, in an environment whose variables
+    /// are looked up with `env`
+    pub fn from_term_info<F>(term_info: &TermInfo, env: F) -> Result<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
         let cap = |name: &'static str| {
             term_info
                 .strings
@@ -163,11 +266,16 @@ impl TermInfoCache {
                 "[Mc",
                 TerminalInput::MousePrefix(MousePrefix::Scroll(ScrollDirection::Right)),
             ),
+            raw_escseq("[I", TerminalInput::Lifecycle(Lifecycle::FocusGained)),
+            raw_escseq("[O", TerminalInput::Lifecycle(Lifecycle::FocusLost)),
         ];
         let mut escape_sequence_prefix_tree = BytePrefixTree::new();
         for &(ref seq, input) in inputs_to_escape.iter() {
             escape_sequence_prefix_tree.insert(seq, input);
         }
+        for (seq, input) in modified_key_sequences() {
+            escape_sequence_prefix_tree.insert(&seq, input);
+        }
         Ok(Self {
             enter_ca: cap("smcup").ok(),
             exit_ca: cap("rmcup").ok(),
@@ -179,12 +287,27 @@ impl TermInfoCache {
             clear: cap("clear")?,
             set_cursor: cap("cup")?,
             bold: cap("bold")?,
+            dim: cap("dim").ok(),
+            italic: cap("sitm").ok(),
             underline: cap("smul")?,
+            double_underline: raw_cap(DOUBLE_UNDERLINE)?,
+            curly_underline: raw_cap(CURLY_UNDERLINE)?,
+            dotted_underline: raw_cap(DOTTED_UNDERLINE)?,
+            dashed_underline: raw_cap(DASHED_UNDERLINE)?,
             no_underline: cap("rmul")?,
+            blink: cap("blink").ok(),
+            reverse: cap("rev").ok(),
+            strikethrough: raw_cap(STRIKETHROUGH)?,
             enable_mouse_reporting: raw_cap(ENABLE_MOUSE_REPORTING)?,
             disable_mouse_reporting: raw_cap(DISABLE_MOUSE_REPORTING)?,
+            enable_focus_reporting: raw_cap(ENABLE_FOCUS_REPORTING)?,
+            disable_focus_reporting: raw_cap(DISABLE_FOCUS_REPORTING)?,
             fg_colours,
             bg_colours,
+            colour_support: ColourSupport::from_environment(
+                env,
+                term_info.numbers.get("colors").copied(),
+            ),
             vars,
             escape_sequence_prefix_tree,
         })
@@ -20,4 +20,8 @@ impl<C: Component> Component for AddOffset<C> {
         let ctx = ctx.add_offset(self.offset);
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        let ctx = ctx.add_offset(self.offset);
+        self.component.size_constraints(state, ctx)
+    }
 }
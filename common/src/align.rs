@@ -71,4 +71,7 @@ impl<C: Component> Component for Align<C> {
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, self.child_ctx(state, ctx))
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
 }
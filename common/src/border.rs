@@ -144,12 +144,13 @@ impl BorderStyle {
 
     fn render_cell(&self, character: char) -> RenderCell {
         RenderCell {
-            character: Some(character),
+            character: Some(Grapheme::from_char(character)),
             style: Style {
                 foreground: Some(self.foreground),
                 background: self.background,
                 bold: Some(self.bold),
                 underline: Some(false),
+                ..Default::default()
             },
         }
     }
@@ -185,23 +186,25 @@ impl BorderStyle {
             self.render_cell(self.chars.bottom_right),
         );
         let title_offset = if let Some(title) = self.title.as_ref() {
+            let title_width = graphemes(title).map(|g| g.width()).sum::<u32>() as i32;
             let before = ICoord::new(1, 0);
-            let after = ICoord::new(title.len() as i32 + 2, 0);
+            let after = ICoord::new(title_width + 2, 0);
             fb.set_cell_relative_to_ctx(ctx, before, 0, self.render_cell(self.chars.before_title));
             fb.set_cell_relative_to_ctx(ctx, after, 0, self.render_cell(self.chars.after_title));
-            for (index, ch) in title.chars().enumerate() {
-                let coord = ICoord::new(index as i32 + 2, 0);
+            let mut coord = ICoord::new(2, 0);
+            for grapheme in graphemes(title) {
                 fb.set_cell_relative_to_ctx(
                     ctx,
                     coord,
                     0,
                     RenderCell {
                         style: self.title_style,
-                        character: Some(ch),
+                        character: Some(grapheme),
                     },
                 );
+                coord.x += grapheme.width() as i32;
             }
-            title.len() as i32 + 2
+            title_width + 2
         } else {
             0
         };
@@ -266,4 +269,12 @@ impl<C: Component> Component for Border<C> {
             + self.style.child_offset_top_left().to_ucoord()
             + self.style.child_offset_bottom_right().to_ucoord()
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component
+            .size_constraints(state, self.style.inner_ctx(ctx))
+            .add_size(
+                self.style.child_offset_top_left().to_ucoord()
+                    + self.style.child_offset_bottom_right().to_ucoord(),
+            )
+    }
 }
@@ -20,6 +20,11 @@ impl<C: Component> Component for BoundSize<C> {
         let child_size = self.component.size(state, ctx.set_size(self.size));
         child_size.pairwise_min(self.size)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component
+            .size_constraints(state, ctx.set_size(self.size))
+            .at_most(self.size)
+    }
 }
 
 pub struct BoundWidth<C: Component> {
@@ -42,6 +47,15 @@ impl<C: Component> Component for BoundWidth<C> {
         let child_size = self.component.size(state, ctx.set_width(self.width));
         child_size.set_width(child_size.width().min(self.width))
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        let size_constraints = self
+            .component
+            .size_constraints(state, ctx.set_width(self.width));
+        SizeConstraints {
+            width: size_constraints.width.at_most(self.width),
+            ..size_constraints
+        }
+    }
 }
 
 pub struct BoundHeight<C: Component> {
@@ -64,4 +78,13 @@ impl<C: Component> Component for BoundHeight<C> {
         let child_size = self.component.size(state, ctx.set_height(self.height));
         child_size.set_height(child_size.height().min(self.height))
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        let size_constraints = self
+            .component
+            .size_constraints(state, ctx.set_height(self.height));
+        SizeConstraints {
+            height: size_constraints.height.at_most(self.height),
+            ..size_constraints
+        }
+    }
 }
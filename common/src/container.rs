@@ -0,0 +1,95 @@
+//! Definitions shared by components which lay out several child components
+//!
+//! Containers pass mouse events only to the child under the mouse, and all other events to every
+//! child. The output of a container is that of the first child to yield anything (see
+//! `ContainerOutput`).
+
+use chargrid_core::{Constraint, ICoord, SizeConstraints, UCoord};
+
+/// The output of a child of a container. Containers pass events to several of their children,
+/// and yield the first output of a child which isn't empty. Components which don't yield anything
+/// have an output of `()` and components which notionally yield a single value have an output of
+/// `Option<_>`, so containers can hold either.
+pub trait ContainerOutput {
+    /// The output of a container none of whose children yielded anything
+    fn empty() -> Self;
+
+    fn is_empty(&self) -> bool;
+
+    /// Returns `self` unless it's empty, in which case returns `other`
+    fn or_output(self, other: Self) -> Self
+    where
+        Self: Sized,
+    {
+        if self.is_empty() { other } else { self }
+    }
+}
+
+impl ContainerOutput for () {
+    fn empty() -> Self {}
+
+    fn is_empty(&self) -> bool {
+        true
+    }
+}
+
+impl<T> ContainerOutput for Option<T> {
+    fn empty() -> Self {
+        None
+    }
+
+    fn is_empty(&self) -> bool {
+        self.is_none()
+    }
+}
+
+/// The axis along which the children of a container are placed
+#[derive(Clone, Copy)]
+pub(crate) enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+impl Axis {
+    pub(crate) fn main(self, size: UCoord) -> u32 {
+        match self {
+            Self::Horizontal => size.width(),
+            Self::Vertical => size.height(),
+        }
+    }
+
+    pub(crate) fn cross(self, size: UCoord) -> u32 {
+        match self {
+            Self::Horizontal => size.height(),
+            Self::Vertical => size.width(),
+        }
+    }
+
+    pub(crate) fn offset(self, main: u32, cross: u32) -> ICoord {
+        match self {
+            Self::Horizontal => ICoord::new(main as i32, cross as i32),
+            Self::Vertical => ICoord::new(cross as i32, main as i32),
+        }
+    }
+
+    pub(crate) fn size(self, main: u32, cross: u32) -> UCoord {
+        match self {
+            Self::Horizontal => UCoord::new(main, cross),
+            Self::Vertical => UCoord::new(cross, main),
+        }
+    }
+
+    pub(crate) fn main_constraint(self, size_constraints: SizeConstraints) -> Constraint {
+        match self {
+            Self::Horizontal => size_constraints.width,
+            Self::Vertical => size_constraints.height,
+        }
+    }
+
+    pub(crate) fn cross_constraint(self, size_constraints: SizeConstraints) -> Constraint {
+        match self {
+            Self::Horizontal => size_constraints.height,
+            Self::Vertical => size_constraints.width,
+        }
+    }
+}
@@ -1,7 +1,8 @@
 pub use crate::control_flow::LoopControl;
 use crate::{
-    align::Alignment,
+    align::{Alignment, AlignmentX, AlignmentY},
     border::BorderStyle,
+    container::ContainerOutput,
     control_flow::{
         Lens, OrClickOut, OrClose, OrEscape, OrEscapeOrClickOut, OrEscapeOrStart, unboxed,
     },
@@ -9,7 +10,8 @@ use crate::{
 };
 pub use chargrid_core::app;
 use chargrid_core::{
-    BoxedComponent, Component, Ctx, Event, FrameBuffer, ICoord, Rgba32, Style, Tint, UCoord, input,
+    BoxedComponent, Component, Constraint, Ctx, Event, FrameBuffer, ICoord, Rgba32,
+    SizeConstraints, Style, Tint, UCoord, input,
 };
 use std::time::Duration;
 
@@ -49,6 +51,9 @@ impl<O, S> Component for CF<O, S> {
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.0.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.0.size_constraints(state, ctx)
+    }
 }
 
 impl<O: 'static, S: 'static> CF<O, S> {
@@ -156,6 +161,19 @@ impl<O: 'static, S: 'static> CF<O, S> {
         self.0.add_y(y).boxed()
     }
 
+    /// Returns a new `CF` with identical behaviour to `self` but
+    /// moved by a specified offset, which may move it outside its
+    /// parent's bounding box
+    pub fn translate(self, offset: ICoord) -> Self {
+        self.0.translate(offset).boxed()
+    }
+
+    /// Returns a new `CF` with identical behaviour to `self` but
+    /// which isn't drawn outside its bounding box
+    pub fn clip(self) -> Self {
+        self.0.clip().boxed()
+    }
+
     /// Returns a new `CF` with identical behaviour to `self` but
     /// whose size has been overridden to a specific value
     pub fn set_size(self, size: UCoord) -> Self {
@@ -192,6 +210,25 @@ impl<O: 'static, S: 'static> CF<O, S> {
         self.0.bound_height(height).boxed()
     }
 
+    /// Returns a new `CF` with identical behaviour to `self` but
+    /// whose size constraints (used by layout containers such as
+    /// `flex_row`) have been overridden
+    pub fn constrain(self, size_constraints: SizeConstraints) -> Self {
+        self.0.constrain(size_constraints).boxed()
+    }
+
+    /// Returns a new `CF` with identical behaviour to `self` but
+    /// whose width constraint has been overridden
+    pub fn constrain_width(self, width: Constraint) -> Self {
+        self.0.constrain_width(width).boxed()
+    }
+
+    /// Returns a new `CF` with identical behaviour to `self` but
+    /// whose height constraint has been overridden
+    pub fn constrain_height(self, height: Constraint) -> Self {
+        self.0.constrain_height(height).boxed()
+    }
+
     /// Returns a new `CF` with identical behaviour to `self` but
     /// with an additional component rendered on top with a specified
     /// padding. Useful for adding title text to components
@@ -610,6 +647,62 @@ where
     unboxed::many(iterable).boxed()
 }
 
+/// A component which lays out the components in `iterable` from left to right with `spacing`
+/// columns between them, aligning each vertically within the height of the tallest
+pub fn hstack<I, S, C>(iterable: I, spacing: u32, alignment: AlignmentY) -> CF<C::Output, S>
+where
+    I: 'static,
+    S: 'static,
+    C: 'static + Component<State = S>,
+    C::Output: ContainerOutput,
+    for<'a> &'a I: IntoIterator<Item = &'a C>,
+    for<'a> &'a mut I: IntoIterator<Item = &'a mut C>,
+{
+    unboxed::hstack(iterable, spacing, alignment).boxed()
+}
+
+/// A component which lays out the components in `iterable` from top to bottom with `spacing`
+/// rows between them, aligning each horizontally within the width of the widest
+pub fn vstack<I, S, C>(iterable: I, spacing: u32, alignment: AlignmentX) -> CF<C::Output, S>
+where
+    I: 'static,
+    S: 'static,
+    C: 'static + Component<State = S>,
+    C::Output: ContainerOutput,
+    for<'a> &'a I: IntoIterator<Item = &'a C>,
+    for<'a> &'a mut I: IntoIterator<Item = &'a mut C>,
+{
+    unboxed::vstack(iterable, spacing, alignment).boxed()
+}
+
+/// A component which divides its bounding box between the components in `iterable` from left
+/// to right according to their size constraints, with `spacing` columns between them
+pub fn flex_row<I, S, C>(iterable: I, spacing: u32) -> CF<C::Output, S>
+where
+    I: 'static,
+    S: 'static,
+    C: 'static + Component<State = S>,
+    C::Output: ContainerOutput,
+    for<'a> &'a I: IntoIterator<Item = &'a C>,
+    for<'a> &'a mut I: IntoIterator<Item = &'a mut C>,
+{
+    unboxed::flex_row(iterable, spacing).boxed()
+}
+
+/// A component which divides its bounding box between the components in `iterable` from top to
+/// bottom according to their size constraints, with `spacing` rows between them
+pub fn flex_column<I, S, C>(iterable: I, spacing: u32) -> CF<C::Output, S>
+where
+    I: 'static,
+    S: 'static,
+    C: 'static + Component<State = S>,
+    C::Output: ContainerOutput,
+    for<'a> &'a I: IntoIterator<Item = &'a C>,
+    for<'a> &'a mut I: IntoIterator<Item = &'a mut C>,
+{
+    unboxed::flex_column(iterable, spacing).boxed()
+}
+
 pub fn styled_string<S: 'static>(string: String, style: Style) -> CF<(), S> {
     unboxed::styled_string(string, style).boxed()
 }
@@ -1,4 +1,4 @@
-use chargrid_core::{Component, Ctx, Event, FrameBuffer, UCoord};
+use chargrid_core::{Component, Ctx, Event, FrameBuffer, SizeConstraints, UCoord};
 use std::marker::PhantomData;
 
 mod boxed;
@@ -73,6 +73,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(self.lens.get(state), ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(self.lens.get(state), ctx)
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
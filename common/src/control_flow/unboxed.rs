@@ -4,19 +4,23 @@ pub use crate::control_flow::{
 };
 use crate::{
     add_offset::AddOffset,
-    align::{Align, Alignment},
+    align::{Align, Alignment, AlignmentX, AlignmentY},
     border::{Border, BorderStyle},
     bound_size::{BoundHeight, BoundSize, BoundWidth},
+    container::ContainerOutput,
     control_flow::{Lens, LensState, OrClose, OrEscape, OrEscapeOrStart, boxed},
     fill::Fill,
+    flex::{Constrain, Flex},
     pad_by::{PadBy, Padding},
     pad_to::PadTo,
     set_size::{SetHeight, SetSize, SetWidth},
+    stack::{HStack, VStack},
     text::StyledString,
+    translate::{Clip, Translate},
 };
 use chargrid_core::{
-    Component, Ctx, Event, FrameBuffer, ICoord, Rgba32, Style, Tint, TintIdentity, UCoord, app,
-    input,
+    Component, Constraint, Ctx, Event, FrameBuffer, ICoord, Rgba32, SizeConstraints, Style, Tint,
+    TintIdentity, UCoord, app, input,
 };
 use std::marker::PhantomData;
 use std::time::Duration;
@@ -38,6 +42,9 @@ impl<C: Component> Component for CF<C> {
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.0.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.0.size_constraints(state, ctx)
+    }
 }
 
 impl<C: Component> CF<C> {
@@ -151,6 +158,17 @@ impl<C: Component> CF<C> {
         })
     }
 
+    pub fn translate(self, offset: ICoord) -> CF<Translate<C>> {
+        cf(Translate {
+            component: self.0,
+            offset,
+        })
+    }
+
+    pub fn clip(self) -> CF<Clip<C>> {
+        cf(Clip { component: self.0 })
+    }
+
     pub fn set_size(self, size: UCoord) -> CF<SetSize<C>> {
         cf(SetSize {
             component: self.0,
@@ -193,6 +211,30 @@ impl<C: Component> CF<C> {
         })
     }
 
+    pub fn constrain(self, size_constraints: SizeConstraints) -> CF<Constrain<C>> {
+        cf(Constrain {
+            component: self.0,
+            width: Some(size_constraints.width),
+            height: Some(size_constraints.height),
+        })
+    }
+
+    pub fn constrain_width(self, width: Constraint) -> CF<Constrain<C>> {
+        cf(Constrain {
+            component: self.0,
+            width: Some(width),
+            height: None,
+        })
+    }
+
+    pub fn constrain_height(self, height: Constraint) -> CF<Constrain<C>> {
+        cf(Constrain {
+            component: self.0,
+            width: None,
+            height: Some(height),
+        })
+    }
+
     pub fn with_title_vertical<T: Component<State = C::State>>(
         self,
         title: T,
@@ -537,6 +579,9 @@ where
     fn size(&self, _state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(&self.state, ctx)
     }
+    fn size_constraints(&self, _state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(&self.state, ctx)
+    }
 }
 
 /// Component decorator intended for use within `loop_`, which wraps yielded values
@@ -562,6 +607,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
 }
 
 /// Component decorator intended for use within `loop_`, which wraps yielded values
@@ -587,6 +635,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
 }
 
 pub struct LoopState<S, C, F> {
@@ -643,6 +694,9 @@ where
     fn size(&self, _state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(&self.state, ctx)
     }
+    fn size_constraints(&self, _state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(&self.state, ctx)
+    }
 }
 
 pub struct Loop<C, F> {
@@ -695,6 +749,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
 }
 
 pub struct LoopUnit<C, F> {
@@ -744,6 +801,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
 }
 
 pub struct LoopMut<T, C, F> {
@@ -799,6 +859,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
 }
 
 /// Call a function on the current state returning a value which is yielded by
@@ -953,6 +1016,9 @@ where
     fn size(&self, _state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(&(), ctx)
     }
+    fn size_constraints(&self, _state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(&(), ctx)
+    }
 }
 
 pub struct IgnoreOutput<O, C: Component<Output = ()>> {
@@ -977,6 +1043,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
 }
 
 struct AndThenPersistentFirst<C, F> {
@@ -1238,6 +1307,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
 }
 
 pub struct Map<C, F> {
@@ -1265,6 +1337,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
 }
 
 pub struct MapVal<C, F> {
@@ -1290,6 +1365,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
 }
 
 pub struct MapSideEffect<C, F> {
@@ -1317,6 +1395,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
 }
 
 pub struct Replace<C, T> {
@@ -1340,6 +1421,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
 }
 
 /// Component decorator that clears the frame buffer before rendering
@@ -1361,6 +1445,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.0.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.0.size_constraints(state, ctx)
+    }
 }
 
 /// Component decorator that yields `app::Exit` in response to a window close event,
@@ -1377,8 +1464,9 @@ where
         self.0.render(state, ctx, fb);
     }
     fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
+        let is_close = event.is_close();
         let output_unless_close = self.0.update(state, ctx, event);
-        if let Event::Input(input::Input::Keyboard(input::keys::ETX)) = event {
+        if is_close {
             return Some(app::Exit);
         }
         output_unless_close
@@ -1386,6 +1474,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.0.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.0.size_constraints(state, ctx)
+    }
 }
 
 pub struct OnExitWithState<C: Component, F: FnMut(&mut C::State)> {
@@ -1400,7 +1491,7 @@ impl<C: Component, F: FnMut(&mut C::State)> Component for OnExitWithState<C, F>
         self.component.render(state, ctx, fb);
     }
     fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
-        if let Event::Input(input::Input::Keyboard(input::keys::ETX)) = event {
+        if event.is_close() {
             (self.f)(state);
         }
         self.component.update(state, ctx, event)
@@ -1408,6 +1499,9 @@ impl<C: Component, F: FnMut(&mut C::State)> Component for OnExitWithState<C, F>
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
 }
 
 /// Component decorator that yields `Err(Escape)` when the escape key is pressed
@@ -1432,6 +1526,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.0.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.0.size_constraints(state, ctx)
+    }
 }
 
 pub struct CatchEscapeOrStart<C: Component>(pub C);
@@ -1461,6 +1558,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.0.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.0.size_constraints(state, ctx)
+    }
 }
 
 pub struct CatchClickOut<C: Component>(pub C);
@@ -1478,7 +1578,7 @@ where
         let is_click_out = match event {
             Event::Input(input::Input::Mouse(input::MouseInput::MousePress { coord, .. })) => {
                 let size = self.size(state, ctx);
-                !ctx.bounding_box.set_size(size).contains_coord(coord)
+                !ctx.set_size(size).contains_coord(coord)
             }
             _ => false,
         };
@@ -1491,6 +1591,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.0.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.0.size_constraints(state, ctx)
+    }
 }
 
 pub struct CatchEscapeOrClickOut<C: Component>(pub C);
@@ -1511,7 +1614,7 @@ where
             }
             Event::Input(input::Input::Mouse(input::MouseInput::MousePress { coord, .. })) => {
                 let size = self.size(state, ctx);
-                if ctx.bounding_box.set_size(size).contains_coord(coord) {
+                if ctx.set_size(size).contains_coord(coord) {
                     None
                 } else {
                     Some(EscapeOrClickOut::ClickOut)
@@ -1528,6 +1631,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.0.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.0.size_constraints(state, ctx)
+    }
 }
 
 pub struct MenuHarness<C: Component>(pub C);
@@ -1566,6 +1672,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.0.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.0.size_constraints(state, ctx)
+    }
 }
 
 pub struct NoPeek<C>(C);
@@ -1584,6 +1693,9 @@ impl<T, C: Component<Output = Option<T>>> Component for NoPeek<C> {
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.0.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.0.size_constraints(state, ctx)
+    }
 }
 
 pub struct Delay<C: Component<Output = ()>> {
@@ -1614,6 +1726,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
 }
 
 pub struct PressAnyKey<C: Component<Output = ()>>(C);
@@ -1639,6 +1754,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.0.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.0.size_constraints(state, ctx)
+    }
 }
 
 pub struct OverlayTint<C: Component, D: Component, T: Tint> {
@@ -1672,6 +1790,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.foreground.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.foreground.size_constraints(state, ctx)
+    }
 }
 
 pub struct Pause<C: Component>(C);
@@ -1691,6 +1812,9 @@ where
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.0.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.0.size_constraints(state, ctx)
+    }
 }
 
 pub struct Some_<C: Component>(pub C);
@@ -1706,6 +1830,9 @@ impl<C: Component> Component for Some_<C> {
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.0.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.0.size_constraints(state, ctx)
+    }
 }
 
 pub struct None_<C: Component>(pub C);
@@ -1722,6 +1849,9 @@ impl<C: Component> Component for None_<C> {
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.0.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.0.size_constraints(state, ctx)
+    }
 }
 
 pub struct Unit<S> {
@@ -1784,6 +1914,50 @@ where
     })
 }
 
+pub fn hstack<I, S, C>(iterable: I, spacing: u32, alignment: AlignmentY) -> CF<HStack<I, S>>
+where
+    C: Component<State = S>,
+    C::Output: ContainerOutput,
+    for<'a> &'a I: IntoIterator<Item = &'a C>,
+    for<'a> &'a mut I: IntoIterator<Item = &'a mut C>,
+{
+    cf(HStack::new(iterable)
+        .with_spacing(spacing)
+        .with_alignment(alignment))
+}
+
+pub fn vstack<I, S, C>(iterable: I, spacing: u32, alignment: AlignmentX) -> CF<VStack<I, S>>
+where
+    C: Component<State = S>,
+    C::Output: ContainerOutput,
+    for<'a> &'a I: IntoIterator<Item = &'a C>,
+    for<'a> &'a mut I: IntoIterator<Item = &'a mut C>,
+{
+    cf(VStack::new(iterable)
+        .with_spacing(spacing)
+        .with_alignment(alignment))
+}
+
+pub fn flex_row<I, S, C>(iterable: I, spacing: u32) -> CF<Flex<I, S>>
+where
+    C: Component<State = S>,
+    C::Output: ContainerOutput,
+    for<'a> &'a I: IntoIterator<Item = &'a C>,
+    for<'a> &'a mut I: IntoIterator<Item = &'a mut C>,
+{
+    cf(Flex::row(iterable).with_spacing(spacing))
+}
+
+pub fn flex_column<I, S, C>(iterable: I, spacing: u32) -> CF<Flex<I, S>>
+where
+    C: Component<State = S>,
+    C::Output: ContainerOutput,
+    for<'a> &'a I: IntoIterator<Item = &'a C>,
+    for<'a> &'a mut I: IntoIterator<Item = &'a mut C>,
+{
+    cf(Flex::column(iterable).with_spacing(spacing))
+}
+
 pub fn styled_string<S>(string: String, style: Style) -> CF<IgnoreState<S, StyledString>> {
     cf(StyledString { string, style }).ignore_state()
 }
@@ -1971,6 +2145,9 @@ impl<C: Component, F: FnMut()> Component for OnEachTick<C, F> {
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
 }
 
 pub struct OnEachTickWithState<C: Component, F: FnMut(&mut C::State)> {
@@ -1990,4 +2167,7 @@ impl<C: Component, F: FnMut(&mut C::State)> Component for OnEachTickWithState<C,
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
 }
@@ -22,7 +22,7 @@ impl<C: Component> Component for Fill<C> {
                             background: Some(self.background),
                             ..Default::default()
                         },
-                        character: Some(' '),
+                        character: Some(Grapheme::from_char(' ')),
                     },
                 );
             }
@@ -35,4 +35,7 @@ impl<C: Component> Component for Fill<C> {
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
 }
@@ -0,0 +1,252 @@
+use crate::container::{Axis, ContainerOutput};
+use chargrid_core::{layout, *};
+use std::marker::PhantomData;
+
+#[derive(Debug, Clone, Copy)]
+pub enum FlexDirection {
+    Row,
+    Column,
+}
+
+/// Divides its bounding box between its children along a row or column according to the
+/// children's `Component::size_constraints` (see `chargrid_core::layout`), leaving `spacing`
+/// cells between adjacent children. Across the row or column, each child is sized by resolving
+/// its constraint against the full width or height of the bounding box, and placed at the top or
+/// left. Events and outputs are routed as described in the `container` module.
+pub struct Flex<I, S> {
+    pub children: I,
+    pub direction: FlexDirection,
+    pub spacing: u32,
+    state: PhantomData<S>,
+}
+
+impl<I, S> Flex<I, S> {
+    pub fn new(direction: FlexDirection, children: I) -> Self {
+        Self {
+            children,
+            direction,
+            spacing: 0,
+            state: PhantomData,
+        }
+    }
+
+    pub fn row(children: I) -> Self {
+        Self::new(FlexDirection::Row, children)
+    }
+
+    pub fn column(children: I) -> Self {
+        Self::new(FlexDirection::Column, children)
+    }
+
+    pub fn with_spacing(self, spacing: u32) -> Self {
+        Self { spacing, ..self }
+    }
+
+    fn axis(&self) -> Axis {
+        match self.direction {
+            FlexDirection::Row => Axis::Horizontal,
+            FlexDirection::Column => Axis::Vertical,
+        }
+    }
+}
+
+impl<I, S, C> Flex<I, S>
+where
+    C: Component<State = S>,
+    for<'a> &'a I: IntoIterator<Item = &'a C>,
+{
+    fn child_size_constraints(&self, state: &S, ctx: Ctx) -> Vec<SizeConstraints> {
+        (&self.children)
+            .into_iter()
+            .map(|child| child.size_constraints(state, ctx))
+            .collect()
+    }
+
+    fn total_spacing(&self, num_children: usize) -> u32 {
+        self.spacing * (num_children as u32).saturating_sub(1)
+    }
+
+    /// Returns the context with which to render or update each child, and the size of the
+    /// container
+    fn child_ctxs<'a>(&self, state: &S, ctx: Ctx<'a>) -> (Vec<Ctx<'a>>, UCoord) {
+        let axis = self.axis();
+        let available = ctx.bounding_box.size();
+        let size_constraints = self.child_size_constraints(state, ctx);
+        let main_constraints = size_constraints
+            .iter()
+            .map(|&size_constraints| axis.main_constraint(size_constraints))
+            .collect::<Vec<_>>();
+        let total_spacing = self.total_spacing(size_constraints.len());
+        let main_sizes = layout::distribute(
+            axis.main(available).saturating_sub(total_spacing),
+            &main_constraints,
+        );
+        let mut child_ctxs = Vec::with_capacity(main_sizes.len());
+        let mut main_offset = 0;
+        let mut cross_size = 0;
+        for (size_constraints, main_size) in size_constraints.into_iter().zip(main_sizes) {
+            let child_cross_size = axis
+                .cross_constraint(size_constraints)
+                .resolve(axis.cross(available));
+            child_ctxs.push(
+                ctx.add_offset(axis.offset(main_offset, 0))
+                    .set_size(axis.size(main_size, child_cross_size)),
+            );
+            main_offset += main_size + self.spacing;
+            cross_size = cross_size.max(child_cross_size);
+        }
+        let main_size = main_offset.saturating_sub(self.spacing);
+        (child_ctxs, axis.size(main_size, cross_size))
+    }
+}
+
+impl<I, S, C> Component for Flex<I, S>
+where
+    C: Component<State = S>,
+    C::Output: ContainerOutput,
+    for<'a> &'a I: IntoIterator<Item = &'a C>,
+    for<'a> &'a mut I: IntoIterator<Item = &'a mut C>,
+{
+    type Output = C::Output;
+    type State = S;
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        let (child_ctxs, _) = self.child_ctxs(state, ctx);
+        for (child, child_ctx) in (&self.children).into_iter().zip(child_ctxs) {
+            child.render(state, child_ctx, fb);
+        }
+    }
+    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
+        let (child_ctxs, _) = self.child_ctxs(state, ctx);
+        let mouse_coord = event.mouse_input().map(|mouse_input| mouse_input.coord());
+        let mut output = C::Output::empty();
+        for (child, child_ctx) in (&mut self.children).into_iter().zip(child_ctxs) {
+            if let Some(mouse_coord) = mouse_coord
+                && !child_ctx.contains_coord(mouse_coord)
+            {
+                continue;
+            }
+            output = output.or_output(child.update(state, child_ctx, event));
+        }
+        output
+    }
+    fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
+        self.child_ctxs(state, ctx).1
+    }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        // fill the available space, but don't shrink below the space needed for each child's
+        // minimum size
+        let axis = self.axis();
+        let size_constraints = self.child_size_constraints(state, ctx);
+        let main_min = size_constraints
+            .iter()
+            .map(|&size_constraints| axis.main_constraint(size_constraints).min)
+            .sum::<u32>()
+            + self.total_spacing(size_constraints.len());
+        let cross_min = size_constraints
+            .iter()
+            .map(|&size_constraints| axis.cross_constraint(size_constraints).min)
+            .max()
+            .unwrap_or(0);
+        let min = axis.size(main_min, cross_min);
+        SizeConstraints {
+            width: Constraint::fill().with_min(min.width()),
+            height: Constraint::fill().with_min(min.height()),
+        }
+    }
+}
+
+/// Overrides the constraints on the size of a component along either axis. When not laid out by
+/// a container, the component's size is found by resolving its constraints against its bounding
+/// box.
+pub struct Constrain<C: Component> {
+    pub component: C,
+    pub width: Option<Constraint>,
+    pub height: Option<Constraint>,
+}
+
+impl<C: Component> Component for Constrain<C> {
+    type Output = C::Output;
+    type State = C::State;
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        self.component.render(state, ctx, fb);
+    }
+    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
+        self.component.update(state, ctx, event)
+    }
+    fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
+        self.size_constraints(state, ctx)
+            .resolve(ctx.bounding_box.size())
+    }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        let SizeConstraints { width, height } = self.component.size_constraints(state, ctx);
+        SizeConstraints {
+            width: self.width.unwrap_or(width),
+            height: self.height.unwrap_or(height),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        border::{Border, BorderStyle},
+        control_flow::{CF, cf},
+        test_util::*,
+    };
+
+    type Child = CF<Option<(char, ICoord)>, ()>;
+
+    fn row() -> Flex<Vec<Child>, ()> {
+        Flex::row(vec![
+            cf(block('a', 2, 1)),
+            cf(Border {
+                component: Filler('b'),
+                style: BorderStyle::default(),
+            }),
+            cf(block('c', 1, 1)),
+        ])
+        .with_spacing(1)
+    }
+
+    #[test]
+    fn row_layout() {
+        // the border forwards the constraints of the filling component inside it
+        assert_eq!(
+            render(&row(), UCoord::new(12, 3)),
+            "aa ┌─────┐ c\n   │bbbbb│\n   └─────┘\n"
+        );
+        assert_eq!(
+            render(&row(), UCoord::new(8, 4)),
+            "aa ┌─┐ c\n   │b│\n   │b│\n   └─┘\n"
+        );
+    }
+
+    #[test]
+    fn column_layout() {
+        let column = Flex::column(vec![cf(block('a', 3, 1)), cf(Filler('b'))]);
+        assert_eq!(render(&column, UCoord::new(4, 3)), "aaa\nbbbb\nbbbb\n");
+        assert_eq!(
+            column.size_constraints(&(), Ctx::default_with_bounding_box_size(UCoord::new(4, 3))),
+            SizeConstraints {
+                width: Constraint::fill().with_min(3),
+                height: Constraint::fill().with_min(1),
+            }
+        );
+    }
+
+    #[test]
+    fn mouse_routing() {
+        let size = UCoord::new(12, 3);
+        let mut row = row();
+        assert_eq!(
+            press(&mut row, size, ICoord::new(5, 1)),
+            Some(('b', ICoord::new(1, 0)))
+        );
+        assert_eq!(
+            press(&mut row, size, ICoord::new(11, 0)),
+            Some(('c', ICoord::new(0, 0)))
+        );
+        assert_eq!(press(&mut row, size, ICoord::new(2, 0)), None);
+    }
+}
@@ -0,0 +1,348 @@
+use crate::container::ContainerOutput;
+use chargrid_core::{layout, *};
+
+/// How the width of a column or the height of a row is decided
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackSize {
+    /// Just large enough for the largest component in the column or row. Components spanning
+    /// several columns or rows which don't fit in the tracks they span enlarge the auto-sized
+    /// tracks among them.
+    Auto,
+    /// A fixed number of cells
+    Fixed(u32),
+    /// A share of the space left over after auto-sized and fixed-size tracks, in proportion to the
+    /// fractions of the other fractional tracks
+    Fraction(u32),
+}
+
+/// A component placed in a grid, occupying `row_span` rows starting at `row` and `column_span`
+/// columns starting at `column`
+pub struct GridCell<C: Component> {
+    pub component: C,
+    pub row: u32,
+    pub column: u32,
+    pub row_span: u32,
+    pub column_span: u32,
+}
+
+impl<C: Component> GridCell<C> {
+    pub fn new(component: C, row: u32, column: u32) -> Self {
+        Self {
+            component,
+            row,
+            column,
+            row_span: 1,
+            column_span: 1,
+        }
+    }
+
+    pub fn with_row_span(self, row_span: u32) -> Self {
+        Self { row_span, ..self }
+    }
+
+    pub fn with_column_span(self, column_span: u32) -> Self {
+        Self {
+            column_span,
+            ..self
+        }
+    }
+}
+
+/// Lays out components in rows and columns. The sizes of columns and rows are given by `columns`
+/// and `rows`, and any columns or rows beyond these which are occupied by a component are
+/// auto-sized. Each component is given a bounding box covering the columns and rows it spans.
+/// Events and outputs are routed as described in the `container` module.
+pub struct Grid<C: Component> {
+    pub cells: Vec<GridCell<C>>,
+    pub columns: Vec<TrackSize>,
+    pub rows: Vec<TrackSize>,
+    pub column_spacing: u32,
+    pub row_spacing: u32,
+}
+
+impl<C: Component> Grid<C> {
+    pub fn new(columns: Vec<TrackSize>, rows: Vec<TrackSize>) -> Self {
+        Self {
+            cells: Vec::new(),
+            columns,
+            rows,
+            column_spacing: 0,
+            row_spacing: 0,
+        }
+    }
+
+    pub fn with_spacing(self, column_spacing: u32, row_spacing: u32) -> Self {
+        Self {
+            column_spacing,
+            row_spacing,
+            ..self
+        }
+    }
+
+    pub fn with_cell(mut self, cell: GridCell<C>) -> Self {
+        self.cells.push(cell);
+        self
+    }
+
+    pub fn add_cell(&mut self, cell: GridCell<C>) {
+        self.cells.push(cell);
+    }
+
+    fn layout(&self, state: &C::State, ctx: Ctx) -> GridLayout {
+        let sizes = self
+            .cells
+            .iter()
+            .map(|cell| cell.component.size(state, ctx))
+            .collect::<Vec<_>>();
+        let available = ctx.bounding_box.size();
+        let columns = Tracks::resolve(
+            &self.columns,
+            self.cells
+                .iter()
+                .zip(sizes.iter())
+                .map(|(cell, size)| (cell.column, cell.column_span, size.width())),
+            self.column_spacing,
+            available.width(),
+        );
+        let rows = Tracks::resolve(
+            &self.rows,
+            self.cells
+                .iter()
+                .zip(sizes.iter())
+                .map(|(cell, size)| (cell.row, cell.row_span, size.height())),
+            self.row_spacing,
+            available.height(),
+        );
+        GridLayout { columns, rows }
+    }
+}
+
+/// The resolved positions and sizes of either the columns or the rows of a grid
+struct Tracks {
+    offsets: Vec<u32>,
+    sizes: Vec<u32>,
+    spacing: u32,
+}
+
+impl Tracks {
+    /// Resolve the sizes of tracks given the start, span and size along the relevant axis of each
+    /// component in the grid
+    fn resolve(
+        track_sizes: &[TrackSize],
+        components: impl Iterator<Item = (u32, u32, u32)>,
+        spacing: u32,
+        available: u32,
+    ) -> Self {
+        let components = components
+            .map(|(start, span, size)| (start as usize, span.max(1) as usize, size))
+            .collect::<Vec<_>>();
+        let count = components
+            .iter()
+            .map(|&(start, span, _)| start + span)
+            .max()
+            .unwrap_or(0)
+            .max(track_sizes.len());
+        let track_size = |i: usize| track_sizes.get(i).copied().unwrap_or(TrackSize::Auto);
+        let mut auto_sizes = vec![0; count];
+        for &(start, span, size) in components.iter() {
+            if span == 1 {
+                auto_sizes[start] = auto_sizes[start].max(size);
+            }
+        }
+        for &(start, span, size) in components.iter() {
+            if span == 1 {
+                continue;
+            }
+            let auto_tracks = (start..(start + span))
+                .filter(|&i| track_size(i) == TrackSize::Auto)
+                .collect::<Vec<_>>();
+            if auto_tracks.is_empty() {
+                continue;
+            }
+            let current_size = (start..(start + span))
+                .map(|i| match track_size(i) {
+                    TrackSize::Auto => auto_sizes[i],
+                    TrackSize::Fixed(size) => size,
+                    TrackSize::Fraction(_) => 0,
+                })
+                .sum::<u32>()
+                + spacing * (span as u32 - 1);
+            if let Some(extra) = size.checked_sub(current_size) {
+                // share the extra space between the auto-sized tracks, with any remainder going
+                // to the first tracks
+                let num_auto_tracks = auto_tracks.len() as u32;
+                for (j, i) in auto_tracks.into_iter().enumerate() {
+                    auto_sizes[i] +=
+                        extra / num_auto_tracks + ((j as u32) < extra % num_auto_tracks) as u32;
+                }
+            }
+        }
+        let constraints = (0..count)
+            .map(|i| match track_size(i) {
+                TrackSize::Auto => Constraint::fixed(auto_sizes[i]),
+                TrackSize::Fixed(size) => Constraint::fixed(size),
+                TrackSize::Fraction(fraction) => Constraint::cells(0).with_grow(fraction),
+            })
+            .collect::<Vec<_>>();
+        let total_spacing = spacing * (count as u32).saturating_sub(1);
+        let sizes = layout::distribute(available.saturating_sub(total_spacing), &constraints);
+        let mut offsets = Vec::with_capacity(count);
+        let mut offset = 0;
+        for &size in sizes.iter() {
+            offsets.push(offset);
+            offset += size + spacing;
+        }
+        Self {
+            offsets,
+            sizes,
+            spacing,
+        }
+    }
+
+    fn total(&self) -> u32 {
+        (self.sizes.iter().sum::<u32>() + self.spacing * self.sizes.len() as u32)
+            .saturating_sub(self.spacing)
+    }
+
+    /// The offset and size of a span of tracks
+    fn span(&self, start: u32, span: u32) -> (u32, u32) {
+        let start = start as usize;
+        let end = start + span.max(1) as usize;
+        let size =
+            self.sizes[start..end].iter().sum::<u32>() + self.spacing * (end - start - 1) as u32;
+        (self.offsets[start], size)
+    }
+}
+
+struct GridLayout {
+    columns: Tracks,
+    rows: Tracks,
+}
+
+impl GridLayout {
+    fn cell_ctx<'a, C: Component>(&self, cell: &GridCell<C>, ctx: Ctx<'a>) -> Ctx<'a> {
+        let (x, width) = self.columns.span(cell.column, cell.column_span);
+        let (y, height) = self.rows.span(cell.row, cell.row_span);
+        ctx.add_offset(ICoord::new(x as i32, y as i32))
+            .set_size(UCoord::new(width, height))
+    }
+}
+
+impl<C> Component for Grid<C>
+where
+    C: Component,
+    C::Output: ContainerOutput,
+{
+    type Output = C::Output;
+    type State = C::State;
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        let layout = self.layout(state, ctx);
+        for cell in self.cells.iter() {
+            cell.component.render(state, layout.cell_ctx(cell, ctx), fb);
+        }
+    }
+    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
+        let layout = self.layout(state, ctx);
+        if let Some(mouse_input) = event.mouse_input() {
+            let coord = mouse_input.coord();
+            if let Some((cell, cell_ctx)) = self.cells.iter_mut().find_map(|cell| {
+                let cell_ctx = layout.cell_ctx(cell, ctx);
+                cell_ctx
+                    .coord_absolute_to_relative(coord)
+                    .map(|_| (cell, cell_ctx))
+            }) {
+                cell.component.update(state, cell_ctx, event)
+            } else {
+                C::Output::empty()
+            }
+        } else {
+            let mut output = C::Output::empty();
+            for cell in self.cells.iter_mut() {
+                let cell_ctx = layout.cell_ctx(cell, ctx);
+                output = output.or_output(cell.component.update(state, cell_ctx, event));
+            }
+            output
+        }
+    }
+    fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
+        let layout = self.layout(state, ctx);
+        UCoord::new(layout.columns.total(), layout.rows.total())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        control_flow::{CF, cf},
+        test_util::*,
+    };
+
+    type Child = CF<Option<(char, ICoord)>, ()>;
+
+    #[test]
+    fn track_resolution() {
+        let grid = Grid::<Child>::new(
+            vec![
+                TrackSize::Auto,
+                TrackSize::Fixed(3),
+                TrackSize::Fraction(1),
+                TrackSize::Fraction(2),
+            ],
+            vec![TrackSize::Fixed(1), TrackSize::Auto],
+        )
+        .with_cell(GridCell::new(cf(block('a', 2, 1)), 0, 0))
+        .with_cell(GridCell::new(cf(Filler('b')), 0, 1))
+        .with_cell(GridCell::new(cf(Filler('c')), 0, 2))
+        .with_cell(GridCell::new(cf(Filler('d')), 0, 3))
+        .with_cell(GridCell::new(cf(block('e', 1, 2)), 1, 1));
+        assert_eq!(
+            render(&grid, UCoord::new(14, 4)),
+            "aabbbcccdddddd\n  e\n  e\n\n"
+        );
+        assert_eq!(
+            grid.size(&(), Ctx::default_with_bounding_box_size(UCoord::new(14, 4))),
+            UCoord::new(14, 3)
+        );
+    }
+
+    /// Two auto-sized columns whose contents are narrower than a component spanning both
+    fn spanning_grid() -> Grid<Child> {
+        Grid::new(vec![TrackSize::Auto, TrackSize::Auto], vec![])
+            .with_spacing(1, 0)
+            .with_cell(GridCell::new(cf(block('a', 1, 1)), 0, 0))
+            .with_cell(GridCell::new(cf(block('b', 1, 1)), 0, 1))
+            .with_cell(GridCell::new(cf(block('c', 5, 1)), 1, 0).with_column_span(2))
+    }
+
+    #[test]
+    fn span_over_auto_tracks() {
+        assert_eq!(render(&spanning_grid(), UCoord::new(8, 2)), "a  b\nccccc\n");
+        let grid = Grid::<Child>::new(vec![TrackSize::Fixed(1), TrackSize::Auto], vec![])
+            .with_cell(GridCell::new(cf(block('a', 5, 1)), 0, 0).with_column_span(2))
+            .with_cell(GridCell::new(cf(block('b', 1, 1)), 1, 1));
+        assert_eq!(render(&grid, UCoord::new(8, 2)), "aaaaa\n b\n");
+        assert_eq!(
+            grid.size(&(), Ctx::default_with_bounding_box_size(UCoord::new(8, 2))),
+            UCoord::new(5, 2)
+        );
+    }
+
+    #[test]
+    fn mouse_routing() {
+        let size = UCoord::new(8, 2);
+        let mut grid = spanning_grid();
+        assert_eq!(
+            press(&mut grid, size, ICoord::new(4, 0)),
+            Some(('b', ICoord::new(1, 0)))
+        );
+        assert_eq!(
+            press(&mut grid, size, ICoord::new(4, 1)),
+            Some(('c', ICoord::new(4, 0)))
+        );
+        // in the spacing between columns
+        assert_eq!(press(&mut grid, size, ICoord::new(2, 0)), None);
+        // beyond the last column
+        assert_eq!(press(&mut grid, size, ICoord::new(6, 1)), None);
+    }
+}
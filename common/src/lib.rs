@@ -2,13 +2,21 @@ pub mod add_offset;
 pub mod align;
 pub mod border;
 pub mod bound_size;
+pub mod container;
 pub mod control_flow;
 pub mod fade;
 pub mod fill;
+pub mod flex;
+pub mod grid;
 pub mod menu;
 pub mod pad_by;
 pub mod pad_to;
+pub mod scroll;
 pub mod set_size;
 pub mod signal;
+pub mod stack;
+#[cfg(test)]
+mod test_util;
 pub mod text;
 pub mod text_field;
+pub mod translate;
@@ -62,7 +62,7 @@ impl<T: Clone, S> Menu<T, S> {
     }
 
     fn menu_index_from_screen_coord(&self, ctx: Ctx, coord: ICoord) -> Option<usize> {
-        if let Some(relative_coord) = ctx.bounding_box.coord_absolute_to_relative(coord) {
+        if let Some(relative_coord) = ctx.coord_absolute_to_relative(coord) {
             let index = relative_coord.y as usize;
             if let Some(&Some(item_index)) = self.offset_to_item_index.get(index) {
                 Some(item_index)
@@ -449,6 +449,7 @@ pub mod identifier {
                 underline: Some(fade.to.underline),
                 foreground: Some(fade_fg.eval(ctx.since_change)),
                 background: Some(fade_bg.eval(ctx.since_change)),
+                ..Default::default()
             };
         })
     }
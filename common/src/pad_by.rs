@@ -46,4 +46,9 @@ impl<C: Component> Component for PadBy<C> {
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx) + self.padding.size_delta()
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component
+            .size_constraints(state, ctx)
+            .add_size(self.padding.size_delta())
+    }
 }
@@ -17,4 +17,9 @@ impl<C: Component> Component for PadTo<C> {
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.component.size(state, ctx).pairwise_max(self.size)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component
+            .size_constraints(state, ctx)
+            .at_least(self.size)
+    }
 }
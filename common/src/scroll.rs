@@ -0,0 +1,448 @@
+use chargrid_core::*;
+
+/// The appearance of the scrollbars of a `Scroll`
+#[derive(Debug, Clone, Copy)]
+pub struct ScrollbarStyle {
+    /// Drawn along the whole length of the scrollbar
+    pub track: RenderCell,
+    /// Drawn over the track to indicate the position and size of the visible region
+    pub thumb: RenderCell,
+}
+
+impl Default for ScrollbarStyle {
+    fn default() -> Self {
+        Self {
+            track: RenderCell::BLANK.with_background(Rgba32::new_grey(63)),
+            thumb: RenderCell::BLANK.with_background(Rgba32::new_grey(191)),
+        }
+    }
+}
+
+/// Displays a window onto a component which may be larger than the window. The window fills the
+/// bounding box (minus space for any scrollbars) and the component is given a bounding box of
+/// the size it reports. Mouse scrolling over the window, PageUp/PageDown and Home/End scroll the
+/// window, and clicking or dragging on a scrollbar moves the window to that position. These
+/// events, and mouse events outside the window, aren't passed to the component (and yield the
+/// default output). All other events are passed to the component.
+pub struct Scroll<C: Component> {
+    pub component: C,
+    /// The coordinate of the component which appears at the top-left of the window. This is
+    /// clamped such that the window doesn't go beyond the bottom-right of the component.
+    pub offset: UCoord,
+    pub vertical_scrollbar: bool,
+    pub horizontal_scrollbar: bool,
+    pub scrollbar_style: ScrollbarStyle,
+}
+
+impl<C: Component> Scroll<C> {
+    pub fn new(component: C) -> Self {
+        Self {
+            component,
+            offset: UCoord::new(0, 0),
+            vertical_scrollbar: true,
+            horizontal_scrollbar: false,
+            scrollbar_style: Default::default(),
+        }
+    }
+
+    fn scrollbars_size(&self) -> UCoord {
+        UCoord::new(
+            self.vertical_scrollbar as u32,
+            self.horizontal_scrollbar as u32,
+        )
+    }
+
+    fn layout<'a>(&self, state: &C::State, ctx: Ctx<'a>) -> ScrollLayout<'a> {
+        let viewport_ctx = ctx.set_size(
+            ctx.bounding_box
+                .size()
+                .saturating_sub(self.scrollbars_size()),
+        );
+        let viewport = viewport_ctx.bounding_box.size();
+        let content = self.component.size(state, viewport_ctx);
+        let max_offset = content.saturating_sub(viewport);
+        let offset = self.offset.pairwise_min(max_offset);
+        let component_ctx = viewport_ctx
+            .clip_to_bounding_box()
+            .set_size(content)
+            .translate(-offset.to_icoord());
+        ScrollLayout {
+            ctx,
+            viewport_ctx,
+            component_ctx,
+            content,
+            offset,
+            max_offset,
+        }
+    }
+}
+
+struct ScrollLayout<'a> {
+    ctx: Ctx<'a>,
+    viewport_ctx: Ctx<'a>,
+    component_ctx: Ctx<'a>,
+    content: UCoord,
+    offset: UCoord,
+    max_offset: UCoord,
+}
+
+/// A scrollbar along one axis, measured in cells from the start of its track
+struct Scrollbar {
+    track_length: u32,
+    thumb_start: u32,
+    thumb_length: u32,
+}
+
+impl Scrollbar {
+    fn new(viewport: u32, content: u32, offset: u32, max_offset: u32) -> Self {
+        let track_length = viewport;
+        if max_offset == 0 || track_length == 0 {
+            return Self {
+                track_length,
+                thumb_start: 0,
+                thumb_length: track_length,
+            };
+        }
+        let thumb_length = ((track_length as u64 * viewport as u64) / content as u64).max(1) as u32;
+        let thumb_start = ((offset as u64 * (track_length - thumb_length) as u64
+            + max_offset as u64 / 2)
+            / max_offset as u64) as u32;
+        Self {
+            track_length,
+            thumb_start,
+            thumb_length,
+        }
+    }
+
+    fn contains_thumb(&self, position: u32) -> bool {
+        position >= self.thumb_start && position < self.thumb_start + self.thumb_length
+    }
+
+    /// The offset which places the centre of the thumb at the given position along the track
+    fn offset_at(&self, position: u32, max_offset: u32) -> u32 {
+        let range = self.track_length.saturating_sub(self.thumb_length);
+        if range == 0 {
+            return 0;
+        }
+        let thumb_start = position.saturating_sub(self.thumb_length / 2).min(range);
+        ((thumb_start as u64 * max_offset as u64 + range as u64 / 2) / range as u64) as u32
+    }
+}
+
+impl<'a> ScrollLayout<'a> {
+    fn vertical_scrollbar(&self) -> Scrollbar {
+        Scrollbar::new(
+            self.viewport_ctx.bounding_box.size().height(),
+            self.content.height(),
+            self.offset.height(),
+            self.max_offset.height(),
+        )
+    }
+
+    fn horizontal_scrollbar(&self) -> Scrollbar {
+        Scrollbar::new(
+            self.viewport_ctx.bounding_box.size().width(),
+            self.content.width(),
+            self.offset.width(),
+            self.max_offset.width(),
+        )
+    }
+
+    /// The position along the vertical scrollbar of a coordinate relative to the scroll
+    /// container, if it's on the vertical scrollbar
+    fn vertical_scrollbar_position(&self, coord: ICoord) -> Option<u32> {
+        let viewport = self.viewport_ctx.bounding_box.size();
+        (coord.x == viewport.width() as i32 && coord.y >= 0 && coord.y < viewport.height() as i32)
+            .then_some(coord.y as u32)
+    }
+
+    fn horizontal_scrollbar_position(&self, coord: ICoord) -> Option<u32> {
+        let viewport = self.viewport_ctx.bounding_box.size();
+        (coord.y == viewport.height() as i32 && coord.x >= 0 && coord.x < viewport.width() as i32)
+            .then_some(coord.x as u32)
+    }
+}
+
+impl<C> Component for Scroll<C>
+where
+    C: Component,
+    C::Output: Default,
+{
+    type Output = C::Output;
+    type State = C::State;
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        let layout = self.layout(state, ctx);
+        self.component.render(state, layout.component_ctx, fb);
+        let viewport = layout.viewport_ctx.bounding_box.size();
+        if self.vertical_scrollbar {
+            let scrollbar = layout.vertical_scrollbar();
+            for y in 0..scrollbar.track_length {
+                let render_cell = if scrollbar.contains_thumb(y) {
+                    self.scrollbar_style.thumb
+                } else {
+                    self.scrollbar_style.track
+                };
+                let coord = ICoord::new(viewport.width() as i32, y as i32);
+                fb.set_cell_relative_to_ctx(ctx, coord, 0, render_cell);
+            }
+        }
+        if self.horizontal_scrollbar {
+            let scrollbar = layout.horizontal_scrollbar();
+            for x in 0..scrollbar.track_length {
+                let render_cell = if scrollbar.contains_thumb(x) {
+                    self.scrollbar_style.thumb
+                } else {
+                    self.scrollbar_style.track
+                };
+                let coord = ICoord::new(x as i32, viewport.height() as i32);
+                fb.set_cell_relative_to_ctx(ctx, coord, 0, render_cell);
+            }
+        }
+    }
+    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
+        use input::*;
+        let layout = self.layout(state, ctx);
+        let viewport = layout.viewport_ctx.bounding_box.size();
+        let mut offset = layout.offset;
+        if let Some(key_event) = event.key_press() {
+            match key_event.key {
+                KeyboardInput::PageUp => {
+                    offset = offset.set_height(offset.height().saturating_sub(viewport.height()))
+                }
+                KeyboardInput::PageDown => {
+                    offset = offset.set_height(offset.height().saturating_add(viewport.height()))
+                }
+                KeyboardInput::Home => offset = offset.set_height(0),
+                KeyboardInput::End => offset = offset.set_height(layout.max_offset.height()),
+                _ => return self.component.update(state, layout.component_ctx, event),
+            }
+            self.offset = offset.pairwise_min(layout.max_offset);
+            return Default::default();
+        }
+        let Some(mouse_input) = event.mouse_input() else {
+            return self.component.update(state, layout.component_ctx, event);
+        };
+        let Some(coord) = layout.ctx.coord_absolute_to_relative(mouse_input.coord()) else {
+            return Default::default();
+        };
+        if let MouseInput::MouseScroll { direction, .. } = mouse_input {
+            offset = match direction {
+                ScrollDirection::Up => offset.set_height(offset.height().saturating_sub(1)),
+                ScrollDirection::Down => offset.set_height(offset.height().saturating_add(1)),
+                ScrollDirection::Left => offset.set_width(offset.width().saturating_sub(1)),
+                ScrollDirection::Right => offset.set_width(offset.width().saturating_add(1)),
+            };
+            self.offset = offset.pairwise_min(layout.max_offset);
+            return Default::default();
+        }
+        if let MouseInput::MousePress { .. }
+        | MouseInput::MouseMove {
+            button: Some(_), ..
+        } = mouse_input
+        {
+            if self.vertical_scrollbar
+                && let Some(position) = layout.vertical_scrollbar_position(coord)
+            {
+                let y = layout
+                    .vertical_scrollbar()
+                    .offset_at(position, layout.max_offset.height());
+                self.offset = offset.set_height(y);
+                return Default::default();
+            }
+            if self.horizontal_scrollbar
+                && let Some(position) = layout.horizontal_scrollbar_position(coord)
+            {
+                let x = layout
+                    .horizontal_scrollbar()
+                    .offset_at(position, layout.max_offset.width());
+                self.offset = offset.set_width(x);
+                return Default::default();
+            }
+        }
+        if coord.is_valid(viewport) {
+            self.component.update(state, layout.component_ctx, event)
+        } else {
+            Default::default()
+        }
+    }
+    fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
+        let layout = self.layout(state, ctx);
+        (layout.content + self.scrollbars_size()).pairwise_min(ctx.bounding_box.size())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{stack::VStack, test_util::*};
+
+    fn size() -> UCoord {
+        UCoord::new(4, 4)
+    }
+
+    /// Eight rows of three cells each, labelled 'a' to 'h', in a window of four rows
+    fn scroll() -> Scroll<VStack<Vec<Block>, ()>> {
+        let rows = ('a'..='h')
+            .map(|character| block(character, 3, 1))
+            .collect();
+        Scroll {
+            scrollbar_style: ScrollbarStyle {
+                track: RenderCell::BLANK.with_character('|'),
+                thumb: RenderCell::BLANK.with_character('#'),
+            },
+            ..Scroll::new(VStack::new(rows))
+        }
+    }
+
+    fn send<C: Component<State = ()>>(component: &mut C, input: input::Input) -> C::Output {
+        component.update(
+            &mut (),
+            Ctx::default_with_bounding_box_size(size()),
+            Event::Input(input),
+        )
+    }
+
+    fn key(component: &mut Scroll<VStack<Vec<Block>, ()>>, key: input::KeyboardInput) {
+        send(component, input::Input::Keyboard(key));
+    }
+
+    fn mouse(component: &mut Scroll<VStack<Vec<Block>, ()>>, mouse_input: input::MouseInput) {
+        send(component, input::Input::Mouse(mouse_input));
+    }
+
+    #[test]
+    fn scrollbar_thumb() {
+        let scrollbar = Scrollbar::new(4, 8, 0, 4);
+        assert_eq!((scrollbar.thumb_start, scrollbar.thumb_length), (0, 2));
+        assert_eq!(Scrollbar::new(4, 8, 2, 4).thumb_start, 1);
+        assert_eq!(Scrollbar::new(4, 8, 4, 4).thumb_start, 2);
+        // the thumb is never shorter than a cell
+        assert_eq!(Scrollbar::new(4, 100, 0, 96).thumb_length, 1);
+        // the thumb fills the track if there's nothing to scroll
+        let scrollbar = Scrollbar::new(4, 3, 0, 0);
+        assert_eq!((scrollbar.thumb_start, scrollbar.thumb_length), (0, 4));
+        assert_eq!(scrollbar.offset_at(2, 0), 0);
+    }
+
+    #[test]
+    fn scrollbar_offset_at() {
+        let scrollbar = Scrollbar::new(4, 8, 0, 4);
+        assert_eq!(scrollbar.offset_at(0, 4), 0);
+        assert_eq!(scrollbar.offset_at(1, 4), 0);
+        assert_eq!(scrollbar.offset_at(2, 4), 2);
+        assert_eq!(scrollbar.offset_at(3, 4), 4);
+    }
+
+    #[test]
+    fn clip_to_viewport() {
+        let mut scroll = scroll();
+        assert_eq!(render(&scroll, size()), "aaa#\nbbb#\nccc|\nddd|\n");
+        scroll.offset = UCoord::new(0, 2);
+        assert_eq!(render(&scroll, size()), "ccc|\nddd#\neee#\nfff|\n");
+        assert_eq!(
+            scroll.size(&(), Ctx::default_with_bounding_box_size(size())),
+            size()
+        );
+    }
+
+    #[test]
+    fn clamp_to_max_offset() {
+        let mut scroll = scroll();
+        scroll.offset = UCoord::new(5, 100);
+        assert_eq!(render(&scroll, size()), "eee|\nfff|\nggg#\nhhh#\n");
+        // the offset is clamped when scrolling
+        key(&mut scroll, input::KeyboardInput::PageUp);
+        assert_eq!(scroll.offset, UCoord::new(0, 0));
+        scroll.offset = UCoord::new(0, 100);
+        mouse(
+            &mut scroll,
+            input::MouseInput::MouseScroll {
+                direction: input::ScrollDirection::Down,
+                coord: ICoord::new(0, 0),
+            },
+        );
+        assert_eq!(scroll.offset, UCoord::new(0, 4));
+    }
+
+    #[test]
+    fn keys() {
+        use input::KeyboardInput::*;
+        let mut scroll = scroll();
+        key(&mut scroll, PageDown);
+        assert_eq!(scroll.offset, UCoord::new(0, 4));
+        key(&mut scroll, PageDown);
+        assert_eq!(scroll.offset, UCoord::new(0, 4));
+        key(&mut scroll, PageUp);
+        assert_eq!(scroll.offset, UCoord::new(0, 0));
+        key(&mut scroll, End);
+        assert_eq!(scroll.offset, UCoord::new(0, 4));
+        key(&mut scroll, Home);
+        assert_eq!(scroll.offset, UCoord::new(0, 0));
+    }
+
+    #[test]
+    fn wheel() {
+        let mut scroll = scroll();
+        let wheel = |direction, coord| input::MouseInput::MouseScroll { direction, coord };
+        mouse(
+            &mut scroll,
+            wheel(input::ScrollDirection::Down, ICoord::new(1, 1)),
+        );
+        mouse(
+            &mut scroll,
+            wheel(input::ScrollDirection::Down, ICoord::new(1, 1)),
+        );
+        assert_eq!(scroll.offset, UCoord::new(0, 2));
+        mouse(
+            &mut scroll,
+            wheel(input::ScrollDirection::Up, ICoord::new(1, 1)),
+        );
+        assert_eq!(scroll.offset, UCoord::new(0, 1));
+        // scrolling outside the scroll container is ignored
+        mouse(
+            &mut scroll,
+            wheel(input::ScrollDirection::Down, ICoord::new(1, 5)),
+        );
+        assert_eq!(scroll.offset, UCoord::new(0, 1));
+    }
+
+    #[test]
+    fn drag_scrollbar() {
+        let mut scroll = scroll();
+        assert_eq!(press(&mut scroll, size(), ICoord::new(3, 3)), None);
+        assert_eq!(scroll.offset, UCoord::new(0, 4));
+        mouse(
+            &mut scroll,
+            input::MouseInput::MouseMove {
+                button: Some(input::MouseButton::Left),
+                coord: ICoord::new(3, 2),
+            },
+        );
+        assert_eq!(scroll.offset, UCoord::new(0, 2));
+        // moving the mouse over the scrollbar without a button held doesn't scroll
+        mouse(
+            &mut scroll,
+            input::MouseInput::MouseMove {
+                button: None,
+                coord: ICoord::new(3, 0),
+            },
+        );
+        assert_eq!(scroll.offset, UCoord::new(0, 2));
+    }
+
+    #[test]
+    fn translate_mouse_coordinates() {
+        let mut scroll = scroll();
+        scroll.offset = UCoord::new(0, 2);
+        assert_eq!(
+            press(&mut scroll, size(), ICoord::new(1, 1)),
+            Some(('d', ICoord::new(1, 0)))
+        );
+        assert_eq!(
+            press(&mut scroll, size(), ICoord::new(2, 3)),
+            Some(('f', ICoord::new(2, 0)))
+        );
+        // outside the scroll container
+        assert_eq!(press(&mut scroll, size(), ICoord::new(1, 4)), None);
+    }
+}
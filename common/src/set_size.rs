@@ -41,6 +41,10 @@ impl<C: Component> Component for SetWidth<C> {
         let ctx = ctx.set_width(self.width);
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        let ctx = ctx.set_width(self.width);
+        self.component.size_constraints(state, ctx)
+    }
 }
 
 pub struct SetHeight<C: Component> {
@@ -63,4 +67,8 @@ impl<C: Component> Component for SetHeight<C> {
         let ctx = ctx.set_height(self.height);
         self.component.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        let ctx = ctx.set_height(self.height);
+        self.component.size_constraints(state, ctx)
+    }
 }
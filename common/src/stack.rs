@@ -0,0 +1,313 @@
+use crate::{
+    align::{AlignmentX, AlignmentY},
+    container::{Axis, ContainerOutput},
+};
+use chargrid_core::*;
+use std::marker::PhantomData;
+
+/// Lays out its children from left to right, with `spacing` columns between adjacent children.
+/// Each child is given a bounding box of the size it reports, aligned vertically within the
+/// height of the tallest child. Events and outputs are routed as described in the
+/// `container` module.
+pub struct HStack<I, S> {
+    pub children: I,
+    pub spacing: u32,
+    pub alignment: AlignmentY,
+    state: PhantomData<S>,
+}
+
+/// Lays out its children from top to bottom, with `spacing` rows between adjacent children.
+/// Each child is given a bounding box of the size it reports, aligned horizontally within the
+/// width of the widest child. Events and outputs are routed as described in the
+/// `container` module.
+pub struct VStack<I, S> {
+    pub children: I,
+    pub spacing: u32,
+    pub alignment: AlignmentX,
+    state: PhantomData<S>,
+}
+
+impl<I, S> HStack<I, S> {
+    pub fn new(children: I) -> Self {
+        Self {
+            children,
+            spacing: 0,
+            alignment: AlignmentY::Top,
+            state: PhantomData,
+        }
+    }
+
+    pub fn with_spacing(self, spacing: u32) -> Self {
+        Self { spacing, ..self }
+    }
+
+    pub fn with_alignment(self, alignment: AlignmentY) -> Self {
+        Self { alignment, ..self }
+    }
+
+    fn layout(&self) -> Layout {
+        let cross_alignment = match self.alignment {
+            AlignmentY::Top => CrossAlignment::Start,
+            AlignmentY::Centre => CrossAlignment::Centre,
+            AlignmentY::Bottom => CrossAlignment::End,
+        };
+        Layout {
+            axis: Axis::Horizontal,
+            spacing: self.spacing,
+            cross_alignment,
+        }
+    }
+}
+
+impl<I, S> VStack<I, S> {
+    pub fn new(children: I) -> Self {
+        Self {
+            children,
+            spacing: 0,
+            alignment: AlignmentX::Left,
+            state: PhantomData,
+        }
+    }
+
+    pub fn with_spacing(self, spacing: u32) -> Self {
+        Self { spacing, ..self }
+    }
+
+    pub fn with_alignment(self, alignment: AlignmentX) -> Self {
+        Self { alignment, ..self }
+    }
+
+    fn layout(&self) -> Layout {
+        let cross_alignment = match self.alignment {
+            AlignmentX::Left => CrossAlignment::Start,
+            AlignmentX::Centre => CrossAlignment::Centre,
+            AlignmentX::Right => CrossAlignment::End,
+        };
+        Layout {
+            axis: Axis::Vertical,
+            spacing: self.spacing,
+            cross_alignment,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum CrossAlignment {
+    Start,
+    Centre,
+    End,
+}
+
+/// The layout shared by `HStack` and `VStack`, expressed in terms of the axis along which
+/// children are placed
+struct Layout {
+    axis: Axis,
+    spacing: u32,
+    cross_alignment: CrossAlignment,
+}
+
+impl Layout {
+    /// Returns the context with which to render or update each child, and the size of the stack
+    fn child_ctxs<'a, 'b, C: 'b + Component>(
+        &self,
+        children: impl IntoIterator<Item = &'b C>,
+        state: &C::State,
+        ctx: Ctx<'a>,
+    ) -> (Vec<Ctx<'a>>, UCoord) {
+        let mut main_offsets_and_sizes = Vec::new();
+        let mut main_offset = 0;
+        for child in children {
+            // each child is sized with the space remaining after the children before it
+            let size = child.size(state, ctx.add_offset(self.axis.offset(main_offset, 0)));
+            main_offsets_and_sizes.push((main_offset, size));
+            main_offset += self.axis.main(size) + self.spacing;
+        }
+        let main_size = main_offset.saturating_sub(self.spacing);
+        let cross_size = main_offsets_and_sizes
+            .iter()
+            .map(|&(_, size)| self.axis.cross(size))
+            .max()
+            .unwrap_or(0);
+        let child_ctxs = main_offsets_and_sizes
+            .into_iter()
+            .map(|(main_offset, size)| {
+                let cross_space = cross_size - self.axis.cross(size);
+                let cross_offset = match self.cross_alignment {
+                    CrossAlignment::Start => 0,
+                    CrossAlignment::Centre => cross_space / 2,
+                    CrossAlignment::End => cross_space,
+                };
+                ctx.add_offset(self.axis.offset(main_offset, cross_offset))
+                    .set_size(size)
+            })
+            .collect();
+        (child_ctxs, self.axis.size(main_size, cross_size))
+    }
+
+    fn render<'b, I, C>(&self, children: &'b I, state: &C::State, ctx: Ctx, fb: &mut FrameBuffer)
+    where
+        C: 'b + Component,
+        &'b I: IntoIterator<Item = &'b C>,
+    {
+        let (child_ctxs, _) = self.child_ctxs(children, state, ctx);
+        for (child, child_ctx) in children.into_iter().zip(child_ctxs) {
+            child.render(state, child_ctx, fb);
+        }
+    }
+
+    fn update<I, C>(
+        &self,
+        children: &mut I,
+        state: &mut C::State,
+        ctx: Ctx,
+        event: Event,
+    ) -> C::Output
+    where
+        C: Component,
+        C::Output: ContainerOutput,
+        for<'b> &'b I: IntoIterator<Item = &'b C>,
+        for<'b> &'b mut I: IntoIterator<Item = &'b mut C>,
+    {
+        let (child_ctxs, _) = self.child_ctxs(&*children, state, ctx);
+        let mouse_coord = event.mouse_input().map(|mouse_input| mouse_input.coord());
+        let mut output = C::Output::empty();
+        for (child, child_ctx) in children.into_iter().zip(child_ctxs) {
+            if let Some(mouse_coord) = mouse_coord
+                && !child_ctx.contains_coord(mouse_coord)
+            {
+                continue;
+            }
+            output = output.or_output(child.update(state, child_ctx, event));
+        }
+        output
+    }
+}
+
+impl<I, S, C> Component for HStack<I, S>
+where
+    C: Component<State = S>,
+    C::Output: ContainerOutput,
+    for<'a> &'a I: IntoIterator<Item = &'a C>,
+    for<'a> &'a mut I: IntoIterator<Item = &'a mut C>,
+{
+    type Output = C::Output;
+    type State = S;
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        self.layout().render(&self.children, state, ctx, fb);
+    }
+    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
+        self.layout().update(&mut self.children, state, ctx, event)
+    }
+    fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
+        self.layout().child_ctxs(&self.children, state, ctx).1
+    }
+}
+
+impl<I, S, C> Component for VStack<I, S>
+where
+    C: Component<State = S>,
+    C::Output: ContainerOutput,
+    for<'a> &'a I: IntoIterator<Item = &'a C>,
+    for<'a> &'a mut I: IntoIterator<Item = &'a mut C>,
+{
+    type Output = C::Output;
+    type State = S;
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        self.layout().render(&self.children, state, ctx, fb);
+    }
+    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
+        self.layout().update(&mut self.children, state, ctx, event)
+    }
+    fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
+        self.layout().child_ctxs(&self.children, state, ctx).1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_util::*, text::StyledString};
+
+    fn hstack(alignment: AlignmentY) -> HStack<Vec<Block>, ()> {
+        HStack::new(vec![block('a', 2, 1), block('b', 1, 3), block('c', 3, 2)])
+            .with_spacing(1)
+            .with_alignment(alignment)
+    }
+
+    #[test]
+    fn hstack_placement() {
+        let size = UCoord::new(10, 4);
+        assert_eq!(
+            render(&hstack(AlignmentY::Top), size),
+            "aa b ccc\n   b ccc\n   b\n\n"
+        );
+        assert_eq!(
+            hstack(AlignmentY::Top).size(&(), Ctx::default_with_bounding_box_size(size)),
+            UCoord::new(8, 3)
+        );
+    }
+
+    #[test]
+    fn hstack_alignment() {
+        let size = UCoord::new(10, 4);
+        assert_eq!(
+            render(&hstack(AlignmentY::Centre), size),
+            "   b ccc\naa b ccc\n   b\n\n"
+        );
+        assert_eq!(
+            render(&hstack(AlignmentY::Bottom), size),
+            "   b\n   b ccc\naa b ccc\n\n"
+        );
+    }
+
+    #[test]
+    fn vstack_placement_and_alignment() {
+        let size = UCoord::new(6, 6);
+        let children = || vec![block('a', 2, 1), block('b', 4, 1), block('c', 1, 2)];
+        assert_eq!(
+            render(&VStack::new(children()).with_spacing(1), size),
+            "aa\n\nbbbb\n\nc\nc\n"
+        );
+        assert_eq!(
+            render(
+                &VStack::new(children()).with_alignment(AlignmentX::Centre),
+                size
+            ),
+            " aa\nbbbb\n c\n c\n\n\n"
+        );
+        assert_eq!(
+            render(
+                &VStack::new(children()).with_alignment(AlignmentX::Right),
+                size
+            ),
+            "  aa\nbbbb\n   c\n   c\n\n\n"
+        );
+    }
+
+    #[test]
+    fn mouse_routing() {
+        let size = UCoord::new(10, 4);
+        let mut stack = hstack(AlignmentY::Bottom);
+        assert_eq!(
+            press(&mut stack, size, ICoord::new(6, 2)),
+            Some(('c', ICoord::new(1, 1)))
+        );
+        assert_eq!(
+            press(&mut stack, size, ICoord::new(3, 0)),
+            Some(('b', ICoord::new(0, 0)))
+        );
+        // between children
+        assert_eq!(press(&mut stack, size, ICoord::new(2, 2)), None);
+        // above a child which is shorter than the stack
+        assert_eq!(press(&mut stack, size, ICoord::new(0, 1)), None);
+    }
+
+    #[test]
+    fn children_without_output() {
+        let text = |string: &str| StyledString::plain_text(string.to_string());
+        let mut stack = VStack::new(vec![text("ab"), text("c")]);
+        let size = UCoord::new(2, 2);
+        assert_eq!(render(&stack, size), "ab\nc\n");
+        let () = press(&mut stack, size, ICoord::new(0, 0));
+    }
+}
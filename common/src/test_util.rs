@@ -0,0 +1,93 @@
+//! Components and helpers for testing layout components
+
+use chargrid_core::*;
+use chargrid_headless::snapshot;
+
+/// Fills a rectangle of a fixed size with a character. Yields the character and the coordinate
+/// (relative to the component) of any mouse press it receives.
+pub struct Block {
+    pub character: char,
+    pub size: UCoord,
+}
+
+pub fn block(character: char, width: u32, height: u32) -> Block {
+    Block {
+        character,
+        size: UCoord::new(width, height),
+    }
+}
+
+impl Component for Block {
+    type Output = Option<(char, ICoord)>;
+    type State = ();
+    fn render(&self, _state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        for y in 0..self.size.height() {
+            for x in 0..self.size.width() {
+                fb.set_cell_relative_to_ctx(
+                    ctx,
+                    ICoord::new(x as i32, y as i32),
+                    0,
+                    RenderCell::BLANK.with_character(self.character),
+                );
+            }
+        }
+    }
+    fn update(&mut self, _state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
+        if let Some(input::MouseInput::MousePress { coord, .. }) = event.mouse_input() {
+            ctx.coord_absolute_to_relative(coord)
+                .map(|coord| (self.character, coord))
+        } else {
+            None
+        }
+    }
+    fn size(&self, _state: &Self::State, _ctx: Ctx) -> UCoord {
+        self.size
+    }
+}
+
+/// Fills its whole bounding box with a character, and fills any space given to it by a layout
+/// container. Yields the character and the coordinate (relative to the component) of any mouse
+/// press it receives.
+pub struct Filler(pub char);
+
+impl Component for Filler {
+    type Output = Option<(char, ICoord)>;
+    type State = ();
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        let size = ctx.bounding_box.size();
+        block(self.0, size.width(), size.height()).render(state, ctx, fb);
+    }
+    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
+        block(self.0, 0, 0).update(state, ctx, event)
+    }
+    fn size(&self, _state: &Self::State, ctx: Ctx) -> UCoord {
+        ctx.bounding_box.size()
+    }
+    fn size_constraints(&self, _state: &Self::State, _ctx: Ctx) -> SizeConstraints {
+        SizeConstraints::fill()
+    }
+}
+
+/// Render a component into a frame buffer of the given size and return its characters
+pub fn render<C: Component<State = ()>>(component: &C, size: UCoord) -> String {
+    let mut fb = FrameBuffer::new(size);
+    component.render(&(), Ctx::default_with_bounding_box_size(size), &mut fb);
+    snapshot::text(&fb)
+}
+
+/// Deliver a left mouse press at the given coordinate to a component occupying a frame buffer
+/// of the given size
+pub fn press<C: Component<State = ()>>(
+    component: &mut C,
+    size: UCoord,
+    coord: ICoord,
+) -> C::Output {
+    component.update(
+        &mut (),
+        Ctx::default_with_bounding_box_size(size),
+        Event::Input(input::Input::Mouse(input::MouseInput::MousePress {
+            button: input::MouseButton::Left,
+            coord,
+        })),
+    )
+}
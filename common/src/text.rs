@@ -50,9 +50,10 @@ impl Target for MeasureBounds {
         _ctx: Ctx<'a>,
         coord: ICoord,
         _depth: i8,
-        _render_cell: RenderCell,
+        render_cell: RenderCell,
     ) {
-        self.max_coord.x = self.max_coord.x.max(coord.x);
+        let width = render_cell.character.map(|g| g.width()).unwrap_or(1);
+        self.max_coord.x = self.max_coord.x.max(coord.x + width as i32 - 1);
         self.max_coord.y = self.max_coord.y.max(coord.y);
     }
 }
@@ -65,34 +66,34 @@ impl StyledString {
         }
     }
 
-    fn process_character<T: Target>(
+    fn process_grapheme<T: Target>(
         mut cursor: ICoord,
-        character: char,
+        grapheme: Grapheme,
         style: Style,
         ctx: Ctx,
         fb: &mut T,
     ) -> ICoord {
-        match character {
-            '\n' => {
+        match grapheme.as_str() {
+            "\n" | "\r\n" => {
                 cursor.x = 0;
                 cursor.y += 1;
             }
-            '\r' => cursor.x = 0,
-            other => {
+            "\r" => cursor.x = 0,
+            _ => {
                 let render_cell = RenderCell {
-                    character: Some(other),
+                    character: Some(grapheme),
                     style,
                 };
                 fb.set_cell_relative_to_ctx(ctx, cursor, 0, render_cell);
-                cursor += ICoord::new(1, 0);
+                cursor += ICoord::new(grapheme.width() as i32, 0);
             }
         }
         cursor
     }
 
     fn process<T: Target>(&self, mut cursor: ICoord, ctx: Ctx, fb: &mut T) -> ICoord {
-        for character in self.string.chars() {
-            cursor = Self::process_character(cursor, character, self.style, ctx, fb);
+        for grapheme in graphemes(&self.string) {
+            cursor = Self::process_grapheme(cursor, grapheme, self.style, ctx, fb);
         }
         cursor
     }
@@ -134,26 +135,32 @@ pub struct StyledStringCharWrapped {
 }
 
 impl StyledStringCharWrapped {
-    fn process_character<T: Target>(
+    fn process_grapheme<T: Target>(
         mut cursor: ICoord,
-        character: char,
+        grapheme: Grapheme,
         style: Style,
         ctx: Ctx,
         fb: &mut T,
     ) -> ICoord {
-        match character {
-            '\n' => {
+        match grapheme.as_str() {
+            "\n" | "\r\n" => {
                 cursor.x = 0;
                 cursor.y += 1;
             }
-            '\r' => cursor.x = 0,
-            other => {
+            "\r" => cursor.x = 0,
+            _ => {
+                let width = grapheme.width() as i32;
+                if cursor.x > 0 && cursor.x + width > ctx.bounding_box.size().width() as i32 {
+                    // wide graphemes which would be split by the edge are moved to the next line
+                    cursor.x = 0;
+                    cursor.y += 1;
+                }
                 let render_cell = RenderCell {
-                    character: Some(other),
+                    character: Some(grapheme),
                     style,
                 };
                 fb.set_cell_relative_to_ctx(ctx, cursor, 0, render_cell);
-                cursor += ICoord::new(1, 0);
+                cursor += ICoord::new(width, 0);
                 if cursor.x >= ctx.bounding_box.size().width() as i32 {
                     cursor.x = 0;
                     cursor.y += 1;
@@ -169,8 +176,8 @@ impl StyledStringCharWrapped {
         ctx: Ctx,
         fb: &mut T,
     ) -> ICoord {
-        for character in styled_string.string.chars() {
-            cursor = Self::process_character(cursor, character, styled_string.style, ctx, fb);
+        for grapheme in graphemes(&styled_string.string) {
+            cursor = Self::process_grapheme(cursor, grapheme, styled_string.style, ctx, fb);
         }
         cursor
     }
@@ -234,17 +241,25 @@ impl Component for StyledStringWordWrapped {
 struct WordWrapState {
     cursor: ICoord,
     current_word_buffer: Vec<RenderCell>,
+    /// Number of columns occupied by the graphemes in `current_word_buffer`
+    current_word_width: u32,
 }
 
 impl WordWrapState {
     fn clear(&mut self) {
         self.cursor = ICoord::new(0, 0);
         self.current_word_buffer.clear();
+        self.current_word_width = 0;
+    }
+
+    fn new_line(&mut self) {
+        self.cursor.x = 0;
+        self.cursor.y += 1;
     }
 
-    fn process_character<T: Target>(
+    fn process_grapheme<T: Target>(
         &mut self,
-        character: char,
+        grapheme: Grapheme,
         style: Style,
         ctx: Ctx,
         fb: &mut T,
@@ -252,21 +267,21 @@ impl WordWrapState {
         if ctx.bounding_box.size().width() == 0 {
             return;
         }
-        match character {
-            '\n' => {
+        match grapheme.as_str() {
+            "\n" | "\r\n" => {
                 self.flush(ctx, fb);
                 self.cursor.x = 0;
                 self.cursor.y += 1;
             }
-            '\r' => {
+            "\r" => {
                 self.flush(ctx, fb);
                 self.cursor.x = 0;
             }
-            ' ' => {
+            " " => {
                 self.flush(ctx, fb);
                 if self.cursor.x != 0 {
                     let render_cell = RenderCell {
-                        character: Some(' '),
+                        character: Some(grapheme),
                         style,
                     };
                     fb.set_cell_relative_to_ctx(ctx, self.cursor, 0, render_cell);
@@ -278,24 +293,38 @@ impl WordWrapState {
                     }
                 }
             }
-            other => {
+            _ => {
+                let width = grapheme.width().min(ctx.bounding_box.size().width());
+                // a wide grapheme can overflow the line even though the word so far fits
+                if self.cursor.x as u32 + self.current_word_width + width
+                    > ctx.bounding_box.size().width()
+                {
+                    if self.cursor.x != 0 {
+                        self.new_line();
+                    }
+                    if self.current_word_width + width > ctx.bounding_box.size().width() {
+                        self.flush(ctx, fb);
+                        if self.cursor.x != 0 {
+                            self.new_line();
+                        }
+                    }
+                }
                 let render_cell = RenderCell {
-                    character: Some(other),
+                    character: Some(grapheme),
                     style,
                 };
                 self.current_word_buffer.push(render_cell);
+                self.current_word_width += width;
                 assert!(
-                    self.cursor.x as u32 + self.current_word_buffer.len() as u32
+                    self.cursor.x as u32 + self.current_word_width
                         <= ctx.bounding_box.size().width()
                 );
-                if self.cursor.x as u32 + self.current_word_buffer.len() as u32
-                    == ctx.bounding_box.size().width()
+                if self.cursor.x as u32 + self.current_word_width == ctx.bounding_box.size().width()
                 {
                     if self.cursor.x == 0 {
                         self.flush(ctx, fb);
                     } else {
-                        self.cursor.x = 0;
-                        self.cursor.y += 1;
+                        self.new_line();
                     }
                 }
             }
@@ -303,13 +332,15 @@ impl WordWrapState {
     }
 
     fn flush<T: Target>(&mut self, ctx: Ctx, fb: &mut T) {
+        self.current_word_width = 0;
         if ctx.bounding_box.size().width() == 0 {
             self.current_word_buffer.clear();
             return;
         }
         for render_cell in self.current_word_buffer.drain(..) {
             fb.set_cell_relative_to_ctx(ctx, self.cursor, 0, render_cell);
-            self.cursor.x += 1;
+            let width = render_cell.character.map(|g| g.width()).unwrap_or(1);
+            self.cursor.x += width.min(ctx.bounding_box.size().width()) as i32;
         }
         assert!(self.cursor.x as u32 <= ctx.bounding_box.size().width());
         if self.cursor.x as u32 == ctx.bounding_box.size().width() {
@@ -324,8 +355,8 @@ impl WordWrapState {
         ctx: Ctx,
         fb: &mut T,
     ) {
-        for character in styled_string.string.chars() {
-            self.process_character(character, styled_string.style, ctx, fb);
+        for grapheme in graphemes(&styled_string.string) {
+            self.process_grapheme(grapheme, styled_string.style, ctx, fb);
         }
     }
 }
@@ -456,3 +487,32 @@ impl From<Vec<StyledString>> for Text {
         Text::new(parts)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::render;
+
+    fn size<C: Component<State = ()>>(component: &C, width: u32) -> UCoord {
+        component.size(
+            &(),
+            Ctx::default_with_bounding_box_size(UCoord::new(width, 10)),
+        )
+    }
+
+    #[test]
+    fn word_wrapped_size_follows_string() {
+        let mut component = StyledString::plain_text("ab cd".to_string()).wrap_word();
+        assert_eq!(size(&component, 3), UCoord::new(3, 2));
+        assert_eq!(size(&component, 10), UCoord::new(5, 1));
+        component.styled_string.string.push_str(" efg");
+        assert_eq!(size(&component, 10), UCoord::new(9, 1));
+    }
+
+    #[test]
+    fn word_wrapped_wide_characters() {
+        let component = StyledString::plain_text("ab 日本語".to_string()).wrap_word();
+        assert_eq!(size(&component, 5), UCoord::new(4, 3));
+        assert_eq!(render(&component, UCoord::new(5, 3)), "ab\n日本\n語\n");
+    }
+}
@@ -1,33 +1,81 @@
 use chargrid_core::*;
 
+/// How a text field displays the position of its cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextFieldCursor {
+    /// Paint the background of the cell at the cursor with the given colour
+    Painted(Rgba32),
+    /// Request that the frontend display its own cursor (e.g. the terminal's cursor) with the
+    /// given shape. Only one cursor can be displayed at a time, so when there are several text
+    /// fields this is best used for whichever has focus.
+    Hardware(CursorShape),
+}
+
+impl Default for TextFieldCursor {
+    fn default() -> Self {
+        Self::Painted(Rgba32::new_grey(63))
+    }
+}
+
 pub struct TextField {
-    text: Vec<char>,
+    text: Vec<Grapheme>,
     width: u32,
     cursor_position: usize,
-    cursor_rgba32: Rgba32,
+    cursor: TextFieldCursor,
     text_style: Style,
 }
 
 impl TextField {
     pub fn with_initial_string(width: u32, string: String) -> Self {
-        let text = string.chars().collect::<Vec<_>>();
+        let text = graphemes(&string).collect::<Vec<_>>();
         Self {
             width,
             cursor_position: text.len(),
-            cursor_rgba32: Rgba32::new_grey(63),
+            cursor: TextFieldCursor::default(),
             text_style: Style::plain_text(),
             text,
         }
     }
 
+    pub fn with_cursor(self, cursor: TextFieldCursor) -> Self {
+        Self { cursor, ..self }
+    }
+
+    /// Change how the cursor is displayed, e.g. to request the hardware cursor when the field
+    /// gains focus
+    pub fn set_cursor(&mut self, cursor: TextFieldCursor) {
+        self.cursor = cursor;
+    }
+
     fn add_character(&mut self, ch: char) {
+        self.add_str(ch.encode_utf8(&mut [0; 4]));
+    }
+
+    /// Insert a string at the cursor all at once, so that pasted text containing newlines doesn't
+    /// cause the field to be submitted. Line breaks and tabs are replaced with spaces (so that
+    /// pasted lines don't run together) and other control characters are dropped. The grapheme
+    /// before the cursor is segmented along with the new text, so that e.g. a combining accent is
+    /// joined onto the character before it.
+    fn add_str(&mut self, s: &str) {
         assert!(self.cursor_position <= self.text.len());
-        if self.cursor_position == self.text.len() {
-            self.text.push(ch);
-        } else {
-            self.text.insert(self.cursor_position, ch);
+        let start = self.cursor_position.saturating_sub(1);
+        let mut string = self.text[start..self.cursor_position]
+            .iter()
+            .map(Grapheme::as_str)
+            .collect::<String>();
+        let mut chars = s.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\r' if chars.peek() == Some(&'\n') => (),
+                ch if ch.is_control() && ch.is_whitespace() => string.push(' '),
+                ch if ch.is_control() => (),
+                ch => string.push(ch),
+            }
         }
-        self.cursor_position += 1;
+        let segmented = graphemes(&string).collect::<Vec<_>>();
+        let num_graphemes = segmented.len();
+        self.text.splice(start..self.cursor_position, segmented);
+        self.cursor_position = start + num_graphemes;
     }
 
     fn backspace(&mut self) {
@@ -45,6 +93,30 @@ impl TextField {
         }
     }
 
+    /// The column at which the grapheme at a given index in the text is drawn, taking into
+    /// account that wide graphemes occupy 2 columns
+    fn column_of_index(&self, index: usize) -> u32 {
+        self.text[..index].iter().map(Grapheme::width).sum()
+    }
+
+    /// The index of the grapheme drawn at a given column, or the length of the text if the
+    /// column is immediately after the end of the text
+    fn index_of_column(&self, column: u32) -> Option<usize> {
+        let mut start = 0;
+        for (i, grapheme) in self.text.iter().enumerate() {
+            let end = start + grapheme.width();
+            if column < end {
+                return Some(i);
+            }
+            start = end;
+        }
+        if column == start {
+            Some(self.text.len())
+        } else {
+            None
+        }
+    }
+
     fn left(&mut self) {
         if self.cursor_position > 0 {
             self.cursor_position -= 1;
@@ -63,26 +135,38 @@ impl Component for TextField {
     type State = ();
 
     fn render(&self, _state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
-        for (i, &ch) in self.text.iter().enumerate().take(self.width as usize) {
+        let mut column = 0;
+        for &grapheme in self.text.iter() {
+            let width = grapheme.width();
+            if column + width > self.width {
+                break;
+            }
             let render_cell = RenderCell {
-                character: Some(ch),
+                character: Some(grapheme),
                 style: self.text_style,
             };
-            fb.set_cell_relative_to_ctx(ctx, ICoord::new(i as i32, 0), 0, render_cell);
+            fb.set_cell_relative_to_ctx(ctx, ICoord::new(column as i32, 0), 0, render_cell);
+            column += width;
+        }
+        let cursor_coord = ICoord::new(self.column_of_index(self.cursor_position) as i32, 0);
+        match self.cursor {
+            TextFieldCursor::Painted(rgba32) => {
+                let cursor_render_cell = RenderCell {
+                    character: None,
+                    style: Style::default().with_background(rgba32),
+                };
+                fb.set_cell_relative_to_ctx(ctx, cursor_coord, 0, cursor_render_cell);
+            }
+            TextFieldCursor::Hardware(shape) => {
+                fb.set_cursor_relative_to_ctx(ctx, Cursor::new(cursor_coord, shape));
+            }
         }
-        let cursor_render_cell = RenderCell {
-            character: None,
-            style: Style::default().with_background(self.cursor_rgba32),
-        };
-        fb.set_cell_relative_to_ctx(
-            ctx,
-            ICoord::new(self.cursor_position as i32, 0),
-            0,
-            cursor_render_cell,
-        );
     }
 
     fn update(&mut self, _state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
+        if let Some(text) = event.paste() {
+            self.add_str(text);
+        }
         if let Some(input) = event.input() {
             use input::*;
             match input {
@@ -93,23 +177,21 @@ impl Component for TextField {
                         button: Some(_),
                     },
                 ) => {
-                    if let Some(coord) = ctx.bounding_box.coord_absolute_to_relative(coord)
-                        && coord.x as usize <= self.text.len()
+                    if let Some(coord) = ctx.coord_absolute_to_relative(coord)
+                        && let Some(index) = self.index_of_column(coord.x as u32)
                     {
-                        self.cursor_position = coord.x as usize;
+                        self.cursor_position = index;
                     }
                 }
                 Input::Keyboard(keyboard_input) => match keyboard_input {
-                    keys::RETURN => return Some(self.text.iter().collect::<String>()),
+                    keys::RETURN => {
+                        return Some(self.text.iter().map(Grapheme::as_str).collect::<String>());
+                    }
                     KeyboardInput::Left => self.left(),
                     KeyboardInput::Right => self.right(),
                     KeyboardInput::Delete => self.delete(),
                     keys::BACKSPACE => self.backspace(),
-                    KeyboardInput::Char(ch) => {
-                        if !ch.is_control() {
-                            self.add_character(ch);
-                        }
-                    }
+                    KeyboardInput::Char(ch) if !ch.is_control() => self.add_character(ch),
                     _ => (),
                 },
                 _ => (),
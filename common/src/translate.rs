@@ -0,0 +1,129 @@
+use chargrid_core::*;
+
+/// Moves a component by an offset without changing its size. Unlike `AddOffset`, the component
+/// may be moved beyond the edges of its bounding box (including by a negative offset), e.g. for
+/// slide-in animations or screen shake. Parts of the component moved outside the clip region
+/// aren't drawn (see `Clip`).
+pub struct Translate<C: Component> {
+    pub component: C,
+    pub offset: ICoord,
+}
+
+impl<C: Component> Component for Translate<C> {
+    type Output = C::Output;
+    type State = C::State;
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        let ctx = ctx.translate(self.offset);
+        self.component.render(state, ctx, fb);
+    }
+    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
+        let ctx = ctx.translate(self.offset);
+        self.component.update(state, ctx, event)
+    }
+    fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
+        let ctx = ctx.translate(self.offset);
+        self.component.size(state, ctx)
+    }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        let ctx = ctx.translate(self.offset);
+        self.component.size_constraints(state, ctx)
+    }
+}
+
+/// Prevents a component from drawing outside its bounding box, and from receiving mouse
+/// coordinates outside its bounding box through `Ctx::coord_absolute_to_relative`
+pub struct Clip<C: Component> {
+    pub component: C,
+}
+
+impl<C: Component> Component for Clip<C> {
+    type Output = C::Output;
+    type State = C::State;
+    fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
+        self.component.render(state, ctx.clip_to_bounding_box(), fb);
+    }
+    fn update(&mut self, state: &mut Self::State, ctx: Ctx, event: Event) -> Self::Output {
+        self.component
+            .update(state, ctx.clip_to_bounding_box(), event)
+    }
+    fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
+        self.component.size(state, ctx)
+    }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.component.size_constraints(state, ctx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{add_offset::AddOffset, align::Align, test_util::*};
+
+    fn size() -> UCoord {
+        UCoord::new(5, 3)
+    }
+
+    #[test]
+    fn translate_clipped() {
+        let mut translate = AddOffset {
+            component: Clip {
+                component: Translate {
+                    component: block('a', 3, 2),
+                    offset: ICoord::new(-2, -1),
+                },
+            },
+            offset: ICoord::new(1, 1),
+        };
+        assert_eq!(render(&translate, size()), "\n a\n\n");
+        assert_eq!(
+            press(&mut translate, size(), ICoord::new(1, 1)),
+            Some(('a', ICoord::new(2, 1)))
+        );
+        // covered by the translated block, but outside the bounding box of the offset component
+        assert_eq!(press(&mut translate, size(), ICoord::new(0, 0)), None);
+    }
+
+    #[test]
+    fn decorators_unchanged_by_clip() {
+        let add_offset = || AddOffset {
+            component: block('a', 2, 1),
+            offset: ICoord::new(1, 1),
+        };
+        let align = || Align::centre(block('a', 1, 1));
+        assert_eq!(render(&add_offset(), size()), "\n aa\n\n");
+        assert_eq!(
+            render(
+                &Clip {
+                    component: add_offset()
+                },
+                size()
+            ),
+            render(&add_offset(), size())
+        );
+        assert_eq!(render(&align(), size()), "\n  a\n\n");
+        assert_eq!(
+            render(&Clip { component: align() }, size()),
+            render(&align(), size())
+        );
+        for coord in [ICoord::new(1, 1), ICoord::new(2, 1), ICoord::new(0, 0)] {
+            assert_eq!(
+                press(
+                    &mut Clip {
+                        component: add_offset()
+                    },
+                    size(),
+                    coord
+                ),
+                press(&mut add_offset(), size(), coord)
+            );
+            assert_eq!(
+                press(&mut Clip { component: align() }, size(), coord),
+                press(&mut align(), size(), coord)
+            );
+        }
+        assert_eq!(
+            press(&mut Clip { component: align() }, size(), ICoord::new(2, 1)),
+            Some(('a', ICoord::new(0, 0)))
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use crate::ICoord;
+#[cfg(feature = "serialize")]
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How long blinking cursors and text drawn by a graphical frontend spend in each of their visible
+/// and invisible phases
+pub const BLINK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// True if blinking cursors and text are in the visible phase of their blink at the given time
+/// (measured from an arbitrary fixed point in time)
+pub fn is_blink_visible_at(time: Duration) -> bool {
+    (time.as_millis() / BLINK_INTERVAL.as_millis()).is_multiple_of(2)
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CursorShape {
+    /// Covers the entire cell
+    #[default]
+    Block,
+    /// A thin vertical line at the left edge of the cell
+    Bar,
+    /// A thin horizontal line at the bottom of the cell
+    Underline,
+}
+
+/// A cursor to display at a cell, requested by a component while rendering with
+/// `FrameBuffer::set_cursor`. Terminal frontends move the terminal's own cursor to the cell, and
+/// graphical frontends draw an equivalent caret. Unlike painting the cell to look like a cursor,
+/// this allows screen readers and input method candidate windows to follow the cursor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cursor {
+    pub coord: ICoord,
+    pub shape: CursorShape,
+    pub blink: bool,
+}
+
+impl Cursor {
+    pub const fn new(coord: ICoord, shape: CursorShape) -> Self {
+        Self {
+            coord,
+            shape,
+            blink: false,
+        }
+    }
+
+    pub const fn with_blink(self, blink: bool) -> Self {
+        Self { blink, ..self }
+    }
+
+    /// Helper for frontends which draw their own cursor. Returns false if the cursor blinks and
+    /// is in the invisible phase of its blink at the given time (measured from an arbitrary fixed
+    /// point in time).
+    pub fn is_visible_at(&self, time: Duration) -> bool {
+        !self.blink || is_blink_visible_at(time)
+    }
+}
@@ -0,0 +1,141 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// The maximum number of bytes of utf-8 stored in a `Grapheme`. This is enough for a base
+/// character with several combining marks, or emoji sequences joined with zero-width joiners
+/// (such as families of 4 people).
+pub const GRAPHEME_CAPACITY: usize = 31;
+
+/// A grapheme cluster (the unit of text that a reader perceives as a single character, such as a
+/// letter followed by combining accents) stored inline so that it can be copied like a `char`.
+/// Clusters longer than `GRAPHEME_CAPACITY` bytes are truncated at a character boundary.
+#[derive(Clone, Copy)]
+pub struct Grapheme {
+    bytes: [u8; GRAPHEME_CAPACITY],
+    len: u8,
+}
+
+impl Grapheme {
+    pub const fn from_char(character: char) -> Self {
+        let mut bytes = [0; GRAPHEME_CAPACITY];
+        character.encode_utf8(&mut bytes);
+        Self {
+            bytes,
+            len: character.len_utf8() as u8,
+        }
+    }
+
+    /// Store a string as a grapheme. The string is assumed to be a single grapheme cluster.
+    pub fn new(string: &str) -> Self {
+        let mut bytes = [0; GRAPHEME_CAPACITY];
+        let mut len = 0;
+        for character in string.chars() {
+            let character_len = character.len_utf8();
+            if len + character_len > GRAPHEME_CAPACITY {
+                break;
+            }
+            character.encode_utf8(&mut bytes[len..]);
+            len += character_len;
+        }
+        Self {
+            bytes,
+            len: len as u8,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        // only ever populated with whole utf-8 encoded characters
+        std::str::from_utf8(&self.bytes[..self.len as usize]).unwrap()
+    }
+
+    /// The first character of the grapheme (i.e. its base character)
+    pub fn first_char(&self) -> char {
+        self.as_str().chars().next().unwrap_or(' ')
+    }
+
+    /// True if the grapheme consists of exactly the given character
+    pub fn is_char(&self, character: char) -> bool {
+        let mut chars = self.as_str().chars();
+        chars.next() == Some(character) && chars.next().is_none()
+    }
+
+    /// The number of cells occupied by the grapheme when rendered. This is 2 for wide graphemes
+    /// (see `character_width`) and 1 for all others, including graphemes consisting only of
+    /// zero-width characters.
+    pub fn width(&self) -> u32 {
+        if self.as_str().width() >= 2 { 2 } else { 1 }
+    }
+}
+
+/// Split a string into its grapheme clusters
+pub fn graphemes(string: &str) -> impl Iterator<Item = Grapheme> + '_ {
+    string.graphemes(true).map(Grapheme::new)
+}
+
+impl From<char> for Grapheme {
+    fn from(character: char) -> Self {
+        Self::from_char(character)
+    }
+}
+
+impl From<&str> for Grapheme {
+    fn from(string: &str) -> Self {
+        Self::new(string)
+    }
+}
+
+impl PartialEq for Grapheme {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Grapheme {}
+
+impl PartialOrd for Grapheme {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Grapheme {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for Grapheme {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl fmt::Debug for Grapheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Grapheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl serde::Serialize for Grapheme {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl<'de> serde::Deserialize<'de> for Grapheme {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        Ok(Self::new(&string))
+    }
+}
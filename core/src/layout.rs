@@ -0,0 +1,415 @@
+//! Constraints on the size of a component, for layout containers which divide the space in their
+//! bounding box between their children in the manner of css flexbox. Each child states a
+//! preferred size along each axis, which is first clamped between its minimum and maximum sizes.
+//! Any space left over is then handed out to children in proportion to their grow factors, and
+//! any shortfall is taken from children in proportion to their shrink factors (weighted by their
+//! preferred sizes), without taking any child beyond its minimum or maximum size.
+
+use crate::UCoord;
+
+/// A size along one axis, relative to the space available to a component
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Length {
+    /// A fixed number of cells
+    Cells(u32),
+    /// A percentage of the available space
+    Percent(u32),
+    /// Whatever space remains after other components have been given their preferred sizes.
+    /// Components which fill are given a preferred size of 0 and a grow factor of at least 1.
+    Fill,
+}
+
+/// Constraints on the size of a component along one axis
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Constraint {
+    pub min: u32,
+    pub preferred: Length,
+    pub max: u32,
+    /// The share of any left over space given to the component, relative to other components
+    pub grow: u32,
+    /// The share of any shortfall in space taken from the component, relative to other components
+    pub shrink: u32,
+}
+
+impl Constraint {
+    /// Exactly the given number of cells
+    pub const fn fixed(cells: u32) -> Self {
+        Self {
+            min: cells,
+            preferred: Length::Cells(cells),
+            max: cells,
+            grow: 0,
+            shrink: 0,
+        }
+    }
+
+    /// Prefers the given number of cells, but shrinks if there isn't enough space
+    pub const fn cells(cells: u32) -> Self {
+        Self {
+            min: 0,
+            preferred: Length::Cells(cells),
+            max: u32::MAX,
+            grow: 0,
+            shrink: 1,
+        }
+    }
+
+    /// Prefers a percentage of the available space, but shrinks if there isn't enough space
+    pub const fn percent(percent: u32) -> Self {
+        Self {
+            min: 0,
+            preferred: Length::Percent(percent),
+            max: u32::MAX,
+            grow: 0,
+            shrink: 1,
+        }
+    }
+
+    /// Takes up all remaining space
+    pub const fn fill() -> Self {
+        Self {
+            min: 0,
+            preferred: Length::Fill,
+            max: u32::MAX,
+            grow: 1,
+            shrink: 1,
+        }
+    }
+
+    pub const fn with_min(self, min: u32) -> Self {
+        Self { min, ..self }
+    }
+
+    pub const fn with_max(self, max: u32) -> Self {
+        Self { max, ..self }
+    }
+
+    pub const fn with_grow(self, grow: u32) -> Self {
+        Self { grow, ..self }
+    }
+
+    pub const fn with_shrink(self, shrink: u32) -> Self {
+        Self { shrink, ..self }
+    }
+
+    /// The constraint on a component which is `cells` larger than a component with this
+    /// constraint, such as a border or padding around it
+    pub fn add_cells(self, cells: u32) -> Self {
+        Self {
+            min: self.min.saturating_add(cells),
+            preferred: match self.preferred {
+                Length::Cells(preferred) => Length::Cells(preferred.saturating_add(cells)),
+                other => other,
+            },
+            max: self.max.saturating_add(cells),
+            ..self
+        }
+    }
+
+    /// The constraint on a component which is never larger than `cells`
+    pub fn at_most(self, cells: u32) -> Self {
+        Self {
+            min: self.min.min(cells),
+            preferred: match self.preferred {
+                Length::Cells(preferred) => Length::Cells(preferred.min(cells)),
+                other => other,
+            },
+            max: self.max.min(cells),
+            ..self
+        }
+    }
+
+    /// The constraint on a component which is never smaller than `cells`
+    pub fn at_least(self, cells: u32) -> Self {
+        Self {
+            min: self.min.max(cells),
+            preferred: match self.preferred {
+                Length::Cells(preferred) => Length::Cells(preferred.max(cells)),
+                other => other,
+            },
+            max: self.max.max(cells),
+            ..self
+        }
+    }
+
+    /// Clamp a size between the minimum and maximum sizes. If these conflict, the minimum wins.
+    fn clamp(&self, size: u32) -> u32 {
+        size.min(self.max).max(self.min)
+    }
+
+    /// The preferred size given the available space, clamped between the minimum and maximum
+    pub fn preferred_size(&self, available: u32) -> u32 {
+        let preferred = match self.preferred {
+            Length::Cells(cells) => cells,
+            Length::Percent(percent) => {
+                ((available as u64 * percent as u64) / 100).min(u32::MAX as u64) as u32
+            }
+            Length::Fill => 0,
+        };
+        self.clamp(preferred)
+    }
+
+    fn grow_weight(&self) -> u64 {
+        match self.preferred {
+            Length::Fill => self.grow.max(1) as u64,
+            _ => self.grow as u64,
+        }
+    }
+
+    /// The size of a component given the available space, if it's the only component occupying
+    /// that space
+    pub fn resolve(&self, available: u32) -> u32 {
+        distribute(available, &[*self])[0]
+    }
+}
+
+/// Constraints on the size of a component along both axes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SizeConstraints {
+    pub width: Constraint,
+    pub height: Constraint,
+}
+
+impl SizeConstraints {
+    /// Exactly the given size
+    pub fn fixed(size: UCoord) -> Self {
+        Self {
+            width: Constraint::fixed(size.width()),
+            height: Constraint::fixed(size.height()),
+        }
+    }
+
+    /// Takes up all remaining space along both axes
+    pub const fn fill() -> Self {
+        Self {
+            width: Constraint::fill(),
+            height: Constraint::fill(),
+        }
+    }
+
+    /// See `Constraint::add_cells`
+    pub fn add_size(self, size: UCoord) -> Self {
+        Self {
+            width: self.width.add_cells(size.width()),
+            height: self.height.add_cells(size.height()),
+        }
+    }
+
+    /// See `Constraint::at_most`
+    pub fn at_most(self, size: UCoord) -> Self {
+        Self {
+            width: self.width.at_most(size.width()),
+            height: self.height.at_most(size.height()),
+        }
+    }
+
+    /// See `Constraint::at_least`
+    pub fn at_least(self, size: UCoord) -> Self {
+        Self {
+            width: self.width.at_least(size.width()),
+            height: self.height.at_least(size.height()),
+        }
+    }
+
+    /// The size of a component given the available space, if it's the only component occupying
+    /// that space
+    pub fn resolve(&self, available: UCoord) -> UCoord {
+        UCoord::new(
+            self.width.resolve(available.width()),
+            self.height.resolve(available.height()),
+        )
+    }
+}
+
+/// Divide `amount` in proportion to `weights`. The cells left over from rounding down are handed
+/// out one at a time in decreasing order of the fractions that were rounded away.
+fn divide_proportionally(amount: u64, weights: &[u64]) -> Vec<u64> {
+    let total_weight = weights.iter().sum::<u64>();
+    if total_weight == 0 {
+        return vec![0; weights.len()];
+    }
+    let mut shares = weights
+        .iter()
+        .map(|&weight| (amount as u128 * weight as u128 / total_weight as u128) as u64)
+        .collect::<Vec<_>>();
+    let mut remaining = amount - shares.iter().sum::<u64>();
+    let mut by_remainder = (0..weights.len()).collect::<Vec<_>>();
+    by_remainder.sort_by_key(|&i| {
+        std::cmp::Reverse(amount as u128 * weights[i] as u128 % total_weight as u128)
+    });
+    for i in by_remainder {
+        if remaining == 0 {
+            break;
+        }
+        if weights[i] > 0 {
+            shares[i] += 1;
+            remaining -= 1;
+        }
+    }
+    shares
+}
+
+/// Divide `available` cells along one axis between components with the given constraints,
+/// returning the size of each component. The sizes only fail to add up to `available` if the
+/// components can't grow or shrink enough to fit.
+pub fn distribute(available: u32, constraints: &[Constraint]) -> Vec<u32> {
+    let mut sizes = constraints
+        .iter()
+        .map(|constraint| constraint.preferred_size(available))
+        .collect::<Vec<_>>();
+    let mut frozen = vec![false; constraints.len()];
+    // each iteration either shares out all the remaining space or stops a component that hit its
+    // minimum or maximum size from changing any further, so this terminates
+    loop {
+        let total = sizes.iter().map(|&size| size as u64).sum::<u64>();
+        let growing = total < available as u64;
+        let weights = constraints
+            .iter()
+            .zip(sizes.iter())
+            .zip(frozen.iter())
+            .map(|((constraint, &size), &frozen)| {
+                if frozen {
+                    0
+                } else if growing {
+                    constraint.grow_weight()
+                } else {
+                    constraint.shrink as u64 * size as u64
+                }
+            })
+            .collect::<Vec<_>>();
+        let shares = divide_proportionally(total.abs_diff(available as u64), &weights);
+        if shares.iter().all(|&share| share == 0) {
+            break;
+        }
+        let mut any_clamped = false;
+        for (i, constraint) in constraints.iter().enumerate() {
+            let size = sizes[i] as u64;
+            let size = if growing {
+                size + shares[i]
+            } else {
+                size.saturating_sub(shares[i])
+            };
+            let clamped = constraint.clamp(size.min(u32::MAX as u64) as u32);
+            if clamped as u64 != size {
+                frozen[i] = true;
+                any_clamped = true;
+            }
+            sizes[i] = clamped;
+        }
+        if !any_clamped {
+            break;
+        }
+    }
+    sizes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn grow_weighting() {
+        assert_eq!(
+            distribute(80, &[Constraint::fixed(10), Constraint::fill()]),
+            vec![10, 70]
+        );
+        assert_eq!(
+            distribute(
+                8,
+                &[
+                    Constraint::cells(0).with_grow(1),
+                    Constraint::cells(0).with_grow(3),
+                    Constraint::cells(2),
+                ]
+            ),
+            vec![2, 4, 2]
+        );
+    }
+
+    #[test]
+    fn shrink_weighting() {
+        // the shortfall is taken in proportion to shrink factor times preferred size
+        assert_eq!(
+            distribute(20, &[Constraint::cells(10), Constraint::cells(30)]),
+            vec![5, 15]
+        );
+        assert_eq!(
+            distribute(
+                20,
+                &[Constraint::cells(10).with_shrink(3), Constraint::cells(30)]
+            ),
+            vec![0, 20]
+        );
+        assert_eq!(
+            distribute(20, &[Constraint::fixed(15), Constraint::cells(10)]),
+            vec![15, 5]
+        );
+    }
+
+    #[test]
+    fn min_max_freezing() {
+        assert_eq!(
+            distribute(
+                100,
+                &[
+                    Constraint::percent(25),
+                    Constraint::fill().with_max(5),
+                    Constraint::fill(),
+                ]
+            ),
+            vec![25, 5, 70]
+        );
+        assert_eq!(
+            distribute(
+                70,
+                &[Constraint::cells(30), Constraint::cells(60).with_min(50)]
+            ),
+            vec![20, 50]
+        );
+        assert_eq!(
+            distribute(
+                20,
+                &[
+                    Constraint::fill().with_min(10),
+                    Constraint::fill(),
+                    Constraint::fill(),
+                ]
+            ),
+            vec![14, 3, 3]
+        );
+        // the minimum wins over the maximum
+        assert_eq!(Constraint::cells(5).with_min(8).with_max(4).resolve(10), 8);
+    }
+
+    #[test]
+    fn rounding_remainder() {
+        let fill = Constraint::fill();
+        assert_eq!(distribute(10, &[fill, fill, fill]), vec![4, 3, 3]);
+        assert_eq!(distribute(11, &[fill, fill, fill]), vec![4, 4, 3]);
+        // the cell left over goes to the component whose share was rounded down the most
+        assert_eq!(
+            distribute(
+                10,
+                &[
+                    Constraint::cells(0).with_grow(1),
+                    Constraint::cells(0).with_grow(2),
+                ]
+            ),
+            vec![3, 7]
+        );
+    }
+
+    #[test]
+    fn percent_overflow() {
+        assert_eq!(
+            distribute(100, &[Constraint::percent(60), Constraint::percent(60)]),
+            vec![50, 50]
+        );
+        assert_eq!(Constraint::percent(200).resolve(u32::MAX), u32::MAX);
+        // components which can't shrink overflow the available space
+        assert_eq!(
+            distribute(50, &[Constraint::fixed(100), Constraint::cells(10)]),
+            vec![100, 0]
+        );
+    }
+}
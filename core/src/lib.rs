@@ -1,12 +1,27 @@
 pub use chargrid_input as input;
 use grid_2d::Grid;
 pub use grid_2d::{ICoord, UCoord};
-use input::{Input, InputPolicy, KeyboardInput, MouseInput};
+use input::{Input, InputPolicy, KeyEvent, KeyboardInput, MouseInput};
 pub use rgb_int;
 pub use rgb_int::Rgba32;
 #[cfg(feature = "serialize")]
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
+use unicode_width::UnicodeWidthChar;
+
+mod cursor;
+mod grapheme;
+pub mod layout;
+pub use cursor::{BLINK_INTERVAL, Cursor, CursorShape, is_blink_visible_at};
+pub use grapheme::{GRAPHEME_CAPACITY, Grapheme, graphemes};
+pub use layout::{Constraint, Length, SizeConstraints};
+
+/// The number of cells occupied by a character when rendered. East Asian wide and fullwidth
+/// characters (such as CJK ideographs and most emoji) occupy 2 cells, and all other characters
+/// occupy 1 cell.
+pub fn character_width(character: char) -> u32 {
+    if character.width() == Some(2) { 2 } else { 1 }
+}
 
 #[derive(Clone, Copy, Debug)]
 pub struct BoundingBox {
@@ -79,6 +94,15 @@ impl BoundingBox {
         self.add_offset(ICoord { x, y })
     }
 
+    /// Move the whole bounding box by the specified offset without changing its size. Unlike
+    /// `add_offset`, the bounding box may be moved beyond its original bottom-right corner.
+    pub fn translate(self, offset: ICoord) -> Self {
+        Self {
+            top_left: self.top_left + offset,
+            bottom_right: self.bottom_right + offset,
+        }
+    }
+
     pub fn constrain_size_by(self, by: ICoord) -> Self {
         let bottom_right = ICoord {
             x: (self.bottom_right.x - by.x).max(self.top_left.x),
@@ -112,13 +136,35 @@ impl BoundingBox {
     pub fn contains_coord(&self, coord: ICoord) -> bool {
         (coord - self.top_left).is_valid(self.size())
     }
+
+    /// The region covered by both bounding boxes. If they don't overlap, the result is empty.
+    pub fn intersection(self, other: Self) -> Self {
+        let top_left = self.top_left.pairwise_max(other.top_left);
+        let bottom_right = self
+            .bottom_right
+            .pairwise_min(other.bottom_right)
+            .pairwise_max(top_left);
+        Self {
+            top_left,
+            bottom_right,
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
 pub struct FrameBufferCell {
-    pub character: char,
+    pub character: Grapheme,
     pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
     pub underline: bool,
+    /// Only relevant if `underline` is set
+    pub underline_style: UnderlineStyle,
+    /// The colour of the underline, or `None` to draw it in the foreground colour
+    pub underline_colour: Option<Rgba32>,
+    pub blink: bool,
+    pub reverse: bool,
+    pub strikethrough: bool,
     pub foreground: Rgba32,
     pub background: Rgba32,
     foreground_depth: i8,
@@ -130,19 +176,64 @@ pub type FrameBufferEnumerate<'a> = grid_2d::GridEnumerate<'a, FrameBufferCell>;
 pub type FrameBufferRows<'a> = grid_2d::GridRows<'a, FrameBufferCell>;
 
 impl FrameBufferCell {
+    /// The character of the cell to the right of a wide character, which is covered by the right
+    /// half of the wide character. Frontends should not draw anything in such cells.
+    pub const CONTINUATION: Grapheme = Grapheme::from_char('\0');
+
     const BLANK: Self = Self {
-        character: ' ',
+        character: Grapheme::from_char(' '),
         bold: false,
+        dim: false,
+        italic: false,
         underline: false,
+        underline_style: UnderlineStyle::Single,
+        underline_colour: None,
+        blink: false,
+        reverse: false,
+        strikethrough: false,
         foreground: Rgba32::new_rgb(255, 255, 255),
         background: Rgba32::new_rgb(0, 0, 0),
         foreground_depth: i8::MIN,
         background_depth: i8::MIN,
     };
-    fn set_character(&mut self, character: char, depth: i8) {
+
+    /// True if this cell is covered by the right half of a wide character in the cell to its left
+    pub fn is_continuation(&self) -> bool {
+        self.character == Self::CONTINUATION
+    }
+
+    /// Helper for frontends which draw their own text. Returns the colours with which to draw
+    /// the cell at the given time (measured from an arbitrary fixed point in time), taking
+    /// `reverse`, `dim` and `blink` into account. Blinking text is drawn in the background colour
+    /// during the invisible phase of its blink.
+    pub fn display_colours_at(&self, time: Duration) -> DisplayColours {
+        let (mut foreground, background) = if self.reverse {
+            (self.background, self.foreground)
+        } else {
+            (self.foreground, self.background)
+        };
+        if self.dim {
+            foreground = foreground.linear_interpolate(background, 127);
+        }
+        let mut underline = self.underline_colour.unwrap_or(foreground);
+        if self.blink && !is_blink_visible_at(time) {
+            foreground = background;
+            underline = background;
+        }
+        DisplayColours {
+            foreground,
+            background,
+            underline,
+        }
+    }
+
+    fn set_character(&mut self, character: Grapheme, depth: i8) -> bool {
         if depth >= self.foreground_depth {
             self.character = character;
             self.foreground_depth = depth;
+            true
+        } else {
+            false
         }
     }
     fn set_bold(&mut self, bold: bool, depth: i8) {
@@ -151,12 +242,54 @@ impl FrameBufferCell {
             self.foreground_depth = depth;
         }
     }
+    fn set_dim(&mut self, dim: bool, depth: i8) {
+        if depth >= self.foreground_depth {
+            self.dim = dim;
+            self.foreground_depth = depth;
+        }
+    }
+    fn set_italic(&mut self, italic: bool, depth: i8) {
+        if depth >= self.foreground_depth {
+            self.italic = italic;
+            self.foreground_depth = depth;
+        }
+    }
     fn set_underline(&mut self, underline: bool, depth: i8) {
         if depth >= self.foreground_depth {
             self.underline = underline;
             self.foreground_depth = depth;
         }
     }
+    fn set_underline_style(&mut self, underline_style: UnderlineStyle, depth: i8) {
+        if depth >= self.foreground_depth {
+            self.underline_style = underline_style;
+            self.foreground_depth = depth;
+        }
+    }
+    fn set_underline_colour(&mut self, underline_colour: Option<Rgba32>, depth: i8) {
+        if depth >= self.foreground_depth {
+            self.underline_colour = underline_colour;
+            self.foreground_depth = depth;
+        }
+    }
+    fn set_blink(&mut self, blink: bool, depth: i8) {
+        if depth >= self.foreground_depth {
+            self.blink = blink;
+            self.foreground_depth = depth;
+        }
+    }
+    fn set_reverse(&mut self, reverse: bool, depth: i8) {
+        if depth >= self.foreground_depth {
+            self.reverse = reverse;
+            self.foreground_depth = depth;
+        }
+    }
+    fn set_strikethrough(&mut self, strikethrough: bool, depth: i8) {
+        if depth >= self.foreground_depth {
+            self.strikethrough = strikethrough;
+            self.foreground_depth = depth;
+        }
+    }
     fn set_foreground(&mut self, foreground: Rgba32, depth: i8) {
         if depth >= self.foreground_depth {
             self.foreground = foreground;
@@ -173,12 +306,14 @@ impl FrameBufferCell {
 
 pub struct FrameBuffer {
     grid: Grid<FrameBufferCell>,
+    cursor: Option<Cursor>,
 }
 
 impl FrameBuffer {
     pub fn new(size: UCoord) -> Self {
         Self {
             grid: Grid::new_copy(size, FrameBufferCell::BLANK),
+            cursor: None,
         }
     }
 
@@ -188,6 +323,7 @@ impl FrameBuffer {
 
     pub fn resize(&mut self, size: UCoord) {
         self.grid = Grid::new_copy(size, FrameBufferCell::BLANK);
+        self.cursor = None;
     }
 
     pub fn clear_with_background(&mut self, background: Rgba32) {
@@ -203,6 +339,30 @@ impl FrameBuffer {
         for cell in self.grid.iter_mut() {
             *cell = FrameBufferCell::BLANK;
         }
+        self.cursor = None;
+    }
+
+    /// The cursor requested while rendering the current frame, if any
+    pub fn cursor(&self) -> Option<Cursor> {
+        self.cursor
+    }
+
+    /// Request that a cursor be displayed at the given (absolute) coordinate. The cursor is
+    /// hidden again at the start of each frame, so components must request it every time they
+    /// render. If several components request a cursor in the same frame, the last request wins.
+    /// Requests outside the frame buffer are ignored.
+    pub fn set_cursor(&mut self, cursor: Cursor) {
+        if cursor.coord.is_valid(self.size()) {
+            self.cursor = Some(cursor);
+        }
+    }
+
+    pub fn clear_cursor(&mut self) {
+        self.cursor = None;
+    }
+
+    pub fn get(&self, coord: ICoord) -> Option<&FrameBufferCell> {
+        self.grid.get(coord)
     }
 
     pub fn enumerate(&self) -> FrameBufferEnumerate<'_> {
@@ -217,19 +377,102 @@ impl FrameBuffer {
         self.grid.rows()
     }
 
-    pub fn set_cell(&mut self, coord: ICoord, depth: i8, render_cell: RenderCell) {
+    /// Update a cell in the frame buffer. A wide character occupies the given cell and the cell to
+    /// its right, which is set to `FrameBufferCell::CONTINUATION` (with the same style). A wide
+    /// character which would be cut off by the right edge of the frame buffer, or whose right
+    /// half would be beneath a character drawn at a greater depth, is replaced with a space.
+    pub fn set_cell(&mut self, coord: ICoord, depth: i8, mut render_cell: RenderCell) {
+        let mut wide = render_cell.character.map(|g| g.width()) == Some(2);
+        let right = coord + ICoord::new(1, 0);
+        if wide
+            && self
+                .grid
+                .get(right)
+                .is_none_or(|cell| depth < cell.foreground_depth)
+        {
+            render_cell.character = Some(Grapheme::from_char(' '));
+            wide = false;
+        }
+        let character_set = self.set_single_cell(coord, depth, render_cell);
+        if wide && character_set {
+            self.set_single_cell(
+                right,
+                depth,
+                RenderCell {
+                    character: Some(FrameBufferCell::CONTINUATION),
+                    ..render_cell
+                },
+            );
+        }
+    }
+
+    /// Replace the remains of any wide character which will be partially overwritten by writing a
+    /// character to the given cell with spaces
+    fn remove_broken_wide_character(&mut self, coord: ICoord) {
+        let Some(&cell) = self.grid.get(coord) else {
+            return;
+        };
+        if cell.is_continuation()
+            && let Some(left_cell) = self.grid.get_mut(coord - ICoord::new(1, 0))
+        {
+            left_cell.character = Grapheme::from_char(' ');
+        }
+        if cell.character.width() == 2
+            && let Some(right_cell) = self.grid.get_mut(coord + ICoord::new(1, 0))
+            && right_cell.is_continuation()
+        {
+            right_cell.character = Grapheme::from_char(' ');
+        }
+    }
+
+    /// Returns true iff the character of the cell was updated
+    fn set_single_cell(&mut self, coord: ICoord, depth: i8, render_cell: RenderCell) -> bool {
+        if let Some(cell) = self.grid.get(coord)
+            && render_cell.character.is_some()
+            && depth >= cell.foreground_depth
+        {
+            self.remove_broken_wide_character(coord);
+        }
+        let mut character_set = false;
         if let Some(cell) = self.grid.get_mut(coord)
             && (cell.foreground_depth <= depth || cell.background_depth <= depth)
         {
             if let Some(character) = render_cell.character {
-                cell.set_character(character, depth);
+                character_set = cell.set_character(character, depth);
             }
             if let Some(bold) = render_cell.style.bold {
                 cell.set_bold(bold, depth);
             }
+            if let Some(dim) = render_cell.style.dim {
+                cell.set_dim(dim, depth);
+            }
+            if let Some(italic) = render_cell.style.italic {
+                cell.set_italic(italic, depth);
+            }
             if let Some(underline) = render_cell.style.underline {
                 cell.set_underline(underline, depth);
             }
+            if let Some(underline_style) = render_cell.style.underline_style {
+                cell.set_underline_style(underline_style, depth);
+            }
+            if let Some(underline_colour) = render_cell.style.underline_colour {
+                let underline_colour_blended = match underline_colour {
+                    UnderlineColour::Foreground => None,
+                    UnderlineColour::Colour(colour) => {
+                        Some(colour.alpha_composite(cell.background))
+                    }
+                };
+                cell.set_underline_colour(underline_colour_blended, depth);
+            }
+            if let Some(blink) = render_cell.style.blink {
+                cell.set_blink(blink, depth);
+            }
+            if let Some(reverse) = render_cell.style.reverse {
+                cell.set_reverse(reverse, depth);
+            }
+            if let Some(strikethrough) = render_cell.style.strikethrough {
+                cell.set_strikethrough(strikethrough, depth);
+            }
             if let Some(foreground) = render_cell.style.foreground {
                 // alpha composite the foreground colour over the existing background colour
                 let foreground_blended = foreground.alpha_composite(cell.background);
@@ -240,6 +483,7 @@ impl FrameBuffer {
                 cell.set_background(background_blended, depth);
             }
         }
+        character_set
     }
 
     pub fn default_ctx<'a>(&self) -> Ctx<'a> {
@@ -247,7 +491,8 @@ impl FrameBuffer {
     }
 
     /**
-     * Update a cell in the frame buffer
+     * Update a cell in the frame buffer. Cells outside the context's bounding box or clip region
+     * are ignored.
      */
     pub fn set_cell_relative_to_ctx<'a>(
         &mut self,
@@ -256,7 +501,9 @@ impl FrameBuffer {
         depth: i8,
         render_cell: RenderCell,
     ) {
-        if let Some(absolute_coord) = ctx.bounding_box.coord_relative_to_absolute(coord) {
+        if let Some(absolute_coord) = ctx.bounding_box.coord_relative_to_absolute(coord)
+            && ctx.clip.contains_coord(absolute_coord)
+        {
             let absolute_depth = depth + ctx.depth;
             self.set_cell(
                 absolute_coord,
@@ -265,8 +512,52 @@ impl FrameBuffer {
             );
         }
     }
+
+    /// Request that a cursor be displayed at a coordinate relative to the context. Requests
+    /// outside the context's bounding box or clip region are ignored.
+    pub fn set_cursor_relative_to_ctx<'a>(&mut self, ctx: Ctx<'a>, cursor: Cursor) {
+        if let Some(coord) = ctx.bounding_box.coord_relative_to_absolute(cursor.coord)
+            && ctx.clip.contains_coord(coord)
+        {
+            self.set_cursor(Cursor { coord, ..cursor });
+        }
+    }
+}
+
+/// The colours with which a frontend should draw a cell (see
+/// `FrameBufferCell::display_colours_at`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DisplayColours {
+    /// The colour of the character and any strikethrough
+    pub foreground: Rgba32,
+    pub background: Rgba32,
+    pub underline: Rgba32,
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum UnderlineStyle {
+    #[default]
+    Single,
+    Double,
+    Curly,
+    Dotted,
+    Dashed,
 }
 
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum UnderlineColour {
+    /// Draw the underline in the foreground colour of the cell, clearing any underline colour
+    /// set by a style applied at a lower depth
+    Foreground,
+    Colour(Rgba32),
+}
+
+/// Attributes which are `None` leave the corresponding attribute of the cell unchanged. Like the
+/// foreground colour, every attribute other than the background colour is only changed if the
+/// depth at which the style is applied is at least the depth at which the foreground of the cell
+/// was last set.
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Style {
@@ -274,6 +565,26 @@ pub struct Style {
     pub underline: Option<bool>,
     pub foreground: Option<Rgba32>,
     pub background: Option<Rgba32>,
+    /// Faint text, drawn by graphical frontends by blending the foreground colour with the
+    /// background colour
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub dim: Option<bool>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub italic: Option<bool>,
+    /// Only has an effect on cells which are underlined
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub underline_style: Option<UnderlineStyle>,
+    /// Only has an effect on cells which are underlined. Cells whose underline colour is never
+    /// set, or is set to `UnderlineColour::Foreground`, are underlined in their foreground colour.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub underline_colour: Option<UnderlineColour>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub blink: Option<bool>,
+    /// Swap the foreground and background colours
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub reverse: Option<bool>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pub strikethrough: Option<bool>,
 }
 
 impl Style {
@@ -282,12 +593,23 @@ impl Style {
         underline: None,
         foreground: None,
         background: None,
+        dim: None,
+        italic: None,
+        underline_style: None,
+        underline_colour: None,
+        blink: None,
+        reverse: None,
+        strikethrough: None,
     };
 
     fn apply_tint(self, tint: &dyn Tint) -> Self {
         Self {
             foreground: self.foreground.map(|r| tint.tint(r)),
             background: self.background.map(|r| tint.tint(r)),
+            underline_colour: self.underline_colour.map(|u| match u {
+                UnderlineColour::Foreground => UnderlineColour::Foreground,
+                UnderlineColour::Colour(r) => UnderlineColour::Colour(tint.tint(r)),
+            }),
             ..self
         }
     }
@@ -320,6 +642,54 @@ impl Style {
             ..self
         }
     }
+    pub const fn with_dim(self, dim: bool) -> Self {
+        Self {
+            dim: Some(dim),
+            ..self
+        }
+    }
+    pub const fn with_italic(self, italic: bool) -> Self {
+        Self {
+            italic: Some(italic),
+            ..self
+        }
+    }
+    pub const fn with_underline_style(self, underline_style: UnderlineStyle) -> Self {
+        Self {
+            underline_style: Some(underline_style),
+            ..self
+        }
+    }
+    pub const fn with_underline_colour(self, underline_colour: Rgba32) -> Self {
+        Self {
+            underline_colour: Some(UnderlineColour::Colour(underline_colour)),
+            ..self
+        }
+    }
+    pub const fn with_underline_colour_foreground(self) -> Self {
+        Self {
+            underline_colour: Some(UnderlineColour::Foreground),
+            ..self
+        }
+    }
+    pub const fn with_blink(self, blink: bool) -> Self {
+        Self {
+            blink: Some(blink),
+            ..self
+        }
+    }
+    pub const fn with_reverse(self, reverse: bool) -> Self {
+        Self {
+            reverse: Some(reverse),
+            ..self
+        }
+    }
+    pub const fn with_strikethrough(self, strikethrough: bool) -> Self {
+        Self {
+            strikethrough: Some(strikethrough),
+            ..self
+        }
+    }
     pub const fn without_bold(self) -> Self {
         Self { bold: None, ..self }
     }
@@ -341,6 +711,45 @@ impl Style {
             ..self
         }
     }
+    pub const fn without_dim(self) -> Self {
+        Self { dim: None, ..self }
+    }
+    pub const fn without_italic(self) -> Self {
+        Self {
+            italic: None,
+            ..self
+        }
+    }
+    pub const fn without_underline_style(self) -> Self {
+        Self {
+            underline_style: None,
+            ..self
+        }
+    }
+    pub const fn without_underline_colour(self) -> Self {
+        Self {
+            underline_colour: None,
+            ..self
+        }
+    }
+    pub const fn without_blink(self) -> Self {
+        Self {
+            blink: None,
+            ..self
+        }
+    }
+    pub const fn without_reverse(self) -> Self {
+        Self {
+            reverse: None,
+            ..self
+        }
+    }
+    pub const fn without_strikethrough(self) -> Self {
+        Self {
+            strikethrough: None,
+            ..self
+        }
+    }
     pub const fn with_foreground_option(self, foreground: Option<Rgba32>) -> Self {
         Self { foreground, ..self }
     }
@@ -353,6 +762,13 @@ impl Style {
             underline: (self.underline.or(other.underline)),
             foreground: (self.foreground.or(other.foreground)),
             background: (self.background.or(other.background)),
+            dim: (self.dim.or(other.dim)),
+            italic: (self.italic.or(other.italic)),
+            underline_style: (self.underline_style.or(other.underline_style)),
+            underline_colour: (self.underline_colour.or(other.underline_colour)),
+            blink: (self.blink.or(other.blink)),
+            reverse: (self.reverse.or(other.reverse)),
+            strikethrough: (self.strikethrough.or(other.strikethrough)),
         }
     }
     pub const fn plain_text() -> Self {
@@ -361,6 +777,13 @@ impl Style {
             underline: Some(false),
             foreground: Some(Rgba32::new_grey(255)),
             background: None,
+            dim: Some(false),
+            italic: Some(false),
+            underline_style: None,
+            underline_colour: None,
+            blink: Some(false),
+            reverse: Some(false),
+            strikethrough: Some(false),
         }
     }
 }
@@ -374,7 +797,7 @@ impl Default for Style {
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct RenderCell {
-    pub character: Option<char>,
+    pub character: Option<Grapheme>,
     pub style: Style,
 }
 
@@ -391,7 +814,7 @@ impl RenderCell {
         }
     }
 
-    pub const fn character(&self) -> Option<char> {
+    pub const fn character(&self) -> Option<Grapheme> {
         self.character
     }
     pub const fn bold(&self) -> Option<bool> {
@@ -400,6 +823,27 @@ impl RenderCell {
     pub const fn underline(&self) -> Option<bool> {
         self.style.underline
     }
+    pub const fn dim(&self) -> Option<bool> {
+        self.style.dim
+    }
+    pub const fn italic(&self) -> Option<bool> {
+        self.style.italic
+    }
+    pub const fn underline_style(&self) -> Option<UnderlineStyle> {
+        self.style.underline_style
+    }
+    pub const fn underline_colour(&self) -> Option<UnderlineColour> {
+        self.style.underline_colour
+    }
+    pub const fn blink(&self) -> Option<bool> {
+        self.style.blink
+    }
+    pub const fn reverse(&self) -> Option<bool> {
+        self.style.reverse
+    }
+    pub const fn strikethrough(&self) -> Option<bool> {
+        self.style.strikethrough
+    }
     pub const fn foreground(&self) -> Option<Rgba32> {
         self.style.foreground
     }
@@ -408,7 +852,13 @@ impl RenderCell {
     }
     pub const fn with_character(self, character: char) -> Self {
         Self {
-            character: Some(character),
+            character: Some(Grapheme::from_char(character)),
+            ..self
+        }
+    }
+    pub const fn with_grapheme(self, grapheme: Grapheme) -> Self {
+        Self {
+            character: Some(grapheme),
             ..self
         }
     }
@@ -424,6 +874,54 @@ impl RenderCell {
             ..self
         }
     }
+    pub const fn with_dim(self, dim: bool) -> Self {
+        Self {
+            style: self.style.with_dim(dim),
+            ..self
+        }
+    }
+    pub const fn with_italic(self, italic: bool) -> Self {
+        Self {
+            style: self.style.with_italic(italic),
+            ..self
+        }
+    }
+    pub const fn with_underline_style(self, underline_style: UnderlineStyle) -> Self {
+        Self {
+            style: self.style.with_underline_style(underline_style),
+            ..self
+        }
+    }
+    pub const fn with_underline_colour(self, underline_colour: Rgba32) -> Self {
+        Self {
+            style: self.style.with_underline_colour(underline_colour),
+            ..self
+        }
+    }
+    pub const fn with_underline_colour_foreground(self) -> Self {
+        Self {
+            style: self.style.with_underline_colour_foreground(),
+            ..self
+        }
+    }
+    pub const fn with_blink(self, blink: bool) -> Self {
+        Self {
+            style: self.style.with_blink(blink),
+            ..self
+        }
+    }
+    pub const fn with_reverse(self, reverse: bool) -> Self {
+        Self {
+            style: self.style.with_reverse(reverse),
+            ..self
+        }
+    }
+    pub const fn with_strikethrough(self, strikethrough: bool) -> Self {
+        Self {
+            style: self.style.with_strikethrough(strikethrough),
+            ..self
+        }
+    }
     pub const fn with_foreground(self, foreground: Rgba32) -> Self {
         Self {
             style: self.style.with_foreground(foreground),
@@ -454,6 +952,48 @@ impl RenderCell {
             ..self
         }
     }
+    pub const fn without_dim(self) -> Self {
+        Self {
+            style: self.style.without_dim(),
+            ..self
+        }
+    }
+    pub const fn without_italic(self) -> Self {
+        Self {
+            style: self.style.without_italic(),
+            ..self
+        }
+    }
+    pub const fn without_underline_style(self) -> Self {
+        Self {
+            style: self.style.without_underline_style(),
+            ..self
+        }
+    }
+    pub const fn without_underline_colour(self) -> Self {
+        Self {
+            style: self.style.without_underline_colour(),
+            ..self
+        }
+    }
+    pub const fn without_blink(self) -> Self {
+        Self {
+            style: self.style.without_blink(),
+            ..self
+        }
+    }
+    pub const fn without_reverse(self) -> Self {
+        Self {
+            style: self.style.without_reverse(),
+            ..self
+        }
+    }
+    pub const fn without_strikethrough(self) -> Self {
+        Self {
+            style: self.style.without_strikethrough(),
+            ..self
+        }
+    }
     pub const fn without_foreground(self) -> Self {
         Self {
             style: self.style.without_foreground(),
@@ -467,6 +1007,10 @@ impl RenderCell {
         }
     }
     pub const fn with_character_option(self, character: Option<char>) -> Self {
+        let character = match character {
+            Some(character) => Some(Grapheme::from_char(character)),
+            None => None,
+        };
         Self { character, ..self }
     }
     pub const fn with_foreground_option(self, foreground: Option<Rgba32>) -> Self {
@@ -530,7 +1074,13 @@ impl<'a, T: Tint> Tint for TintDynCompose<'a, T> {
 pub struct Ctx<'a> {
     pub tint: &'a dyn Tint,
     pub depth: i8,
+    /// The region occupied by the component in absolute coordinates. Its top-left corner is the
+    /// origin of the coordinates relative to the context.
     pub bounding_box: BoundingBox,
+    /// Cells outside this region (in absolute coordinates) aren't drawn, even if they're inside
+    /// the bounding box. This allows a component's bounding box to extend beyond the region in
+    /// which it's visible (e.g. when scrolling).
+    pub clip: BoundingBox,
 }
 
 #[macro_export]
@@ -556,6 +1106,7 @@ impl<'a> Ctx<'a> {
             tint: &TintIdentity,
             depth: 0,
             bounding_box: BoundingBox::default_with_size(size),
+            clip: BoundingBox::default_with_size(size),
         }
     }
 
@@ -580,6 +1131,16 @@ impl<'a> Ctx<'a> {
         self.add_offset(ICoord { x, y })
     }
 
+    /// Move the bounding box by the specified offset without changing its size or the clip
+    /// region. Parts of the component moved outside the clip region (e.g. by a negative offset)
+    /// aren't drawn.
+    pub fn translate(self, offset: ICoord) -> Self {
+        Self {
+            bounding_box: self.bounding_box.translate(offset),
+            ..self
+        }
+    }
+
     pub fn add_depth(self, depth_delta: i8) -> Self {
         Self {
             depth: self.depth + depth_delta,
@@ -622,19 +1183,73 @@ impl<'a> Ctx<'a> {
         }
     }
 
+    /// Restrict drawing to the part of the current clip region inside the bounding box
+    pub fn clip_to_bounding_box(self) -> Self {
+        Self {
+            clip: self.clip.intersection(self.bounding_box),
+            ..self
+        }
+    }
+
     pub fn top_left(self) -> ICoord {
         self.bounding_box.top_left
     }
+
+    /// True iff the absolute coordinate is both inside the bounding box and visible (ie. inside
+    /// the clip region). Useful for deciding whether a mouse event applies to a component.
+    pub fn contains_coord(&self, coord: ICoord) -> bool {
+        self.clip.contains_coord(coord) && self.bounding_box.contains_coord(coord)
+    }
+
+    /// Convert an absolute coordinate (such as that of a mouse event) to one relative to the
+    /// context, or `None` if the coordinate is outside the bounding box or isn't visible
+    pub fn coord_absolute_to_relative(&self, coord: ICoord) -> Option<ICoord> {
+        if self.clip.contains_coord(coord) {
+            self.bounding_box.coord_absolute_to_relative(coord)
+        } else {
+            None
+        }
+    }
+}
+
+/// Changes to the state of the window (or terminal) in which an application is running
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lifecycle {
+    FocusGained,
+    FocusLost,
+    /// The user asked to close the window (e.g. by clicking its close button). Unless the
+    /// component yields something in response, this is followed by ctrl+c (`keys::ETX`) input.
+    CloseRequested,
+    /// The application is no longer visible (e.g. the window was minimised or the browser tab was
+    /// hidden)
+    Suspended,
+    /// The application is visible again after being suspended
+    Resumed,
+}
+
+/// The size of the display changed. By the time this is delivered, the frame buffer has already
+/// been resized (which clears its contents). Graphical frontends scale a fixed-size grid to fit
+/// their window, so when their window changes size they deliver this with `old_size` and
+/// `new_size` equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Resize {
+    pub old_size: UCoord,
+    pub new_size: UCoord,
 }
 
 #[derive(Debug, Clone, Copy)]
-pub enum Event {
+pub enum Event<'a> {
     Input(Input),
+    /// Text pasted from the clipboard, delivered as a single event rather than a sequence of key
+    /// presses
+    Paste(&'a str),
     Tick(Duration),
     Peek,
+    Lifecycle(Lifecycle),
+    Resize(Resize),
 }
 
-impl Event {
+impl<'a> Event<'a> {
     pub fn input(self) -> Option<Input> {
         if let Self::Input(input) = self {
             Some(input)
@@ -655,6 +1270,32 @@ impl Event {
         matches!(self, Self::Peek)
     }
 
+    pub fn lifecycle(self) -> Option<Lifecycle> {
+        if let Self::Lifecycle(lifecycle) = self {
+            Some(lifecycle)
+        } else {
+            None
+        }
+    }
+
+    pub fn resize(self) -> Option<Resize> {
+        if let Self::Resize(resize) = self {
+            Some(resize)
+        } else {
+            None
+        }
+    }
+
+    /// True if the user asked for the application to close, either by closing its window or by
+    /// pressing ctrl+c in a terminal
+    pub fn is_close(self) -> bool {
+        matches!(
+            self,
+            Self::Input(Input::Keyboard(input::keys::ETX))
+                | Self::Lifecycle(Lifecycle::CloseRequested)
+        )
+    }
+
     pub fn is_escape(self) -> bool {
         matches!(self, Self::Input(Input::Keyboard(input::keys::ESCAPE)))
     }
@@ -679,6 +1320,14 @@ impl Event {
         self.input().and_then(Input::keyboard)
     }
 
+    pub fn key_event(self) -> Option<KeyEvent> {
+        self.input().and_then(Input::key_event)
+    }
+
+    pub fn key_press(self) -> Option<KeyEvent> {
+        self.input().and_then(Input::key_press)
+    }
+
     pub fn mouse_input(self) -> Option<MouseInput> {
         self.input().and_then(Input::mouse)
     }
@@ -688,6 +1337,14 @@ impl Event {
         self.input().and_then(Input::gamepad)
     }
 
+    pub fn paste(self) -> Option<&'a str> {
+        if let Self::Paste(text) = self {
+            Some(text)
+        } else {
+            None
+        }
+    }
+
     pub fn input_policy(self) -> Option<InputPolicy> {
         self.input().and_then(Input::policy)
     }
@@ -719,6 +1376,13 @@ pub trait Component {
     /// the size of the components they decorate (e.g. when drawing a border around a component, its
     /// size must be known).
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord;
+
+    /// Return constraints on the size of this component, for layout containers which divide
+    /// their space between their children (see `layout`). By default, a component's size is fixed
+    /// at its current size.
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        SizeConstraints::fixed(self.size(state, ctx))
+    }
 }
 
 /// A wrapper of `Component` implementation which erases its specific by placing it inside a `Box`
@@ -736,6 +1400,9 @@ impl<O, S> Component for BoxedComponent<O, S> {
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
         self.0.size(state, ctx)
     }
+    fn size_constraints(&self, state: &Self::State, ctx: Ctx) -> SizeConstraints {
+        self.0.size_constraints(state, ctx)
+    }
 }
 
 pub mod app {
@@ -749,9 +1416,131 @@ pub mod prelude {
     #[cfg(feature = "gamepad")]
     pub use super::input::{GamepadButton, GamepadInput};
     pub use super::{
-        Component, Ctx, Event, FrameBuffer, ICoord, RenderCell, Rgba32, Style, Tint, UCoord, app,
-        ctx_tint, input, input::Input, input::KeyboardInput, input::MouseButton, input::MouseInput,
+        Component, Constraint, Ctx, Cursor, CursorShape, Event, FrameBuffer, Grapheme, ICoord,
+        Lifecycle, RenderCell, Resize, Rgba32, SizeConstraints, Style, Tint, UCoord,
+        UnderlineStyle, app, ctx_tint, input, input::Input, input::KeyEvent, input::KeyEventKind,
+        input::KeyModifiers, input::KeyboardInput, input::MouseButton, input::MouseInput,
         input::ScrollDirection,
     };
     pub use std::time::Duration;
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn row(fb: &FrameBuffer) -> Vec<&str> {
+        fb.iter().map(|cell| cell.character.as_str()).collect()
+    }
+
+    /// The context of a component translated 2 cells left of a bounding box covering the last 3
+    /// cells of a 4x1 frame buffer, clipped to that bounding box
+    fn translated_clipped_ctx<'a>(fb: &FrameBuffer) -> Ctx<'a> {
+        fb.default_ctx()
+            .add_x(1)
+            .clip_to_bounding_box()
+            .translate(ICoord::new(-2, 0))
+    }
+
+    #[test]
+    fn negative_translate_clipped() {
+        let mut fb = FrameBuffer::new(UCoord::new(4, 1));
+        let ctx = translated_clipped_ctx(&fb);
+        for (x, character) in ['a', 'b', 'c', 'd'].into_iter().enumerate() {
+            fb.set_cell_relative_to_ctx(
+                ctx,
+                ICoord::new(x as i32, 0),
+                0,
+                RenderCell::BLANK.with_character(character),
+            );
+        }
+        // 'a' and 'b' are outside the clip region, and 'd' is outside the bounding box
+        assert_eq!(row(&fb), [" ", "c", " ", " "]);
+    }
+
+    #[test]
+    fn coord_absolute_to_relative_clipped() {
+        let fb = FrameBuffer::new(UCoord::new(4, 1));
+        let ctx = translated_clipped_ctx(&fb);
+        assert_eq!(
+            ctx.coord_absolute_to_relative(ICoord::new(1, 0)),
+            Some(ICoord::new(2, 0))
+        );
+        assert!(ctx.contains_coord(ICoord::new(1, 0)));
+        // inside the bounding box but outside the clip region
+        assert_eq!(ctx.coord_absolute_to_relative(ICoord::new(0, 0)), None);
+        assert!(!ctx.contains_coord(ICoord::new(0, 0)));
+        // inside the clip region but outside the bounding box
+        assert_eq!(ctx.coord_absolute_to_relative(ICoord::new(2, 0)), None);
+        assert!(!ctx.contains_coord(ICoord::new(2, 0)));
+    }
+
+    #[test]
+    fn overwrite_left_half_of_wide_character() {
+        let mut fb = FrameBuffer::new(UCoord::new(4, 1));
+        fb.set_cell(ICoord::new(1, 0), 0, RenderCell::BLANK.with_character('日'));
+        assert_eq!(row(&fb), [" ", "日", "\0", " "]);
+        fb.set_cell(ICoord::new(1, 0), 0, RenderCell::BLANK.with_character('a'));
+        assert_eq!(row(&fb), [" ", "a", " ", " "]);
+    }
+
+    #[test]
+    fn overwrite_right_half_of_wide_character() {
+        let mut fb = FrameBuffer::new(UCoord::new(4, 1));
+        fb.set_cell(ICoord::new(1, 0), 0, RenderCell::BLANK.with_character('日'));
+        fb.set_cell(ICoord::new(2, 0), 0, RenderCell::BLANK.with_character('a'));
+        assert_eq!(row(&fb), [" ", " ", "a", " "]);
+    }
+
+    #[test]
+    fn overwrite_wide_character_below_depth() {
+        let mut fb = FrameBuffer::new(UCoord::new(4, 1));
+        fb.set_cell(ICoord::new(1, 0), 1, RenderCell::BLANK.with_character('日'));
+        fb.set_cell(ICoord::new(2, 0), 0, RenderCell::BLANK.with_character('a'));
+        assert_eq!(row(&fb), [" ", "日", "\0", " "]);
+    }
+
+    #[test]
+    fn wide_character_below_depth() {
+        let mut fb = FrameBuffer::new(UCoord::new(4, 1));
+        fb.set_cell(ICoord::new(2, 0), 1, RenderCell::BLANK.with_character('a'));
+        fb.set_cell(ICoord::new(1, 0), 0, RenderCell::BLANK.with_character('日'));
+        assert_eq!(row(&fb), [" ", " ", "a", " "]);
+        let mut fb = FrameBuffer::new(UCoord::new(4, 1));
+        fb.set_cell(ICoord::new(1, 0), 0, RenderCell::BLANK.with_character('日'));
+        fb.set_cell(ICoord::new(2, 0), 1, RenderCell::BLANK.with_character('a'));
+        assert_eq!(row(&fb), [" ", " ", "a", " "]);
+    }
+
+    #[test]
+    fn wide_character_at_last_column() {
+        let mut fb = FrameBuffer::new(UCoord::new(3, 1));
+        fb.set_cell(ICoord::new(2, 0), 0, RenderCell::BLANK.with_character('日'));
+        assert_eq!(row(&fb), [" ", " ", " "]);
+        fb.set_cell(ICoord::new(1, 0), 0, RenderCell::BLANK.with_character('日'));
+        assert_eq!(row(&fb), [" ", "日", "\0"]);
+    }
+
+    #[test]
+    fn underline_colour_cleared_by_higher_depth() {
+        let mut fb = FrameBuffer::new(UCoord::new(1, 1));
+        let coord = ICoord::new(0, 0);
+        let red = Rgba32::new_rgb(255, 0, 0);
+        fb.set_cell(
+            coord,
+            0,
+            RenderCell::BLANK
+                .with_underline(true)
+                .with_underline_colour(red),
+        );
+        assert_eq!(fb.get(coord).unwrap().underline_colour, Some(red));
+        fb.set_cell(
+            coord,
+            1,
+            RenderCell::BLANK.with_underline_colour_foreground(),
+        );
+        assert_eq!(fb.get(coord).unwrap().underline_colour, None);
+        fb.set_cell(coord, 0, RenderCell::BLANK.with_underline_colour(red));
+        assert_eq!(fb.get(coord).unwrap().underline_colour, None);
+    }
+}
@@ -2,6 +2,7 @@ use chargrid_ansi_terminal::{Context, col_encode};
 use colour_grid_app::app;
 
 enum ColEncodeChoice {
+    Auto,
     TrueColour,
     Rgb,
     Greyscale,
@@ -12,12 +13,13 @@ impl ColEncodeChoice {
     fn parser() -> impl meap::Parser<Item = Self> {
         use ColEncodeChoice::*;
         meap::choose_at_most_one!(
+            flag("auto").some_if(Auto),
             flag("true-colour").some_if(TrueColour),
             flag("rgb").some_if(Rgb),
             flag("greyscale").some_if(Greyscale),
             flag("ansi").some_if(Ansi),
         )
-        .with_default_general(TrueColour)
+        .with_default_general(Auto)
     }
 }
 
@@ -31,6 +33,7 @@ fn main() {
     let app = app();
     use ColEncodeChoice as C;
     match col_encode_choice {
+        C::Auto => context.run(app, col_encode::Auto),
         C::TrueColour => context.run(app, col_encode::XtermTrueColour),
         C::Rgb => context.run(app, col_encode::FromTermInfoRgb),
         C::Greyscale => context.run(app, col_encode::FromTermInfoGreyscale),
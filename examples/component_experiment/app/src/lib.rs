@@ -22,6 +22,7 @@ impl HelloWorld {
                     background: Some(Rgba32::new_rgb(255, 0, 255)),
                     underline: Some(true),
                     bold: Some(true),
+                    ..Default::default()
                 },
             }
             .wrap_word(),
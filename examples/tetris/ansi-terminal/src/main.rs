@@ -1,7 +1,12 @@
+use chargrid_ansi_terminal::chargrid_input::{KeyEvent, KeyModifiers, KeyboardInput};
 use chargrid_ansi_terminal::{Context, col_encode};
 use tetris_app::app;
 
 fn main() {
-    let context = Context::new().unwrap();
-    context.run(app(rand::rng()), col_encode::FromTermInfoRgb);
+    let mut context = Context::new().unwrap();
+    context.set_suspend_key(Some(KeyEvent::new(
+        KeyboardInput::Char('z'),
+        KeyModifiers::CTRL,
+    )));
+    context.run(app(rand::rng()), col_encode::Auto);
 }
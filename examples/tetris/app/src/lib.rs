@@ -1,4 +1,5 @@
 use chargrid::{
+    align::AlignmentY,
     border::{Border, BorderStyle},
     control_flow::*,
     core::TintDim,
@@ -14,8 +15,8 @@ use tetris::{GameState, Input as TetrisInput, Meta, Piece, PieceType, Tetris};
 const BLANK_FOREGROUND_COLOUR: Rgba32 = Rgba32::new_rgb(24, 24, 24);
 const FOREGROUND_COLOUR: Rgba32 = Rgba32::new_grey(255);
 const BACKGROUND_COLOUR: Rgba32 = Rgba32::new_grey(0);
-const BLOCK_CHAR: char = '+';
-const BLANK_CHAR: char = '-';
+const BLOCK_CHAR: Grapheme = Grapheme::from_char('+');
+const BLANK_CHAR: Grapheme = Grapheme::from_char('-');
 
 const NEXT_PIECE_SIZE: [u32; 2] = [6, 4];
 
@@ -59,43 +60,37 @@ struct TetrisState {
 }
 
 struct TetrisComponent {
-    board_view: Border<TetrisBoardView>,
-    next_piece_view: Border<TetrisNextPieceView>,
+    view: CF<(), GameState>,
 }
 
 impl TetrisComponent {
     fn new() -> Self {
         let BorderStyles { common, next_piece } = BorderStyles::new();
+        let board_view = cf(Border {
+            component: TetrisBoardView,
+            style: common,
+        });
+        let next_piece_view = cf(Border {
+            component: TetrisNextPieceView,
+            style: next_piece,
+        })
+        .lens_state(lens!(GameState[next_piece]: Piece));
         Self {
-            board_view: Border {
-                component: TetrisBoardView,
-                style: common,
-            },
-            next_piece_view: Border {
-                component: TetrisNextPieceView,
-                style: next_piece,
-            },
+            view: hstack(vec![board_view, next_piece_view], 0, AlignmentY::Top),
         }
     }
 }
 
 enum TetrisOutput {
     GameOver,
+    Pause,
 }
 
 impl Component for TetrisComponent {
     type Output = Option<TetrisOutput>;
     type State = TetrisState;
     fn render(&self, state: &Self::State, ctx: Ctx, fb: &mut FrameBuffer) {
-        self.board_view.render(&state.tetris.game_state, ctx, fb);
-        self.next_piece_view.render(
-            &state.tetris.game_state.next_piece,
-            ctx.add_offset(ICoord::new(
-                self.board_view.size(&state.tetris.game_state, ctx).width() as i32,
-                0,
-            )),
-            fb,
-        );
+        self.view.render(&state.tetris.game_state, ctx, fb);
     }
     fn update(&mut self, state: &mut Self::State, _ctx: Ctx, event: Event) -> Self::Output {
         use input::*;
@@ -124,6 +119,11 @@ impl Component for TetrisComponent {
                 }) => state.tetris.input(TetrisInput::Right),
                 _ => (),
             },
+            // pause the game when the player switches to another window
+            Event::Lifecycle(Lifecycle::FocusLost | Lifecycle::Suspended) => {
+                return Some(TetrisOutput::Pause);
+            }
+            Event::Lifecycle(_) | Event::Resize(_) | Event::Paste(_) => (),
             Event::Tick(duration) => {
                 if let Some(meta) = state.tetris.tick(duration, &mut state.rng) {
                     match meta {
@@ -135,9 +135,7 @@ impl Component for TetrisComponent {
         None
     }
     fn size(&self, state: &Self::State, ctx: Ctx) -> UCoord {
-        let board_size = TetrisBoardView.size(&state.tetris.game_state, ctx);
-        let next_piece_size = TetrisNextPieceView.size(&state.tetris.game_state.next_piece, ctx);
-        board_size.set_width(board_size.width() + next_piece_size.width())
+        self.view.size(&state.tetris.game_state, ctx)
     }
 }
 
@@ -171,6 +169,7 @@ impl Component for TetrisBoardView {
                     underline: Some(false),
                     foreground: Some(FOREGROUND_COLOUR),
                     background: Some(piece_colour(state.piece.typ)),
+                    ..Default::default()
                 },
             };
             fb.set_cell_relative_to_ctx(ctx, coord, 0, cell_info);
@@ -195,6 +194,7 @@ impl Component for TetrisNextPieceView {
                     underline: Some(false),
                     foreground: Some(FOREGROUND_COLOUR),
                     background: Some(piece_colour(state.typ)),
+                    ..Default::default()
                 },
             };
             fb.set_cell_relative_to_ctx(ctx, offset + coord, 0, cell_info);
@@ -244,7 +244,7 @@ fn pausable_tetris() -> CF<Option<PausableTetrisOutput>, TetrisState> {
         tetris()
             .catch_escape()
             .and_then(|or_escape| match or_escape {
-                Err(Escape) => pause_menu()
+                Err(Escape) | Ok(TetrisOutput::Pause) => pause_menu()
                     .centre()
                     .overlay_tint(tetris(), TintDim(63), 10)
                     .catch_escape()
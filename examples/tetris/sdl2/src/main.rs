@@ -22,6 +22,8 @@ fn main() {
         underline_width_cell_ratio: 0.1,
         underline_top_offset_cell_ratio: 0.8,
         resizable: false,
+        key_repeat_and_release: false,
+        frame_config: FrameConfig::default(),
     });
     context.run(app(rand::rng()));
 }
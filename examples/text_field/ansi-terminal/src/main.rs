@@ -3,5 +3,5 @@ use text_field_app::app;
 
 fn main() {
     let context = Context::new().unwrap();
-    context.run(app(), col_encode::FromTermInfoRgb);
+    context.run(app(), col_encode::Auto);
 }
@@ -0,0 +1,148 @@
+use crate::snapshot;
+use chargrid_input::Input;
+use chargrid_runtime::{
+    Component, FrameBuffer, Lifecycle, UCoord, app, on_frame, on_input, on_lifecycle, on_paste,
+    on_resize,
+};
+use std::time::Duration;
+
+/// A single step of a script used to drive a component
+#[derive(Clone, Debug)]
+pub enum Step {
+    /// Deliver an input event to the component
+    Input(Input),
+    /// Deliver text pasted from the clipboard to the component
+    Paste(String),
+    /// Advance time by the given duration and render a frame
+    Tick(Duration),
+    /// Deliver a lifecycle event (e.g. the window losing focus) to the component
+    Lifecycle(Lifecycle),
+    /// Change the size of the display
+    Resize(UCoord),
+}
+
+impl From<Input> for Step {
+    fn from(input: Input) -> Self {
+        Self::Input(input)
+    }
+}
+
+impl From<Lifecycle> for Step {
+    fn from(lifecycle: Lifecycle) -> Self {
+        Self::Lifecycle(lifecycle)
+    }
+}
+
+impl From<Duration> for Step {
+    fn from(duration: Duration) -> Self {
+        Self::Tick(duration)
+    }
+}
+
+/// A context which runs a component without a display. Inputs and the passage of time are
+/// simulated by the caller, and the resulting frame buffer can be inspected directly or rendered
+/// to a text snapshot.
+pub struct Context {
+    chargrid_frame_buffer: FrameBuffer,
+}
+
+impl Context {
+    /// Initialise a new context with a frame buffer of the given size.
+    pub fn new(size: UCoord) -> Self {
+        Self {
+            chargrid_frame_buffer: FrameBuffer::new(size),
+        }
+    }
+
+    pub fn size(&self) -> UCoord {
+        self.chargrid_frame_buffer.size()
+    }
+
+    /// Simulate the display changing size. The frame buffer is cleared, just like when a terminal
+    /// is resized, and the component is notified with an `Event::Resize` (unless the size is
+    /// unchanged).
+    pub fn resize<C>(&mut self, component: &mut C, size: UCoord) -> app::Output
+    where
+        C: Component<State = (), Output = app::Output>,
+    {
+        on_resize(component, size, &mut self.chargrid_frame_buffer)
+    }
+
+    pub fn frame_buffer(&self) -> &FrameBuffer {
+        &self.chargrid_frame_buffer
+    }
+
+    /// Deliver a single input event to the component
+    pub fn input<C>(&mut self, component: &mut C, input: Input) -> app::Output
+    where
+        C: Component<State = (), Output = app::Output>,
+    {
+        on_input(component, input, &self.chargrid_frame_buffer)
+    }
+
+    /// Deliver text pasted from the clipboard to the component
+    pub fn paste<C>(&mut self, component: &mut C, text: &str) -> app::Output
+    where
+        C: Component<State = (), Output = app::Output>,
+    {
+        on_paste(component, text, &self.chargrid_frame_buffer)
+    }
+
+    /// Deliver a single lifecycle event to the component
+    pub fn lifecycle<C>(&mut self, component: &mut C, lifecycle: Lifecycle) -> app::Output
+    where
+        C: Component<State = (), Output = app::Output>,
+    {
+        on_lifecycle(component, lifecycle, &self.chargrid_frame_buffer)
+    }
+
+    /// Deliver a tick of the given duration to the component, then render it to the frame buffer
+    /// (unless the tick caused the component to exit)
+    pub fn tick<C>(&mut self, component: &mut C, since_last_frame: Duration) -> app::Output
+    where
+        C: Component<State = (), Output = app::Output>,
+    {
+        on_frame(component, since_last_frame, &mut self.chargrid_frame_buffer)
+    }
+
+    /// Apply a single step of a script to the component
+    pub fn step<C>(&mut self, component: &mut C, step: Step) -> app::Output
+    where
+        C: Component<State = (), Output = app::Output>,
+    {
+        match step {
+            Step::Input(input) => self.input(component, input),
+            Step::Paste(text) => self.paste(component, &text),
+            Step::Tick(duration) => self.tick(component, duration),
+            Step::Lifecycle(lifecycle) => self.lifecycle(component, lifecycle),
+            Step::Resize(size) => self.resize(component, size),
+        }
+    }
+
+    /// Apply each step of a script to the component in order, stopping early if the component
+    /// exits. Note that nothing is rendered to the frame buffer until a `Step::Tick` is applied.
+    pub fn run<C, I>(&mut self, component: &mut C, steps: I) -> app::Output
+    where
+        C: Component<State = (), Output = app::Output>,
+        I: IntoIterator,
+        I::Item: Into<Step>,
+    {
+        for step in steps {
+            if let Some(app::Exit) = self.step(component, step.into()) {
+                return Some(app::Exit);
+            }
+        }
+        None
+    }
+
+    /// Render the characters of the current frame to a string with one line per row
+    pub fn text_snapshot(&self) -> String {
+        snapshot::text(&self.chargrid_frame_buffer)
+    }
+
+    /// Render the characters and styles of the current frame to a string. See
+    /// `snapshot::styled_text` for a description of the format.
+    pub fn styled_text_snapshot(&self) -> String {
+        snapshot::styled_text(&self.chargrid_frame_buffer)
+    }
+}
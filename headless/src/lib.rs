@@ -0,0 +1,5 @@
+mod context;
+pub mod snapshot;
+
+pub use chargrid_input;
+pub use context::*;
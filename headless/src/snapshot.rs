@@ -0,0 +1,379 @@
+//! Render frame buffers to text for comparison against golden files
+
+use chargrid_runtime::{CursorShape, FrameBuffer, FrameBufferCell, Rgba32, UnderlineStyle};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Set this environment variable to make `assert_matches_file` (re)write golden files rather than
+/// comparing against them.
+pub const UPDATE_ENV_VAR: &str = "CHARGRID_UPDATE_SNAPSHOTS";
+
+/// Render the characters of a frame buffer with one line per row. Trailing whitespace is removed
+/// from each line so that golden files survive editors which strip it.
+pub fn text(frame_buffer: &FrameBuffer) -> String {
+    let mut out = String::new();
+    for row in frame_buffer.rows() {
+        let line = row
+            .iter()
+            .filter(|cell| !cell.is_continuation())
+            .map(|cell| cell.character.as_str())
+            .collect::<String>();
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct CellStyle {
+    foreground: Rgba32,
+    background: Rgba32,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: Option<(UnderlineStyle, Option<Rgba32>)>,
+    blink: bool,
+    reverse: bool,
+    strikethrough: bool,
+}
+
+fn write_hex(out: &mut String, Rgba32 { r, g, b, .. }: Rgba32) {
+    write!(out, "#{:02x}{:02x}{:02x}", r, g, b).unwrap();
+}
+
+impl CellStyle {
+    fn of_cell(cell: &FrameBufferCell) -> Self {
+        Self {
+            foreground: cell.foreground,
+            background: cell.background,
+            bold: cell.bold,
+            dim: cell.dim,
+            italic: cell.italic,
+            underline: cell
+                .underline
+                .then_some((cell.underline_style, cell.underline_colour)),
+            blink: cell.blink,
+            reverse: cell.reverse,
+            strikethrough: cell.strikethrough,
+        }
+    }
+
+    fn write_description(&self, out: &mut String) {
+        out.push_str("fg=");
+        write_hex(out, self.foreground);
+        out.push_str(" bg=");
+        write_hex(out, self.background);
+        for (set, name) in [
+            (self.bold, "bold"),
+            (self.dim, "dim"),
+            (self.italic, "italic"),
+        ] {
+            if set {
+                write!(out, " {}", name).unwrap();
+            }
+        }
+        if let Some((style, colour)) = self.underline {
+            out.push_str(" underline");
+            match style {
+                UnderlineStyle::Single => (),
+                UnderlineStyle::Double => out.push_str("=double"),
+                UnderlineStyle::Curly => out.push_str("=curly"),
+                UnderlineStyle::Dotted => out.push_str("=dotted"),
+                UnderlineStyle::Dashed => out.push_str("=dashed"),
+            }
+            if let Some(colour) = colour {
+                out.push_str(" underline-colour=");
+                write_hex(out, colour);
+            }
+        }
+        for (set, name) in [
+            (self.blink, "blink"),
+            (self.reverse, "reverse"),
+            (self.strikethrough, "strikethrough"),
+        ] {
+            if set {
+                write!(out, " {}", name).unwrap();
+            }
+        }
+    }
+}
+
+/// Render the characters and styles of a frame buffer. Each row is written as its characters
+/// surrounded by '|' (so trailing spaces are preserved), followed by one indented line for each
+/// run of cells in that row which share a style, e.g.:
+/// ```text
+/// |hi  |
+///   0..2 fg=#ffffff bg=#000000 bold
+///   2..4 fg=#ffffff bg=#000000 underline=curly underline-colour=#ff0000
+/// ```
+/// If a cursor was requested, it's described on a final line, e.g. `cursor 3,0 bar blink`.
+pub fn styled_text(frame_buffer: &FrameBuffer) -> String {
+    let mut out = String::new();
+    for row in frame_buffer.rows() {
+        out.push('|');
+        out.extend(
+            row.iter()
+                .filter(|cell| !cell.is_continuation())
+                .map(|cell| cell.character.as_str()),
+        );
+        out.push_str("|\n");
+        let mut start = 0;
+        while start < row.len() {
+            let style = CellStyle::of_cell(&row[start]);
+            let end = row[start..]
+                .iter()
+                .position(|cell| CellStyle::of_cell(cell) != style)
+                .map_or(row.len(), |len| start + len);
+            write!(out, "  {}..{} ", start, end).unwrap();
+            style.write_description(&mut out);
+            out.push('\n');
+            start = end;
+        }
+    }
+    if let Some(cursor) = frame_buffer.cursor() {
+        let shape = match cursor.shape {
+            CursorShape::Block => "block",
+            CursorShape::Bar => "bar",
+            CursorShape::Underline => "underline",
+        };
+        write!(
+            out,
+            "cursor {},{} {}",
+            cursor.coord.x, cursor.coord.y, shape
+        )
+        .unwrap();
+        if cursor.blink {
+            out.push_str(" blink");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Compare a snapshot against the contents of a golden file, panicking with both versions if they
+/// differ. If the environment variable named by `UPDATE_ENV_VAR` is set, the golden file is
+/// written instead (creating any missing parent directories).
+pub fn assert_matches_file<P: AsRef<Path>>(actual: &str, path: P) {
+    let path = path.as_ref();
+    if std::env::var_os(UPDATE_ENV_VAR).is_some() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(path, actual).unwrap();
+        return;
+    }
+    let expected = match fs::read_to_string(path) {
+        Ok(expected) => expected,
+        Err(e) => panic!(
+            "failed to read snapshot {} ({}), set {} to create it",
+            path.display(),
+            e,
+            UPDATE_ENV_VAR
+        ),
+    };
+    if expected != actual {
+        panic!(
+            "snapshot {} doesn't match\n--- expected ---\n{}--- actual ---\n{}",
+            path.display(),
+            expected,
+            actual
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Context, Step};
+    use chargrid_common::{
+        border::*,
+        control_flow::*,
+        text_field::{TextField, TextFieldCursor},
+    };
+    use chargrid_core::{
+        Component, Ctx, CursorShape, Event, Lifecycle, Resize, UCoord, app, input::*,
+    };
+    use std::time::Duration;
+
+    fn text_field_app() -> impl Component<State = (), Output = app::Output> {
+        text_field_app_with_cursor(TextFieldCursor::default())
+    }
+
+    fn text_field_app_with_cursor(
+        cursor: TextFieldCursor,
+    ) -> impl Component<State = (), Output = app::Output> {
+        cf(Border {
+            component: TextField::with_initial_string(6, "abc".to_string()).with_cursor(cursor),
+            style: BorderStyle::default(),
+        })
+        .map(|_| app::Exit)
+        .exit_on_close()
+        .clear_each_frame()
+    }
+
+    #[test]
+    fn text_field_plain() {
+        let mut context = Context::new(UCoord::new(10, 4));
+        let mut component = text_field_app();
+        let script = [
+            Step::Input(Input::Keyboard(KeyboardInput::Char('d'))),
+            Step::Tick(Duration::from_millis(16)),
+        ];
+        assert!(context.run(&mut component, script).is_none());
+        assert_eq!(context.text_snapshot(), "┌──────┐\n│abcd  │\n└──────┘\n\n");
+        assert!(
+            context
+                .input(&mut component, Input::Keyboard(keys::RETURN))
+                .is_some()
+        );
+        assert!(
+            context
+                .lifecycle(&mut component, Lifecycle::CloseRequested)
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn text_field_paste() {
+        let mut context = Context::new(UCoord::new(10, 4));
+        let mut component = text_field_app();
+        let script = [
+            Step::Paste("d\r\ne".to_string()),
+            Step::Tick(Duration::from_millis(16)),
+        ];
+        assert!(context.run(&mut component, script).is_none());
+        assert_eq!(context.text_snapshot(), "┌──────┐\n│abcd e│\n└──────┘\n\n");
+    }
+
+    #[test]
+    fn wide_characters() {
+        let mut context = Context::new(UCoord::new(10, 4));
+        let mut component = text_field_app();
+        let script = [
+            Step::Paste("日本".to_string()),
+            Step::Tick(Duration::from_millis(16)),
+        ];
+        assert!(context.run(&mut component, script).is_none());
+        assert_eq!(context.text_snapshot(), "┌──────┐\n│abc日 │\n└──────┘\n\n");
+    }
+
+    #[test]
+    fn combining_characters() {
+        let mut context = Context::new(UCoord::new(10, 4));
+        let mut component = text_field_app();
+        let script = [
+            Step::Input(Input::Keyboard(KeyboardInput::Char('e'))),
+            Step::Input(Input::Keyboard(KeyboardInput::Char('\u{301}'))),
+            Step::Paste("👍🏽".to_string()),
+            Step::Tick(Duration::from_millis(16)),
+        ];
+        assert!(context.run(&mut component, script).is_none());
+        assert_eq!(
+            context.text_snapshot(),
+            "┌──────┐\n│abce\u{301}👍🏽│\n└──────┘\n\n"
+        );
+    }
+
+    #[test]
+    fn close_requested_followed_by_ctrl_c() {
+        #[derive(Default)]
+        struct ExitOnCtrlC {
+            lifecycles: Vec<Lifecycle>,
+            inputs: Vec<Input>,
+        }
+        impl Component for ExitOnCtrlC {
+            type Output = app::Output;
+            type State = ();
+            fn render(&self, _state: &(), _ctx: Ctx, _fb: &mut FrameBuffer) {}
+            fn update(&mut self, _state: &mut (), _ctx: Ctx, event: Event) -> app::Output {
+                match event {
+                    Event::Input(input) => self.inputs.push(input),
+                    Event::Lifecycle(lifecycle) => self.lifecycles.push(lifecycle),
+                    _ => (),
+                }
+                event
+                    .keyboard_input()
+                    .filter(|&input| input == keys::ETX)
+                    .map(|_| app::Exit)
+            }
+            fn size(&self, _state: &(), ctx: Ctx) -> UCoord {
+                ctx.bounding_box.size()
+            }
+        }
+        let mut context = Context::new(UCoord::new(10, 4));
+        let mut component = ExitOnCtrlC::default();
+        assert!(
+            context
+                .lifecycle(&mut component, Lifecycle::FocusLost)
+                .is_none()
+        );
+        assert!(
+            context
+                .lifecycle(&mut component, Lifecycle::CloseRequested)
+                .is_some()
+        );
+        assert_eq!(
+            component.lifecycles,
+            [Lifecycle::FocusLost, Lifecycle::CloseRequested]
+        );
+        assert_eq!(component.inputs, [Input::Keyboard(keys::ETX)]);
+    }
+
+    #[test]
+    fn resize() {
+        struct LastResize(Option<Resize>);
+        impl Component for LastResize {
+            type Output = app::Output;
+            type State = ();
+            fn render(&self, _state: &(), _ctx: Ctx, _fb: &mut FrameBuffer) {}
+            fn update(&mut self, _state: &mut (), _ctx: Ctx, event: Event) -> app::Output {
+                if let Some(resize) = event.resize() {
+                    self.0 = Some(resize);
+                }
+                None
+            }
+            fn size(&self, _state: &(), ctx: Ctx) -> UCoord {
+                ctx.bounding_box.size()
+            }
+        }
+        let mut context = Context::new(UCoord::new(10, 4));
+        let mut component = LastResize(None);
+        context.resize(&mut component, UCoord::new(10, 4));
+        assert_eq!(component.0, None);
+        context.resize(&mut component, UCoord::new(20, 5));
+        assert_eq!(
+            component.0,
+            Some(Resize {
+                old_size: UCoord::new(10, 4),
+                new_size: UCoord::new(20, 5),
+            })
+        );
+        assert_eq!(context.size(), UCoord::new(20, 5));
+    }
+
+    #[test]
+    fn text_field_styled() {
+        let mut context = Context::new(UCoord::new(10, 4));
+        let mut component = text_field_app();
+        context.tick(&mut component, Duration::from_millis(16));
+        assert_matches_file(
+            &context.styled_text_snapshot(),
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/text_field.txt"),
+        );
+    }
+
+    #[test]
+    fn text_field_hardware_cursor() {
+        let mut context = Context::new(UCoord::new(10, 4));
+        let mut component = text_field_app_with_cursor(TextFieldCursor::Hardware(CursorShape::Bar));
+        context.tick(&mut component, Duration::from_millis(16));
+        assert_matches_file(
+            &context.styled_text_snapshot(),
+            concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/snapshots/text_field_hardware_cursor.txt"
+            ),
+        );
+    }
+}
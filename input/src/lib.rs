@@ -78,6 +78,24 @@ impl KeyboardInput {
             _ => None,
         }
     }
+
+    fn name(self) -> String {
+        use KeyboardInput::*;
+        use key_names::*;
+        match self {
+            Char(c) => c.to_string(),
+            Function(n) => format!("f{}", n),
+            Up => UP.to_string(),
+            Down => DOWN.to_string(),
+            Left => LEFT.to_string(),
+            Right => RIGHT.to_string(),
+            Home => HOME.to_string(),
+            End => END.to_string(),
+            PageUp => PAGE_UP.to_string(),
+            PageDown => PAGE_DOWN.to_string(),
+            Delete => DELETE.to_string(),
+        }
+    }
 }
 
 #[cfg(feature = "serialize")]
@@ -86,20 +104,9 @@ impl serde::Serialize for KeyboardInput {
     where
         S: serde::Serializer,
     {
-        use KeyboardInput::*;
-        use key_names::*;
         match self {
-            Char(c) => serializer.serialize_char(*c),
-            Function(n) => serializer.serialize_str(&format!("f{}", n)),
-            Up => serializer.serialize_str(UP),
-            Down => serializer.serialize_str(DOWN),
-            Left => serializer.serialize_str(LEFT),
-            Right => serializer.serialize_str(RIGHT),
-            Home => serializer.serialize_str(HOME),
-            End => serializer.serialize_str(END),
-            PageUp => serializer.serialize_str(PAGE_UP),
-            PageDown => serializer.serialize_str(PAGE_DOWN),
-            Delete => serializer.serialize_str(DELETE),
+            KeyboardInput::Char(c) => serializer.serialize_char(*c),
+            other => serializer.serialize_str(&other.name()),
         }
     }
 }
@@ -130,6 +137,296 @@ impl<'de> serde::Deserialize<'de> for KeyboardInput {
     }
 }
 
+/// The set of modifier keys held while a key was pressed
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct KeyModifiers {
+    bits: u8,
+}
+
+impl KeyModifiers {
+    pub const NONE: Self = Self { bits: 0 };
+    pub const CTRL: Self = Self { bits: 1 << 0 };
+    pub const ALT: Self = Self { bits: 1 << 1 };
+    pub const SHIFT: Self = Self { bits: 1 << 2 };
+    pub const SUPER: Self = Self { bits: 1 << 3 };
+
+    pub const fn union(self, other: Self) -> Self {
+        Self {
+            bits: self.bits | other.bits,
+        }
+    }
+
+    pub const fn difference(self, other: Self) -> Self {
+        Self {
+            bits: self.bits & !other.bits,
+        }
+    }
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.bits & other.bits == other.bits
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.bits == 0
+    }
+
+    pub const fn ctrl(self) -> bool {
+        self.contains(Self::CTRL)
+    }
+
+    pub const fn alt(self) -> bool {
+        self.contains(Self::ALT)
+    }
+
+    pub const fn shift(self) -> bool {
+        self.contains(Self::SHIFT)
+    }
+
+    pub const fn super_(self) -> bool {
+        self.contains(Self::SUPER)
+    }
+
+    pub const fn with_ctrl_if(self, ctrl: bool) -> Self {
+        if ctrl { self.union(Self::CTRL) } else { self }
+    }
+
+    pub const fn with_alt_if(self, alt: bool) -> Self {
+        if alt { self.union(Self::ALT) } else { self }
+    }
+
+    pub const fn with_shift_if(self, shift: bool) -> Self {
+        if shift { self.union(Self::SHIFT) } else { self }
+    }
+
+    pub const fn with_super_if(self, super_: bool) -> Self {
+        if super_ {
+            self.union(Self::SUPER)
+        } else {
+            self
+        }
+    }
+}
+
+impl std::ops::BitOr for KeyModifiers {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        self.union(rhs)
+    }
+}
+
+impl std::ops::BitOrAssign for KeyModifiers {
+    fn bitor_assign(&mut self, rhs: Self) {
+        *self = self.union(rhs);
+    }
+}
+
+/// Distinguishes between a key being pressed, auto-repeating while held, and released. Frontends
+/// only report `Repeat` and `Release` events if they have been configured to do so. Otherwise
+/// key repeats are reported as presses, and releases aren't reported at all.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum KeyEventKind {
+    #[default]
+    Press,
+    Repeat,
+    Release,
+}
+
+impl KeyEventKind {
+    /// Helper for frontends. Unless a frontend is configured to report key repeats and releases,
+    /// repeats are reported as presses and releases are ignored.
+    pub fn reportable(self, report_repeat_and_release: bool) -> Option<Self> {
+        match self {
+            _ if report_repeat_and_release => Some(self),
+            Self::Press | Self::Repeat => Some(Self::Press),
+            Self::Release => None,
+        }
+    }
+}
+
+/// A key event along with the modifier keys held at the time. For printable characters, shift is
+/// already accounted for by the character itself (e.g. shift+a is reported as 'A') so it does not
+/// appear in `modifiers`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct KeyEvent {
+    pub key: KeyboardInput,
+    pub modifiers: KeyModifiers,
+    pub kind: KeyEventKind,
+}
+
+impl KeyEvent {
+    /// Combine a key with the modifiers that were held when it was pressed. Shift is dropped from
+    /// the modifiers if the key is a printable character, as frontends resolve shift to the
+    /// shifted character.
+    pub fn new(key: KeyboardInput, modifiers: KeyModifiers) -> Self {
+        let modifiers = match key {
+            KeyboardInput::Char(ch) if !ch.is_control() => {
+                modifiers.difference(KeyModifiers::SHIFT)
+            }
+            _ => modifiers,
+        };
+        Self {
+            key,
+            modifiers,
+            kind: KeyEventKind::Press,
+        }
+    }
+
+    pub const fn unmodified(key: KeyboardInput) -> Self {
+        Self {
+            key,
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Press,
+        }
+    }
+
+    pub const fn with_kind(self, kind: KeyEventKind) -> Self {
+        Self { kind, ..self }
+    }
+
+    pub const fn is_press(&self) -> bool {
+        matches!(self.kind, KeyEventKind::Press)
+    }
+
+    pub const fn is_repeat(&self) -> bool {
+        matches!(self.kind, KeyEventKind::Repeat)
+    }
+
+    pub const fn is_release(&self) -> bool {
+        matches!(self.kind, KeyEventKind::Release)
+    }
+
+    /// Convert to an `Input`. Key presses without modifiers are reported as `Input::Keyboard` so
+    /// components which don't care about modifiers, repeats or releases continue to work
+    /// unchanged.
+    pub const fn input(self) -> Input {
+        if self.modifiers.is_empty() && self.is_press() {
+            Input::Keyboard(self.key)
+        } else {
+            Input::Key(self)
+        }
+    }
+}
+
+impl From<KeyboardInput> for KeyEvent {
+    fn from(key: KeyboardInput) -> Self {
+        Self::unmodified(key)
+    }
+}
+
+#[cfg(feature = "serialize")]
+mod modifier_names {
+    pub const CTRL: &str = "ctrl-";
+    pub const ALT: &str = "alt-";
+    pub const SHIFT: &str = "shift-";
+    pub const SUPER: &str = "super-";
+}
+
+#[cfg(feature = "serialize")]
+mod key_event_kind_names {
+    pub const REPEAT: &str = ":repeat";
+    pub const RELEASE: &str = ":release";
+}
+
+#[cfg(feature = "serialize")]
+impl KeyEvent {
+    fn try_from_str(mut s: &str) -> Option<Self> {
+        use key_event_kind_names::*;
+        use modifier_names::*;
+        let mut kind = KeyEventKind::Press;
+        if let Some(rest) = s.strip_suffix(REPEAT) {
+            kind = KeyEventKind::Repeat;
+            s = rest;
+        } else if let Some(rest) = s.strip_suffix(RELEASE) {
+            kind = KeyEventKind::Release;
+            s = rest;
+        }
+        let mut modifiers = KeyModifiers::NONE;
+        loop {
+            let (prefix, modifier) = if s.starts_with(CTRL) {
+                (CTRL, KeyModifiers::CTRL)
+            } else if s.starts_with(ALT) {
+                (ALT, KeyModifiers::ALT)
+            } else if s.starts_with(SHIFT) {
+                (SHIFT, KeyModifiers::SHIFT)
+            } else if s.starts_with(SUPER) {
+                (SUPER, KeyModifiers::SUPER)
+            } else {
+                break;
+            };
+            // a lone "-" is the minus key rather than the end of a modifier
+            if s.len() == prefix.len() {
+                break;
+            }
+            modifiers |= modifier;
+            s = &s[prefix.len()..];
+        }
+        KeyboardInput::try_from_str(s).map(|key| Self {
+            key,
+            modifiers,
+            kind,
+        })
+    }
+
+    fn name(self) -> String {
+        use modifier_names::*;
+        let mut s = String::new();
+        if self.modifiers.ctrl() {
+            s.push_str(CTRL);
+        }
+        if self.modifiers.alt() {
+            s.push_str(ALT);
+        }
+        if self.modifiers.shift() {
+            s.push_str(SHIFT);
+        }
+        if self.modifiers.super_() {
+            s.push_str(SUPER);
+        }
+        s.push_str(&self.key.name());
+        match self.kind {
+            KeyEventKind::Press => (),
+            KeyEventKind::Repeat => s.push_str(key_event_kind_names::REPEAT),
+            KeyEventKind::Release => s.push_str(key_event_kind_names::RELEASE),
+        }
+        s
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl serde::Serialize for KeyEvent {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.name())
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl<'de> serde::Deserialize<'de> for KeyEvent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct Visitor;
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = KeyEvent;
+
+            fn expecting(&self, formatter: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                formatter.write_str("a key event description")
+            }
+
+            fn visit_str<E>(self, s: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                KeyEvent::try_from_str(s).ok_or_else(|| E::custom(format!("couldn't parse {}", s)))
+            }
+        }
+        deserializer.deserialize_str(Visitor)
+    }
+}
+
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum MouseInput {
@@ -142,7 +439,8 @@ pub enum MouseInput {
         coord: ICoord,
     },
     MouseRelease {
-        // some platforms (e.g. ansi terminal) don't report the button that was released
+        // some platforms (e.g. ansi terminals without SGR mouse reporting) don't report the button
+        // that was released
         button: Result<MouseButton, NotSupported>,
         coord: ICoord,
     },
@@ -226,6 +524,10 @@ pub enum InputPolicy {
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Input {
     Keyboard(KeyboardInput),
+    /// A key pressed while holding modifier keys, or a key repeat or release (if the frontend is
+    /// configured to report them). Key presses without modifiers are reported as
+    /// `Input::Keyboard`.
+    Key(KeyEvent),
     Mouse(MouseInput),
     #[cfg(feature = "gamepad")]
     Gamepad(GamepadInput),
@@ -234,7 +536,7 @@ pub enum Input {
 impl Input {
     pub fn is_keyboard(&self) -> bool {
         match self {
-            Input::Keyboard(_) => true,
+            Input::Keyboard(_) | Input::Key(_) => true,
             Input::Mouse(_) => false,
             #[cfg(feature = "gamepad")]
             Input::Gamepad(_) => false,
@@ -243,7 +545,7 @@ impl Input {
 
     pub fn is_mouse(&self) -> bool {
         match self {
-            Input::Keyboard(_) => false,
+            Input::Keyboard(_) | Input::Key(_) => false,
             Input::Mouse(_) => true,
             #[cfg(feature = "gamepad")]
             Input::Gamepad(_) => false,
@@ -253,8 +555,7 @@ impl Input {
     #[cfg(feature = "gamepad")]
     pub fn is_gamepad(&self) -> bool {
         match self {
-            Input::Keyboard(_) => false,
-            Input::Mouse(_) => false,
+            Input::Keyboard(_) | Input::Key(_) | Input::Mouse(_) => false,
             Input::Gamepad(_) => true,
         }
     }
@@ -262,15 +563,31 @@ impl Input {
     pub fn keyboard(self) -> Option<KeyboardInput> {
         match self {
             Input::Keyboard(keyboard_input) => Some(keyboard_input),
+            Input::Key(_) | Input::Mouse(_) => None,
+            #[cfg(feature = "gamepad")]
+            Input::Gamepad(_) => None,
+        }
+    }
+
+    /// Returns any key event, along with the modifiers held at the time (if any)
+    pub fn key_event(self) -> Option<KeyEvent> {
+        match self {
+            Input::Keyboard(keyboard_input) => Some(KeyEvent::unmodified(keyboard_input)),
+            Input::Key(key_event) => Some(key_event),
             Input::Mouse(_) => None,
             #[cfg(feature = "gamepad")]
             Input::Gamepad(_) => None,
         }
     }
 
+    /// Returns any key press (including key repeats), ignoring key releases
+    pub fn key_press(self) -> Option<KeyEvent> {
+        self.key_event().filter(|key_event| !key_event.is_release())
+    }
+
     pub fn mouse(self) -> Option<MouseInput> {
         match self {
-            Input::Keyboard(_) => None,
+            Input::Keyboard(_) | Input::Key(_) => None,
             Input::Mouse(mouse_input) => Some(mouse_input),
             #[cfg(feature = "gamepad")]
             Input::Gamepad(_) => None,
@@ -280,7 +597,7 @@ impl Input {
     #[cfg(feature = "gamepad")]
     pub fn gamepad(self) -> Option<GamepadInput> {
         match self {
-            Input::Keyboard(_) | Input::Mouse(_) => None,
+            Input::Keyboard(_) | Input::Key(_) | Input::Mouse(_) => None,
             Input::Gamepad(gamepad_input) => Some(gamepad_input),
         }
     }
@@ -343,6 +660,60 @@ mod serde_test {
         t(KeyboardInput::Char('☃'));
     }
 
+    #[test]
+    fn reversable_key_event() {
+        use super::{KeyEvent, KeyEventKind, KeyModifiers, KeyboardInput};
+        fn t(s: &str, key_event: KeyEvent) {
+            let json = format!("\"{}\"", s);
+            assert_eq!(serde_json::to_string(&key_event).unwrap(), json);
+            assert_eq!(serde_json::from_str::<KeyEvent>(&json).unwrap(), key_event);
+        }
+        t(
+            "ctrl-s",
+            KeyEvent::new(KeyboardInput::Char('s'), KeyModifiers::CTRL),
+        );
+        t(
+            "alt-\\r",
+            KeyEvent::new(KeyboardInput::Char('\r'), KeyModifiers::ALT),
+        );
+        t(
+            "ctrl-alt-shift-super-up",
+            KeyEvent::new(
+                KeyboardInput::Up,
+                KeyModifiers::CTRL | KeyModifiers::ALT | KeyModifiers::SHIFT | KeyModifiers::SUPER,
+            ),
+        );
+        t(
+            "shift-f5",
+            KeyEvent::new(KeyboardInput::Function(5), KeyModifiers::SHIFT),
+        );
+        t(
+            "ctrl--",
+            KeyEvent::new(KeyboardInput::Char('-'), KeyModifiers::CTRL),
+        );
+        t("-", KeyEvent::unmodified(KeyboardInput::Char('-')));
+        t(
+            "ctrl-s:release",
+            KeyEvent::new(KeyboardInput::Char('s'), KeyModifiers::CTRL)
+                .with_kind(KeyEventKind::Release),
+        );
+        t(
+            "down:repeat",
+            KeyEvent::unmodified(KeyboardInput::Down).with_kind(KeyEventKind::Repeat),
+        );
+        t(
+            "::release",
+            KeyEvent::unmodified(KeyboardInput::Char(':')).with_kind(KeyEventKind::Release),
+        );
+        t(
+            "ctrl-S",
+            KeyEvent::new(
+                KeyboardInput::Char('S'),
+                KeyModifiers::CTRL | KeyModifiers::SHIFT,
+            ),
+        );
+    }
+
     #[test]
     fn example() {
         use super::KeyboardInput;
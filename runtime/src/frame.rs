@@ -0,0 +1,180 @@
+use std::time::Duration;
+
+/// Frames which are due within this much time are started early by `FrameScheduler::is_frame_due`,
+/// so that frontends driven by the display's refresh rate don't skip frames due to jitter
+const FRAME_DUE_TOLERANCE: Duration = Duration::from_millis(1);
+
+/// How often frames are rendered
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameRate {
+    /// Aim to render the given number of frames per second. Frame rates which aren't positive
+    /// and finite (or are too small for the duration of a frame to be represented) are treated
+    /// as `Unlimited`.
+    Fps(f64),
+    /// Render frames as often as possible. Graphical frontends which synchronise with the
+    /// display's refresh rate (vsync) render a frame per refresh.
+    Unlimited,
+}
+
+/// The duration reported by the `Event::Tick` delivered at the start of each frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timestep {
+    /// Report the given duration on each frame regardless of how much time actually passed. This
+    /// makes logic driven by ticks deterministic, but it runs slow if frames take longer than
+    /// intended.
+    Fixed(Duration),
+    /// Report the real time which passed since the previous frame, up to
+    /// `FrameConfig::max_step`
+    Elapsed,
+}
+
+/// Configuration of the timing of frames, shared by all frontends so that logic driven by
+/// `Event::Tick` behaves the same regardless of frontend
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameConfig {
+    pub frame_rate: FrameRate,
+    pub timestep: Timestep,
+    /// The longest duration reported by a single tick when using `Timestep::Elapsed`. This
+    /// prevents logic from trying to catch up on a large amount of time in one step, e.g. after
+    /// the application was suspended.
+    pub max_step: Duration,
+}
+
+impl FrameConfig {
+    pub const DEFAULT: Self = Self {
+        frame_rate: FrameRate::Fps(60.),
+        timestep: Timestep::Elapsed,
+        max_step: Duration::from_millis(250),
+    };
+
+    /// The intended duration of each frame, or `None` for unlimited frame rates
+    pub fn frame_duration(&self) -> Option<Duration> {
+        match self.frame_rate {
+            FrameRate::Fps(fps) if fps > 0. && fps.is_finite() => {
+                Duration::try_from_secs_f64(1. / fps).ok()
+            }
+            FrameRate::Fps(_) | FrameRate::Unlimited => None,
+        }
+    }
+}
+
+impl Default for FrameConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Decides when frames should start, and the duration to report on each frame, according to a
+/// `FrameConfig`. Times are passed in as the duration since an arbitrary fixed point in time (as
+/// `std::time::Instant` isn't available on all platforms).
+#[derive(Debug, Clone)]
+pub struct FrameScheduler {
+    config: FrameConfig,
+    last_frame: Option<Duration>,
+}
+
+impl FrameScheduler {
+    pub fn new(config: FrameConfig) -> Self {
+        Self {
+            config,
+            last_frame: None,
+        }
+    }
+
+    pub fn config(&self) -> FrameConfig {
+        self.config
+    }
+
+    /// How long after `now` the next frame should start
+    pub fn until_next_frame(&self, now: Duration) -> Duration {
+        match (self.last_frame, self.config.frame_duration()) {
+            (Some(last_frame), Some(frame_duration)) => {
+                (last_frame + frame_duration).saturating_sub(now)
+            }
+            _ => Duration::ZERO,
+        }
+    }
+
+    /// Whether enough time has passed since the last frame for a new frame to start
+    pub fn is_frame_due(&self, now: Duration) -> bool {
+        self.until_next_frame(now) <= FRAME_DUE_TOLERANCE
+    }
+
+    /// Record that a frame is starting, returning the duration to report in its tick
+    pub fn start_frame(&mut self, now: Duration) -> Duration {
+        let since_last_frame = match self.last_frame {
+            Some(last_frame) => now.saturating_sub(last_frame),
+            None => self.config.frame_duration().unwrap_or(Duration::ZERO),
+        };
+        self.last_frame = Some(now);
+        match self.config.timestep {
+            Timestep::Fixed(step) => step,
+            Timestep::Elapsed => since_last_frame.min(self.config.max_step),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn elapsed() {
+        let mut scheduler = FrameScheduler::new(FrameConfig {
+            frame_rate: FrameRate::Fps(50.),
+            timestep: Timestep::Elapsed,
+            max_step: ms(100),
+        });
+        assert_eq!(scheduler.start_frame(ms(1000)), ms(20));
+        assert!(!scheduler.is_frame_due(ms(1005)));
+        assert_eq!(scheduler.until_next_frame(ms(1005)), ms(15));
+        assert!(scheduler.is_frame_due(ms(1019)));
+        assert_eq!(scheduler.start_frame(ms(1025)), ms(25));
+        assert_eq!(scheduler.start_frame(ms(2000)), ms(100));
+    }
+
+    #[test]
+    fn fixed() {
+        let mut scheduler = FrameScheduler::new(FrameConfig {
+            frame_rate: FrameRate::Unlimited,
+            timestep: Timestep::Fixed(ms(16)),
+            ..FrameConfig::DEFAULT
+        });
+        assert_eq!(scheduler.start_frame(ms(0)), ms(16));
+        assert!(scheduler.is_frame_due(ms(1)));
+        assert_eq!(scheduler.start_frame(ms(500)), ms(16));
+    }
+
+    #[test]
+    fn unlimited_elapsed() {
+        let mut scheduler = FrameScheduler::new(FrameConfig {
+            frame_rate: FrameRate::Unlimited,
+            timestep: Timestep::Elapsed,
+            max_step: ms(100),
+        });
+        // there's no previous frame to measure from, nor an intended frame duration to assume
+        assert!(scheduler.is_frame_due(ms(1000)));
+        assert_eq!(scheduler.start_frame(ms(1000)), Duration::ZERO);
+        assert!(scheduler.is_frame_due(ms(1000)));
+        assert_eq!(scheduler.until_next_frame(ms(1003)), Duration::ZERO);
+        assert_eq!(scheduler.start_frame(ms(1003)), ms(3));
+        assert_eq!(scheduler.start_frame(ms(1500)), ms(100));
+    }
+
+    #[test]
+    fn invalid_frame_rates() {
+        for fps in [0., -60., f64::NAN, f64::INFINITY, f64::MIN_POSITIVE] {
+            let mut scheduler = FrameScheduler::new(FrameConfig {
+                frame_rate: FrameRate::Fps(fps),
+                ..FrameConfig::DEFAULT
+            });
+            assert_eq!(scheduler.config().frame_duration(), None);
+            assert_eq!(scheduler.start_frame(ms(1000)), Duration::ZERO);
+            assert!(scheduler.is_frame_due(ms(1000)));
+        }
+    }
+}
@@ -1,28 +1,106 @@
 pub use chargrid_core::{
-    Component, FrameBuffer, FrameBufferCell, ICoord, Rgba32, UCoord, app, rgb_int,
+    Component, Cursor, CursorShape, DisplayColours, FrameBuffer, FrameBufferCell, Grapheme, ICoord,
+    Lifecycle, Rgba32, UCoord, UnderlineStyle, app, rgb_int,
+};
+use chargrid_core::{
+    Event, Resize,
+    input::{Input, keys},
 };
-use chargrid_core::{Event, input::Input};
 use std::time::Duration;
 
-pub fn on_input<C>(component: &mut C, input: Input, frame_buffer: &FrameBuffer) -> app::Output
+mod frame;
+pub use frame::{FrameConfig, FrameRate, FrameScheduler, Timestep};
+
+pub fn on_input<C, O>(component: &mut C, input: Input, frame_buffer: &FrameBuffer) -> Option<O>
 where
-    C: Component<State = (), Output = app::Output>,
+    C: Component<State = (), Output = Option<O>>,
 {
     component.update(&mut (), frame_buffer.default_ctx(), Event::Input(input))
 }
 
-pub fn on_frame<C>(
+/// Deliver text pasted from the clipboard to the component as a single event
+pub fn on_paste<C, O>(component: &mut C, text: &str, frame_buffer: &FrameBuffer) -> Option<O>
+where
+    C: Component<State = (), Output = Option<O>>,
+{
+    component.update(&mut (), frame_buffer.default_ctx(), Event::Paste(text))
+}
+
+/// Deliver a lifecycle event to the component. If the component doesn't yield anything in
+/// response to `Lifecycle::CloseRequested`, it's then sent ctrl+c (`keys::ETX`), which is how
+/// closing the window was reported before lifecycle events existed.
+pub fn on_lifecycle<C, O>(
+    component: &mut C,
+    lifecycle: Lifecycle,
+    frame_buffer: &FrameBuffer,
+) -> Option<O>
+where
+    C: Component<State = (), Output = Option<O>>,
+{
+    let output = component.update(
+        &mut (),
+        frame_buffer.default_ctx(),
+        Event::Lifecycle(lifecycle),
+    );
+    if output.is_none() && lifecycle == Lifecycle::CloseRequested {
+        return on_input(component, Input::Keyboard(keys::ETX), frame_buffer);
+    }
+    output
+}
+
+/// Resize the frame buffer, then notify the component of the change. Does nothing if the frame
+/// buffer is already the given size.
+pub fn on_resize<C, O>(
+    component: &mut C,
+    new_size: UCoord,
+    frame_buffer: &mut FrameBuffer,
+) -> Option<O>
+where
+    C: Component<State = (), Output = Option<O>>,
+{
+    let old_size = frame_buffer.size();
+    if new_size == old_size {
+        return None;
+    }
+    frame_buffer.resize(new_size);
+    component.update(
+        &mut (),
+        frame_buffer.default_ctx(),
+        Event::Resize(Resize { old_size, new_size }),
+    )
+}
+
+/// Notify the component that the window changed size without changing the size of the grid, as
+/// happens in frontends which scale a fixed-size grid to fit their window
+pub fn on_window_resize<C, O>(component: &mut C, frame_buffer: &FrameBuffer) -> Option<O>
+where
+    C: Component<State = (), Output = Option<O>>,
+{
+    let size = frame_buffer.size();
+    component.update(
+        &mut (),
+        frame_buffer.default_ctx(),
+        Event::Resize(Resize {
+            old_size: size,
+            new_size: size,
+        }),
+    )
+}
+
+pub fn on_frame<C, O>(
     component: &mut C,
     since_last_frame: Duration,
     frame_buffer: &mut FrameBuffer,
-) -> app::Output
+) -> Option<O>
 where
-    C: Component<State = (), Output = app::Output>,
+    C: Component<State = (), Output = Option<O>>,
 {
     let ctx = frame_buffer.default_ctx();
     if let Some(output) = component.update(&mut (), ctx, Event::Tick(since_last_frame)) {
         return Some(output);
     }
+    // components request the cursor each time they render
+    frame_buffer.clear_cursor();
     component.render(&(), ctx, frame_buffer);
     None
 }
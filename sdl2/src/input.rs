@@ -1,4 +1,5 @@
-use chargrid_input::{KeyboardInput, keys};
+use chargrid_input::{Input, KeyEvent, KeyEventKind, KeyModifiers, KeyboardInput, keys};
+use sdl2::clipboard::ClipboardUtil;
 use sdl2::keyboard::{Keycode, Mod};
 
 macro_rules! convert_char_shift {
@@ -7,7 +8,7 @@ macro_rules! convert_char_shift {
     };
 }
 
-pub fn sdl2_to_chargrid(keycode: Keycode, mod_: Mod) -> Option<KeyboardInput> {
+pub fn sdl2_to_chargrid(keycode: Keycode, mod_: Mod, kind: KeyEventKind) -> Option<KeyEvent> {
     let shift = mod_.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
     let key = match keycode {
         Keycode::Space => KeyboardInput::Char(' '),
@@ -98,5 +99,23 @@ pub fn sdl2_to_chargrid(keycode: Keycode, mod_: Mod) -> Option<KeyboardInput> {
         Keycode::Delete => KeyboardInput::Delete,
         _ => return None,
     };
-    Some(key)
+    let modifiers = KeyModifiers::NONE
+        .with_ctrl_if(mod_.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD))
+        .with_alt_if(mod_.intersects(Mod::LALTMOD | Mod::RALTMOD))
+        .with_shift_if(shift)
+        .with_super_if(mod_.intersects(Mod::LGUIMOD | Mod::RGUIMOD));
+    Some(KeyEvent::new(key, modifiers).with_kind(kind))
+}
+
+/// If the input is a paste shortcut (ctrl+v, or cmd+v on macos), returns the contents of the
+/// clipboard
+pub fn paste_from_clipboard(input: Input, clipboard: &ClipboardUtil) -> Option<String> {
+    let key_event = input.key_press()?;
+    let is_paste_shortcut = key_event.key == KeyboardInput::Char('v')
+        && (key_event.modifiers == KeyModifiers::CTRL
+            || key_event.modifiers == KeyModifiers::SUPER);
+    if !is_paste_shortcut || !clipboard.has_clipboard_text() {
+        return None;
+    }
+    clipboard.clipboard_text().ok()
 }
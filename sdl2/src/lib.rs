@@ -1,16 +1,22 @@
 #[cfg(feature = "gamepad")]
 use chargrid_gamepad::GamepadContext;
-use chargrid_input::{Input, MouseButton, MouseInput, ScrollDirection, keys};
-use chargrid_runtime::{Component, FrameBuffer, ICoord, Rgba32, UCoord, app, on_frame, on_input};
-use sdl2::{event::Event, pixels::Color, rect::Rect, rwops::RWops, surface::Surface, ttf};
-use std::{
-    thread,
-    time::{Duration, Instant},
+use chargrid_input::{Input, KeyEventKind, MouseButton, MouseInput, ScrollDirection};
+use chargrid_runtime::{
+    Component, CursorShape, FrameBuffer, FrameScheduler, ICoord, Lifecycle, Rgba32, UCoord,
+    UnderlineStyle, app, on_frame, on_input, on_lifecycle, on_paste, on_window_resize,
 };
+pub use chargrid_runtime::{FrameConfig, FrameRate, Timestep};
+use sdl2::{
+    event::{Event, WindowEvent},
+    pixels::Color,
+    rect::Rect,
+    rwops::RWops,
+    surface::Surface,
+    ttf,
+};
+use std::{thread, time::Instant};
 mod input;
 
-const FRAME_DURATION: Duration = Duration::from_micros(1_000_000 / 60);
-
 pub struct FontBytes {
     pub normal: Vec<u8>,
     pub bold: Vec<u8>,
@@ -19,6 +25,19 @@ pub struct FontBytes {
 struct FontGeneric<T> {
     normal: T,
     bold: T,
+    italic: T,
+    bold_italic: T,
+}
+
+impl<T> FontGeneric<T> {
+    fn select(&self, bold: bool, italic: bool) -> &T {
+        match (bold, italic) {
+            (false, false) => &self.normal,
+            (true, false) => &self.bold,
+            (false, true) => &self.italic,
+            (true, true) => &self.bold_italic,
+        }
+    }
 }
 
 type FontSdl2Ttf<'ttf_context, 'font_data> = FontGeneric<ttf::Font<'ttf_context, 'font_data>>;
@@ -40,9 +59,17 @@ impl FontBytes {
         ttf_context: &'ttf_context ttf::Sdl2TtfContext,
         pt_size: u16,
     ) -> FontSdl2Ttf<'ttf_context, 'font_data> {
+        // italic text is drawn by slanting the glyphs of the regular fonts
+        let load_italic = |font_data| {
+            let mut font = Self::load_single(font_data, ttf_context, pt_size);
+            font.set_style(ttf::FontStyle::ITALIC);
+            font
+        };
         FontGeneric {
             normal: Self::load_single(&self.normal, ttf_context, pt_size),
             bold: Self::load_single(&self.bold, ttf_context, pt_size),
+            italic: load_italic(&self.normal),
+            bold_italic: load_italic(&self.bold),
         }
     }
 }
@@ -53,6 +80,47 @@ pub struct Dimensions<T> {
     pub height: T,
 }
 
+fn rgba32_to_color(Rgba32 { r, g, b, a }: Rgba32) -> Color {
+    Color::RGBA(r, g, b, a)
+}
+
+/// The rectangles making up an underline of the given style whose top-left corner is at
+/// (`left`, `top`)
+fn underline_rects(
+    style: UnderlineStyle,
+    left: i32,
+    top: i32,
+    width: u32,
+    thickness: u32,
+) -> Vec<Rect> {
+    match style {
+        UnderlineStyle::Single => vec![Rect::new(left, top, width, thickness)],
+        UnderlineStyle::Double => vec![
+            Rect::new(left, top, width, thickness),
+            Rect::new(left, top + 2 * thickness as i32, width, thickness),
+        ],
+        UnderlineStyle::Dotted => (0..width)
+            .step_by(2 * thickness as usize)
+            .map(|x| Rect::new(left + x as i32, top, thickness, thickness))
+            .collect(),
+        UnderlineStyle::Dashed => {
+            let dash = (width / 4).max(1);
+            [0, width / 2]
+                .into_iter()
+                .map(|x| Rect::new(left + (dash / 2 + x) as i32, top, dash, thickness))
+                .collect()
+        }
+        UnderlineStyle::Curly => (0..width)
+            .map(|x| {
+                // one period of a sine wave per cell
+                let phase = x as f64 * std::f64::consts::TAU / width as f64;
+                let offset = (phase.sin() * thickness as f64).round() as i32;
+                Rect::new(left + x as i32, top + offset, 1, thickness)
+            })
+            .collect(),
+    }
+}
+
 pub struct Config {
     pub title: String,
     pub font_bytes: FontBytes,
@@ -63,6 +131,11 @@ pub struct Config {
     pub underline_width_cell_ratio: f64,
     pub underline_top_offset_cell_ratio: f64,
     pub resizable: bool,
+    /// Report key repeats and releases as `Input::Key` events. When false, key repeats are
+    /// reported as presses and releases aren't reported.
+    pub key_repeat_and_release: bool,
+    /// With `FrameRate::Unlimited`, frames are synchronised with the display's refresh rate
+    pub frame_config: FrameConfig,
 }
 
 pub struct Context {
@@ -83,6 +156,7 @@ impl Context {
         let video_subsys = sdl_context
             .video()
             .expect("failed to connect to video subsystem");
+        let clipboard = video_subsys.clipboard();
         let ttf_context = ttf::init().expect("failed to initialize ttf context");
         let font = config.font_bytes.load(&ttf_context, config.font_point_size);
         let mut window_builder = video_subsys.window(
@@ -95,11 +169,11 @@ impl Context {
             window_builder.resizable();
         }
         let window = window_builder.build().expect("failed to open window");
-        let mut canvas = window
-            .into_canvas()
-            .accelerated()
-            .build()
-            .expect("failed to create canvas");
+        let mut canvas_builder = window.into_canvas().accelerated();
+        if config.frame_config.frame_rate == FrameRate::Unlimited {
+            canvas_builder = canvas_builder.present_vsync();
+        }
+        let mut canvas = canvas_builder.build().expect("failed to create canvas");
         #[cfg(feature = "gamepad")]
         let mut gamepad_context = GamepadContext::new();
         canvas.set_draw_color(Color::RGBA(0, 0, 0, 255));
@@ -122,8 +196,10 @@ impl Context {
         };
         let mut current_mouse_position = ICoord::new(0, 0);
         let mut chargrid_frame_buffer = FrameBuffer::new(grid_size);
+        let start = Instant::now();
+        let mut frame_scheduler = FrameScheduler::new(config.frame_config);
         'mainloop: loop {
-            let frame_start = Instant::now();
+            let frame_start = start.elapsed();
             #[cfg(feature = "gamepad")]
             for input in gamepad_context.drain_input() {
                 if let Some(app::Exit) = on_input(
@@ -139,12 +215,63 @@ impl Context {
                 .expect("failed to create event pump")
                 .poll_iter()
             {
+                if let Event::Window {
+                    win_event: WindowEvent::SizeChanged(..),
+                    ..
+                } = event
+                {
+                    if let Some(app::Exit) =
+                        on_window_resize(&mut component, &chargrid_frame_buffer)
+                    {
+                        break 'mainloop;
+                    }
+                    continue;
+                }
+                let lifecycle = match event {
+                    Event::Quit { .. } => Some(Lifecycle::CloseRequested),
+                    Event::Window { win_event, .. } => match win_event {
+                        WindowEvent::FocusGained => Some(Lifecycle::FocusGained),
+                        WindowEvent::FocusLost => Some(Lifecycle::FocusLost),
+                        WindowEvent::Minimized => Some(Lifecycle::Suspended),
+                        WindowEvent::Restored => Some(Lifecycle::Resumed),
+                        _ => None,
+                    },
+                    Event::AppWillEnterBackground { .. } => Some(Lifecycle::Suspended),
+                    Event::AppDidEnterForeground { .. } => Some(Lifecycle::Resumed),
+                    _ => None,
+                };
+                if let Some(lifecycle) = lifecycle {
+                    if let Some(app::Exit) =
+                        on_lifecycle(&mut component, lifecycle, &chargrid_frame_buffer)
+                    {
+                        break 'mainloop;
+                    }
+                    continue;
+                }
                 let input = match event {
                     Event::KeyDown {
+                        keycode: Some(keycode),
+                        keymod,
+                        repeat,
+                        ..
+                    } => {
+                        let kind = if repeat {
+                            KeyEventKind::Repeat
+                        } else {
+                            KeyEventKind::Press
+                        };
+                        kind.reportable(config.key_repeat_and_release)
+                            .and_then(|kind| input::sdl2_to_chargrid(keycode, keymod, kind))
+                            .map(|key_event| key_event.input())
+                    }
+                    Event::KeyUp {
                         keycode: Some(keycode),
                         keymod,
                         ..
-                    } => input::sdl2_to_chargrid(keycode, keymod).map(Input::Keyboard),
+                    } => KeyEventKind::Release
+                        .reportable(config.key_repeat_and_release)
+                        .and_then(|kind| input::sdl2_to_chargrid(keycode, keymod, kind))
+                        .map(|key_event| key_event.input()),
                     Event::MouseMotion {
                         mousestate, x, y, ..
                     } => {
@@ -217,17 +344,22 @@ impl Context {
                             })
                         })
                     }
-                    Event::Quit { .. } => Some(Input::Keyboard(keys::ETX)),
                     _ => None,
                 };
-                if let Some(input) = input
-                    && let Some(app::Exit) = on_input(&mut component, input, &chargrid_frame_buffer)
-                {
+                let Some(input) = input else {
+                    continue;
+                };
+                let output = match input::paste_from_clipboard(input, &clipboard) {
+                    Some(text) => on_paste(&mut component, &text, &chargrid_frame_buffer),
+                    None => on_input(&mut component, input, &chargrid_frame_buffer),
+                };
+                if let Some(app::Exit) = output {
                     break 'mainloop;
                 }
             }
+            let since_last_frame = frame_scheduler.start_frame(frame_start);
             if let Some(app::Exit) =
-                on_frame(&mut component, FRAME_DURATION, &mut chargrid_frame_buffer)
+                on_frame(&mut component, since_last_frame, &mut chargrid_frame_buffer)
             {
                 break;
             }
@@ -243,6 +375,12 @@ impl Context {
                     },
                 )
                 .expect("failed to clear surface");
+            let time = start.elapsed();
+            let cursor = chargrid_frame_buffer
+                .cursor()
+                .filter(|cursor| cursor.is_visible_at(time));
+            let thickness = (config.underline_width_cell_ratio * config.cell_dimensions_px.height)
+                .max(1.) as u32;
             for (coord, cell) in chargrid_frame_buffer.enumerate() {
                 let dst = Rect::new(
                     (coord.x as f64 * config.cell_dimensions_px.width) as i32,
@@ -250,38 +388,53 @@ impl Context {
                     config.cell_dimensions_px.width as u32,
                     config.cell_dimensions_px.height as u32,
                 );
-                let bg_colour = {
-                    let Rgba32 { r, g, b, a } = cell.background;
-                    Color::RGBA(r, g, b, a)
-                };
-                let fg_colour = {
-                    let Rgba32 { r, g, b, a } = cell.foreground;
-                    Color::RGBA(r, g, b, a)
-                };
+                let colours = cell.display_colours_at(time);
+                let mut bg_colour = rgba32_to_color(colours.background);
+                let mut fg_colour = rgba32_to_color(colours.foreground);
+                if let Some(cursor) = cursor
+                    && cursor.coord == coord
+                    && cursor.shape == CursorShape::Block
+                {
+                    // a block cursor inverts the colours of its cell
+                    std::mem::swap(&mut bg_colour, &mut fg_colour);
+                }
                 text_surface
                     .fill_rect(dst, bg_colour)
                     .expect("failed to fill background");
                 if cell.underline {
+                    let top = dst.top()
+                        + (config.underline_top_offset_cell_ratio
+                            * config.cell_dimensions_px.height) as i32;
+                    text_surface
+                        .fill_rects(
+                            &underline_rects(
+                                cell.underline_style,
+                                dst.left(),
+                                top,
+                                dst.width(),
+                                thickness,
+                            ),
+                            rgba32_to_color(colours.underline),
+                        )
+                        .expect("failed to fill underline");
+                }
+                if cell.strikethrough {
                     let rect = Rect::new(
                         dst.left(),
-                        dst.top()
-                            + (config.underline_top_offset_cell_ratio
-                                * config.cell_dimensions_px.height)
-                                as i32,
-                        config.cell_dimensions_px.width as u32,
-                        (config.underline_width_cell_ratio * config.cell_dimensions_px.height)
-                            as u32,
+                        dst.top() + (dst.height() as i32 - thickness as i32) / 2,
+                        dst.width(),
+                        thickness,
                     );
                     text_surface
                         .fill_rect(rect, fg_colour)
-                        .expect("failed to fill underline");
+                        .expect("failed to fill strikethrough");
                 }
-                if cell.character == ' ' {
+                if cell.character.is_char(' ') || cell.is_continuation() {
                     continue;
                 }
-                let font = if cell.bold { &font.bold } else { &font.normal };
                 let surface = font
-                    .render_char(cell.character)
+                    .select(cell.bold, cell.italic)
+                    .render(cell.character.as_str())
                     .solid(fg_colour)
                     .expect("failed to render character");
                 let blit_dst = Rect::new(
@@ -296,6 +449,32 @@ impl Context {
                     .blit(None, &mut text_surface, Some(blit_dst))
                     .expect("failed to copy character to surface");
             }
+            if let Some(cursor) = cursor
+                && let Some(cell) = chargrid_frame_buffer.get(cursor.coord)
+            {
+                let left = (cursor.coord.x as f64 * config.cell_dimensions_px.width) as i32;
+                let top = (cursor.coord.y as f64 * config.cell_dimensions_px.height) as i32;
+                let rect = match cursor.shape {
+                    CursorShape::Block => None,
+                    CursorShape::Bar => Some(Rect::new(
+                        left,
+                        top,
+                        thickness,
+                        config.cell_dimensions_px.height as u32,
+                    )),
+                    CursorShape::Underline => Some(Rect::new(
+                        left,
+                        top + config.cell_dimensions_px.height as i32 - thickness as i32,
+                        config.cell_dimensions_px.width as u32,
+                        thickness,
+                    )),
+                };
+                if let Some(rect) = rect {
+                    text_surface
+                        .fill_rect(rect, rgba32_to_color(cell.foreground))
+                        .expect("failed to fill cursor");
+                }
+            }
             let text_texture = text_surface
                 .as_texture(&texture_creator)
                 .expect("failed to create texture from surface");
@@ -303,8 +482,8 @@ impl Context {
                 .copy(&text_texture, None, None)
                 .expect("failed to copy rendered character to canvas");
             canvas.present();
-            let since_frame_start = frame_start.elapsed();
-            if let Some(until_next_frame) = FRAME_DURATION.checked_sub(since_frame_start) {
+            let until_next_frame = frame_scheduler.until_next_frame(start.elapsed());
+            if !until_next_frame.is_zero() {
                 thread::sleep(until_next_frame);
             }
         }
@@ -101,3 +101,18 @@ pub fn key_from_js(key_code: u8, shift: bool) -> Option<KeyboardInput> {
     };
     Some(key)
 }
+
+pub fn key_event_from_js(
+    keyboard_event: &web_sys::KeyboardEvent,
+    kind: KeyEventKind,
+) -> Option<KeyEvent> {
+    let shift = keyboard_event.shift_key();
+    key_from_js(keyboard_event.key_code() as u8, shift).map(|key| {
+        let modifiers = KeyModifiers::NONE
+            .with_ctrl_if(keyboard_event.ctrl_key())
+            .with_alt_if(keyboard_event.alt_key())
+            .with_shift_if(shift)
+            .with_super_if(keyboard_event.meta_key());
+        KeyEvent::new(key, modifiers).with_kind(kind)
+    })
+}
@@ -4,8 +4,12 @@ mod input;
 use chargrid_gamepad::GamepadContext;
 pub use chargrid_input;
 pub use chargrid_input::{Input, MouseInput};
-use chargrid_input::{MouseButton, ScrollDirection};
-use chargrid_runtime::{Component, FrameBuffer, Rgba32, app, on_frame, on_input};
+use chargrid_input::{KeyEventKind, MouseButton, ScrollDirection};
+use chargrid_runtime::{
+    Component, CursorShape, FrameBuffer, FrameScheduler, Grapheme, Lifecycle, Rgba32,
+    UnderlineStyle, app, on_frame, on_input, on_lifecycle, on_paste, on_window_resize,
+};
+pub use chargrid_runtime::{FrameConfig, FrameRate, Timestep};
 use grid_2d::ICoord;
 pub use grid_2d::UCoord;
 use js_sys::Function;
@@ -14,19 +18,83 @@ use std::rc::Rc;
 pub use std::time::Duration;
 use wasm_bindgen::JsCast;
 use wasm_bindgen::prelude::*;
-use web_sys::{Element, HtmlElement, KeyboardEvent, MouseEvent, Node, WheelEvent};
+use web_sys::{
+    ClipboardEvent, CssStyleDeclaration, Element, HtmlElement, KeyboardEvent, MouseEvent, Node,
+    WheelEvent,
+};
 
 fn rgba32_to_web_colour(Rgba32 { r, g, b, a }: Rgba32) -> String {
     format!("rgba({},{},{},{})", r, g, b, a as f64 / 255.0)
 }
 
+/// The lines drawn through or under the text of a cell, which are set together with the
+/// text-decoration-* CSS properties
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+struct Decoration {
+    underline: Option<(UnderlineStyle, Rgba32)>,
+    strikethrough: bool,
+}
+
+impl Decoration {
+    fn apply(&self, element_style: &CssStyleDeclaration) {
+        let line = match (self.underline.is_some(), self.strikethrough) {
+            (false, false) => {
+                element_style
+                    .remove_property("text-decoration-line")
+                    .unwrap();
+                element_style
+                    .remove_property("text-decoration-style")
+                    .unwrap();
+                element_style
+                    .remove_property("text-decoration-color")
+                    .unwrap();
+                return;
+            }
+            (true, false) => "underline",
+            (false, true) => "line-through",
+            (true, true) => "underline line-through",
+        };
+        element_style
+            .set_property("text-decoration-line", line)
+            .unwrap();
+        // CSS only allows a single style and colour for all the lines of an element
+        match self.underline {
+            Some((style, colour)) => {
+                let style = match style {
+                    UnderlineStyle::Single => "solid",
+                    UnderlineStyle::Double => "double",
+                    UnderlineStyle::Curly => "wavy",
+                    UnderlineStyle::Dotted => "dotted",
+                    UnderlineStyle::Dashed => "dashed",
+                };
+                element_style
+                    .set_property("text-decoration-style", style)
+                    .unwrap();
+                element_style
+                    .set_property("text-decoration-color", &rgba32_to_web_colour(colour))
+                    .unwrap();
+            }
+            None => {
+                element_style
+                    .remove_property("text-decoration-style")
+                    .unwrap();
+                element_style
+                    .remove_property("text-decoration-color")
+                    .unwrap();
+            }
+        }
+    }
+}
+
 struct ElementCell {
     element: HtmlElement,
-    character: char,
+    character: Grapheme,
     bold: bool,
-    underline: bool,
+    italic: bool,
+    decoration: Decoration,
     foreground: Rgba32,
     background: Rgba32,
+    bar_cursor: bool,
 }
 
 impl ElementCell {
@@ -41,11 +109,13 @@ impl ElementCell {
             .unwrap();
         Self {
             element,
-            character: ' ',
+            character: Grapheme::from_char(' '),
             bold: false,
-            underline: false,
+            italic: false,
+            decoration: Decoration::default(),
             foreground: Rgba32::new_grey(0),
             background: Rgba32::new_grey(0),
+            bar_cursor: false,
         }
     }
 }
@@ -70,6 +140,8 @@ pub struct Context {
     element_grid: grid_2d::Grid<ElementCell>,
     chargrid_frame_buffer: FrameBuffer,
     container_element: Element,
+    key_repeat_and_release: bool,
+    frame_scheduler: FrameScheduler,
     #[cfg(feature = "gamepad")]
     gamepad: GamepadContext,
 }
@@ -142,49 +214,97 @@ impl Context {
             element_grid,
             chargrid_frame_buffer,
             container_element: document.get_element_by_id(container).unwrap(),
+            key_repeat_and_release: false,
+            frame_scheduler: FrameScheduler::new(FrameConfig::default()),
             #[cfg(feature = "gamepad")]
             gamepad: GamepadContext::new(),
         }
     }
 
-    fn render_internal(&mut self) {
-        for (chargrid_cell, element_cell) in self
+    /// Report key repeats and releases as `Input::Key` events. By default, key repeats are
+    /// reported as presses and releases aren't reported.
+    pub fn set_key_repeat_and_release(&mut self, key_repeat_and_release: bool) {
+        self.key_repeat_and_release = key_repeat_and_release;
+    }
+
+    /// Set the frame rate and the durations reported by `Event::Tick`. Frames are requested at
+    /// the rate of the loop method (with `LoopMethod::RequestAnimationFrame` this is the
+    /// display's refresh rate), so `FrameRate::Unlimited` renders a frame each time one is
+    /// requested.
+    pub fn set_frame_config(&mut self, frame_config: FrameConfig) {
+        self.frame_scheduler = FrameScheduler::new(frame_config);
+    }
+
+    /// Update the elements to match the frame buffer. The time (since an arbitrary fixed point)
+    /// is used to blink the cursor.
+    fn render_internal(&mut self, time: Duration) {
+        let cursor = self
+            .chargrid_frame_buffer
+            .cursor()
+            .filter(|cursor| cursor.is_visible_at(time));
+        for ((coord, chargrid_cell), element_cell) in self
             .chargrid_frame_buffer
-            .iter()
+            .enumerate()
             .zip(self.element_grid.iter_mut())
         {
             if element_cell.character != chargrid_cell.character {
                 element_cell.character = chargrid_cell.character;
-                let string = match chargrid_cell.character {
-                    ' ' => "&nbsp;".to_string(),
-                    other => other.to_string(),
+                let string = if chargrid_cell.character.is_char(' ') {
+                    "&nbsp;".to_string()
+                } else if chargrid_cell.is_continuation() {
+                    String::new()
+                } else {
+                    chargrid_cell.character.to_string()
                 };
                 element_cell.element.set_inner_html(&string);
             }
+            let colours = chargrid_cell.display_colours_at(time);
+            let mut foreground = colours.foreground;
+            let mut background = colours.background;
+            let mut underline = chargrid_cell
+                .underline
+                .then_some(chargrid_cell.underline_style);
+            let mut bar_cursor = false;
+            if let Some(cursor) = cursor
+                && cursor.coord == coord
+            {
+                match cursor.shape {
+                    // a block cursor inverts the colours of its cell
+                    CursorShape::Block => std::mem::swap(&mut foreground, &mut background),
+                    CursorShape::Underline => underline = Some(UnderlineStyle::Single),
+                    CursorShape::Bar => bar_cursor = true,
+                }
+            }
             let element_style = element_cell.element.style();
-            if element_cell.foreground != chargrid_cell.foreground {
-                element_cell.foreground = chargrid_cell.foreground;
+            if element_cell.foreground != foreground {
+                element_cell.foreground = foreground;
                 element_style
-                    .set_property("color", &rgba32_to_web_colour(chargrid_cell.foreground))
+                    .set_property("color", &rgba32_to_web_colour(foreground))
                     .unwrap();
             }
-            if element_cell.background != chargrid_cell.background {
-                element_cell.background = chargrid_cell.background;
+            if element_cell.background != background {
+                element_cell.background = background;
                 element_style
-                    .set_property(
-                        "background-color",
-                        &rgba32_to_web_colour(chargrid_cell.background),
-                    )
+                    .set_property("background-color", &rgba32_to_web_colour(background))
                     .unwrap();
             }
-            if element_cell.underline != chargrid_cell.underline {
-                element_cell.underline = chargrid_cell.underline;
-                if chargrid_cell.underline {
+            let decoration = Decoration {
+                underline: underline.map(|style| (style, colours.underline)),
+                strikethrough: chargrid_cell.strikethrough,
+            };
+            if element_cell.decoration != decoration {
+                element_cell.decoration = decoration;
+                decoration.apply(&element_style);
+            }
+            if element_cell.bar_cursor != bar_cursor {
+                element_cell.bar_cursor = bar_cursor;
+                if bar_cursor {
+                    // a thin line in the text colour along the left edge of the cell
                     element_style
-                        .set_property("text-decoration", "underline")
+                        .set_property("box-shadow", "inset 0.12em 0 0 currentColor")
                         .unwrap();
                 } else {
-                    element_style.remove_property("text-decoration").unwrap();
+                    element_style.remove_property("box-shadow").unwrap();
                 }
             }
             if element_cell.bold != chargrid_cell.bold {
@@ -195,6 +315,14 @@ impl Context {
                     element_style.remove_property("font-weight").unwrap();
                 }
             }
+            if element_cell.italic != chargrid_cell.italic {
+                element_cell.italic = chargrid_cell.italic;
+                if chargrid_cell.italic {
+                    element_style.set_property("font-style", "italic").unwrap();
+                } else {
+                    element_style.remove_property("font-style").unwrap();
+                }
+            }
         }
     }
 
@@ -224,18 +352,18 @@ where
     let performance = window.performance().unwrap();
     let f: Rc<RefCell<Option<Closure<_>>>> = Rc::new(RefCell::new(None));
     let g = f.clone();
-    let mut last_frame_time_stamp = performance.now();
     *g.borrow_mut() = Some(Closure::wrap(Box::new(move || {
-        let frame_time_stamp = performance.now();
-        let since_last_frame = frame_time_stamp - last_frame_time_stamp;
-        last_frame_time_stamp = frame_time_stamp;
+        let now = Duration::from_secs_f64(performance.now() / 1000.);
         let mut context = context.borrow_mut();
-        on_frame(
-            &mut *component.borrow_mut(),
-            Duration::from_millis(since_last_frame as u64),
-            &mut context.chargrid_frame_buffer,
-        );
-        context.render_internal();
+        if context.frame_scheduler.is_frame_due(now) {
+            let since_last_frame = context.frame_scheduler.start_frame(now);
+            on_frame(
+                &mut *component.borrow_mut(),
+                since_last_frame,
+                &mut context.chargrid_frame_buffer,
+            );
+            context.render_internal(now);
+        }
         match loop_method {
             LoopMethod::RequestAnimationFrame => {
                 window
@@ -303,13 +431,36 @@ where
         let context = context.clone();
         Closure::wrap(Box::new(move |event: JsValue| {
             let keyboard_event = event.unchecked_ref::<KeyboardEvent>();
-            if let Some(keyboard_input) =
-                input::key_from_js(keyboard_event.key_code() as u8, keyboard_event.shift_key())
+            let context = context.borrow();
+            let kind = if keyboard_event.repeat() {
+                KeyEventKind::Repeat
+            } else {
+                KeyEventKind::Press
+            };
+            if let Some(kind) = kind.reportable(context.key_repeat_and_release)
+                && let Some(key_event) = input::key_event_from_js(keyboard_event, kind)
             {
                 on_input(
                     &mut *component.borrow_mut(),
-                    Input::Keyboard(keyboard_input),
-                    &context.borrow().chargrid_frame_buffer,
+                    key_event.input(),
+                    &context.chargrid_frame_buffer,
+                );
+            }
+        }) as Box<dyn FnMut(JsValue)>)
+    };
+    let handle_keyup = {
+        let component = component.clone();
+        let context = context.clone();
+        Closure::wrap(Box::new(move |event: JsValue| {
+            let keyboard_event = event.unchecked_ref::<KeyboardEvent>();
+            let context = context.borrow();
+            if let Some(kind) = KeyEventKind::Release.reportable(context.key_repeat_and_release)
+                && let Some(key_event) = input::key_event_from_js(keyboard_event, kind)
+            {
+                on_input(
+                    &mut *component.borrow_mut(),
+                    key_event.input(),
+                    &context.chargrid_frame_buffer,
                 );
             }
         }) as Box<dyn FnMut(JsValue)>)
@@ -421,6 +572,57 @@ where
             }
         }) as Box<dyn FnMut(JsValue)>)
     };
+    let handle_paste = {
+        let component = component.clone();
+        let context = context.clone();
+        Closure::wrap(Box::new(move |event: JsValue| {
+            let clipboard_event = event.unchecked_ref::<ClipboardEvent>();
+            if let Some(text) = clipboard_event
+                .clipboard_data()
+                .and_then(|data| data.get_data("text").ok())
+            {
+                clipboard_event.prevent_default();
+                let context = context.borrow();
+                on_paste(
+                    &mut *component.borrow_mut(),
+                    &text,
+                    &context.chargrid_frame_buffer,
+                );
+            }
+        }) as Box<dyn FnMut(JsValue)>)
+    };
+    let handle_lifecycle = |lifecycle_of_event: fn() -> Lifecycle| {
+        let component = component.clone();
+        let context = context.clone();
+        Closure::wrap(Box::new(move |_event: JsValue| {
+            let context = context.borrow();
+            on_lifecycle(
+                &mut *component.borrow_mut(),
+                lifecycle_of_event(),
+                &context.chargrid_frame_buffer,
+            );
+        }) as Box<dyn FnMut(JsValue)>)
+    };
+    let handle_focus = handle_lifecycle(|| Lifecycle::FocusGained);
+    let handle_blur = handle_lifecycle(|| Lifecycle::FocusLost);
+    let handle_visibility_change = handle_lifecycle(|| {
+        let hidden = web_sys::window()
+            .and_then(|window| window.document())
+            .is_some_and(|document| document.hidden());
+        if hidden {
+            Lifecycle::Suspended
+        } else {
+            Lifecycle::Resumed
+        }
+    });
+    let handle_resize = {
+        let component = component.clone();
+        let context = context.clone();
+        Closure::wrap(Box::new(move |_event: JsValue| {
+            let context = context.borrow();
+            on_window_resize(&mut *component.borrow_mut(), &context.chargrid_frame_buffer);
+        }) as Box<dyn FnMut(JsValue)>)
+    };
     let handle_wheel = Closure::wrap(Box::new(move |event: JsValue| {
         let context = context.borrow_mut();
         let mut component = component.borrow_mut();
@@ -470,6 +672,9 @@ where
     window
         .add_event_listener_with_callback("keydown", handle_keydown.as_ref().unchecked_ref())
         .unwrap();
+    window
+        .add_event_listener_with_callback("keyup", handle_keyup.as_ref().unchecked_ref())
+        .unwrap();
     window
         .add_event_listener_with_callback("mousemove", handle_mouse_move.as_ref().unchecked_ref())
         .unwrap();
@@ -479,12 +684,38 @@ where
     window
         .add_event_listener_with_callback("mouseup", handle_mouse_up.as_ref().unchecked_ref())
         .unwrap();
+    window
+        .add_event_listener_with_callback("paste", handle_paste.as_ref().unchecked_ref())
+        .unwrap();
+    window
+        .add_event_listener_with_callback("focus", handle_focus.as_ref().unchecked_ref())
+        .unwrap();
+    window
+        .add_event_listener_with_callback("blur", handle_blur.as_ref().unchecked_ref())
+        .unwrap();
+    window
+        .document()
+        .unwrap()
+        .add_event_listener_with_callback(
+            "visibilitychange",
+            handle_visibility_change.as_ref().unchecked_ref(),
+        )
+        .unwrap();
+    window
+        .add_event_listener_with_callback("resize", handle_resize.as_ref().unchecked_ref())
+        .unwrap();
     window
         .add_event_listener_with_callback("wheel", handle_wheel.as_ref().unchecked_ref())
         .unwrap();
     handle_keydown.forget();
+    handle_keyup.forget();
     handle_mouse_move.forget();
     handle_mouse_down.forget();
     handle_mouse_up.forget();
+    handle_paste.forget();
+    handle_focus.forget();
+    handle_blur.forget();
+    handle_visibility_change.forget();
+    handle_resize.forget();
     handle_wheel.forget();
 }
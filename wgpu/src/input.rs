@@ -1,8 +1,9 @@
 use crate::Dimensions;
 use chargrid_input::{
-    ICoord, Input, KeyboardInput, MouseButton as ChargridMouseButton, MouseButton, MouseInput,
-    ScrollDirection, keys,
+    ICoord, Input, KeyEvent, KeyEventKind, KeyModifiers, KeyboardInput,
+    MouseButton as ChargridMouseButton, MouseButton, MouseInput, ScrollDirection, keys,
 };
+use chargrid_runtime::Lifecycle;
 use winit::{
     dpi::{LogicalPosition, PhysicalSize},
     event::{ElementState, MouseButton as GlutinMouseButton, MouseScrollDelta, WindowEvent},
@@ -12,6 +13,7 @@ use winit::{
 pub enum Event {
     Input(Input),
     Resize(PhysicalSize<u32>),
+    Lifecycle(Lifecycle),
 }
 
 macro_rules! convert_char_shift {
@@ -121,6 +123,28 @@ fn convert_keycode_keyboard_input(code: KeyCode, shift: bool) -> Option<Keyboard
     Some(keyboard_input)
 }
 
+/// If the input is a paste shortcut (ctrl+v, or cmd+v on macos), returns the contents of the
+/// clipboard
+pub fn paste_from_clipboard(input: Input, clipboard: &mut arboard::Clipboard) -> Option<String> {
+    let key_event = input.key_press()?;
+    let is_paste_shortcut = key_event.key == KeyboardInput::Char('v')
+        && (key_event.modifiers == KeyModifiers::CTRL
+            || key_event.modifiers == KeyModifiers::SUPER);
+    if !is_paste_shortcut {
+        return None;
+    }
+    clipboard.get_text().ok()
+}
+
+fn convert_modifiers(modifier_state: ModifiersState) -> KeyModifiers {
+    KeyModifiers::NONE
+        .with_ctrl_if(modifier_state.control_key())
+        .with_alt_if(modifier_state.alt_key())
+        .with_shift_if(modifier_state.shift_key())
+        .with_super_if(modifier_state.super_key())
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn convert_event(
     event: WindowEvent,
     cell_dimensions: Dimensions<f64>,
@@ -129,17 +153,29 @@ pub fn convert_event(
     last_mouse_button: &mut Option<MouseButton>,
     scale_factor: &mut f64,
     modifier_state: ModifiersState,
+    key_repeat_and_release: bool,
 ) -> Option<Event> {
     match event {
-        WindowEvent::CloseRequested => Some(Event::Input(Input::Keyboard(keys::ETX))),
+        WindowEvent::CloseRequested => Some(Event::Lifecycle(Lifecycle::CloseRequested)),
+        WindowEvent::Focused(true) => Some(Event::Lifecycle(Lifecycle::FocusGained)),
+        WindowEvent::Focused(false) => Some(Event::Lifecycle(Lifecycle::FocusLost)),
+        WindowEvent::Occluded(true) => Some(Event::Lifecycle(Lifecycle::Suspended)),
+        WindowEvent::Occluded(false) => Some(Event::Lifecycle(Lifecycle::Resumed)),
         WindowEvent::Resized(physical_size) => Some(Event::Resize(physical_size)),
         WindowEvent::KeyboardInput { event, .. } => {
-            if event.state == ElementState::Pressed
+            let kind = match (event.state, event.repeat) {
+                (ElementState::Pressed, false) => KeyEventKind::Press,
+                (ElementState::Pressed, true) => KeyEventKind::Repeat,
+                (ElementState::Released, _) => KeyEventKind::Release,
+            };
+            if let Some(kind) = kind.reportable(key_repeat_and_release)
                 && let PhysicalKey::Code(key_code) = event.physical_key
                 && let Some(keyboard_input) =
                     convert_keycode_keyboard_input(key_code, modifier_state.shift_key())
             {
-                return Some(Event::Input(Input::Keyboard(keyboard_input)));
+                let key_event = KeyEvent::new(keyboard_input, convert_modifiers(modifier_state))
+                    .with_kind(kind);
+                return Some(Event::Input(key_event.input()));
             }
             None
         }
@@ -2,7 +2,11 @@ use crate::text_renderer::{TextRenderer, TextRendererArgs};
 use anyhow::anyhow;
 #[cfg(feature = "gamepad")]
 use chargrid_gamepad::GamepadContext;
-use chargrid_runtime::{Component, FrameBuffer, app, on_frame, on_input};
+use chargrid_runtime::{
+    Component, Cursor, CursorShape, FrameBuffer, FrameScheduler, UnderlineStyle, app, on_frame,
+    on_input, on_lifecycle, on_paste, on_window_resize,
+};
+pub use chargrid_runtime::{FrameConfig, FrameRate, Timestep};
 use grid_2d::{Grid, ICoord, UCoord};
 use std::{
     borrow::Cow,
@@ -47,6 +51,12 @@ pub struct Config {
     pub underline_width_cell_ratio: f64,
     pub underline_top_offset_cell_ratio: f64,
     pub force_secondary_adapter: bool,
+    /// Report key repeats and releases as `Input::Key` events. When false, key repeats are
+    /// reported as presses and releases aren't reported.
+    pub key_repeat_and_release: bool,
+    /// Frames are always synchronised with the display's refresh rate, so `FrameRate::Unlimited`
+    /// renders a frame per refresh
+    pub frame_config: FrameConfig,
 }
 
 fn rgb_to_srgb_channel(c: f32) -> f32 {
@@ -110,7 +120,10 @@ struct WgpuState {
 struct BackgroundCellInstance {
     background_colour: [f32; 3],
     foreground_colour: [f32; 3],
-    underline: u32,
+    underline_colour: [f32; 3],
+    /// The style of underline (see `underline_style_decoration`) and whether the cell is struck
+    /// through, as interpreted by the fragment shader
+    decorations: u32,
 }
 
 impl Default for BackgroundCellInstance {
@@ -118,11 +131,27 @@ impl Default for BackgroundCellInstance {
         Self {
             background_colour: [0.; 3],
             foreground_colour: [1.; 3],
-            underline: 0,
+            underline_colour: [1.; 3],
+            decorations: 0,
         }
     }
 }
 
+/// Bit of `BackgroundCellInstance::decorations` set for cells which are struck through
+const DECORATION_STRIKETHROUGH: u32 = 1 << 3;
+
+/// The value of the lowest 3 bits of `BackgroundCellInstance::decorations` for each style of
+/// underline (0 meaning no underline)
+fn underline_style_decoration(underline_style: UnderlineStyle) -> u32 {
+    match underline_style {
+        UnderlineStyle::Single => 1,
+        UnderlineStyle::Double => 2,
+        UnderlineStyle::Curly => 3,
+        UnderlineStyle::Dotted => 4,
+        UnderlineStyle::Dashed => 5,
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, zerocopy::IntoBytes, zerocopy::FromZeros, zerocopy::Immutable)]
 struct GlobalUniforms {
@@ -415,9 +444,14 @@ impl WgpuState {
                         },
                         wgpu::VertexAttribute {
                             format: wgpu::VertexFormat::Uint32,
-                            offset: 24,
+                            offset: 36,
                             shader_location: 2,
                         },
+                        wgpu::VertexAttribute {
+                            format: wgpu::VertexFormat::Float32x3,
+                            offset: 24,
+                            shader_location: 3,
+                        },
                     ],
                 }],
             },
@@ -463,17 +497,39 @@ impl WgpuState {
             text_renderer,
         }
     }
-    fn render_background(&mut self) {
-        for (buffer_cell, background_cell_instance) in self
+    fn render_background(&mut self, cursor: Option<Cursor>, time: Duration) {
+        for ((coord, buffer_cell), background_cell_instance) in self
             .chargrid_frame_buffer
-            .iter()
+            .enumerate()
             .zip(self.background_cell_instance_data.iter_mut())
         {
+            let colours = buffer_cell.display_colours_at(time);
+            let mut background = colours.background;
+            let mut foreground = colours.foreground;
+            let mut underline = buffer_cell.underline.then_some(buffer_cell.underline_style);
+            if let Some(cursor) = cursor
+                && cursor.coord == coord
+            {
+                match cursor.shape {
+                    // a block cursor inverts the colours of its cell
+                    CursorShape::Block => std::mem::swap(&mut background, &mut foreground),
+                    CursorShape::Underline => underline = Some(UnderlineStyle::Single),
+                    // drawn by the text renderer
+                    CursorShape::Bar => (),
+                }
+            }
             background_cell_instance.background_colour =
-                rgb_to_srgb(buffer_cell.background.to_f32_array_rgb_01());
+                rgb_to_srgb(background.to_f32_array_rgb_01());
             background_cell_instance.foreground_colour =
-                rgb_to_srgb(buffer_cell.foreground.to_f32_array_rgb_01());
-            background_cell_instance.underline = buffer_cell.underline as u32;
+                rgb_to_srgb(foreground.to_f32_array_rgb_01());
+            background_cell_instance.underline_colour =
+                rgb_to_srgb(colours.underline.to_f32_array_rgb_01());
+            background_cell_instance.decorations = underline.map_or(0, underline_style_decoration)
+                | if buffer_cell.strikethrough {
+                    DECORATION_STRIKETHROUGH
+                } else {
+                    0
+                };
         }
 
         self.background_cell_instance_buffer = populate_and_finish_buffer(
@@ -527,7 +583,7 @@ impl WgpuState {
         }
     }
 
-    fn render_foreground(&mut self) {
+    fn render_foreground(&mut self, time: Duration) {
         if let Ok(frame) = self.surface.get_current_texture() {
             let mut encoder = self
                 .device
@@ -561,6 +617,7 @@ impl WgpuState {
                 self.text_renderer
                     .render(
                         &self.chargrid_frame_buffer,
+                        time,
                         &self.surface_configuration,
                         &self.device,
                         &self.queue,
@@ -575,9 +632,15 @@ impl WgpuState {
         }
     }
 
-    fn render(&mut self) {
-        self.render_background();
-        self.render_foreground();
+    /// Render the frame buffer. The time (since an arbitrary fixed point) is used to blink the
+    /// cursor and blinking text.
+    fn render(&mut self, time: Duration) {
+        let cursor = self
+            .chargrid_frame_buffer
+            .cursor()
+            .filter(|cursor| cursor.is_visible_at(time));
+        self.render_background(cursor, time);
+        self.render_foreground(time);
     }
 }
 
@@ -670,6 +733,7 @@ struct Context {
     wgpu_state: WgpuState,
     sizes: Sizes,
     input_state: InputState,
+    clipboard: Option<arboard::Clipboard>,
     #[cfg(feature = "gamepad")]
     gamepad: GamepadContext,
 }
@@ -698,7 +762,7 @@ impl Context {
         let sizes = Sizes {
             font_size_px,
             cell_dimensions: cell_dimensions_px,
-            character_cell_offset_px: character_cell_offset_px,
+            character_cell_offset_px,
             underline_width: underline_width_cell_ratio,
             underline_top_offset: underline_top_offset_cell_ratio,
             native_window_dimensions: dimensions_px,
@@ -715,11 +779,15 @@ impl Context {
             font_bytes,
         });
         log::info!("grid size: {:?}", grid_size);
+        let clipboard = arboard::Clipboard::new()
+            .inspect_err(|e| log::warn!("clipboard unavailable: {}", e))
+            .ok();
         Context {
             window: window.clone(),
             wgpu_state,
             sizes,
             input_state: Default::default(),
+            clipboard,
             #[cfg(feature = "gamepad")]
             gamepad: GamepadContext::new(),
         }
@@ -728,17 +796,17 @@ impl Context {
 
 struct AppState {
     exited: bool,
-    frame_instant: Instant,
-    last_update_instant: Instant,
+    start_instant: Instant,
+    frame_scheduler: FrameScheduler,
     current_window_dimensions: Dimensions<f64>,
 }
 
 impl AppState {
-    fn new() -> Self {
+    fn new(frame_config: FrameConfig) -> Self {
         Self {
             exited: false,
-            frame_instant: Instant::now(),
-            last_update_instant: Instant::now(),
+            start_instant: Instant::now(),
+            frame_scheduler: FrameScheduler::new(frame_config),
             current_window_dimensions: Dimensions {
                 width: 0.,
                 height: 0.,
@@ -763,9 +831,9 @@ where
 {
     fn new(component: C, config: Config) -> Self {
         Self {
+            state: AppState::new(config.frame_config),
             config,
             context: None,
-            state: AppState::new(),
             component,
         }
     }
@@ -788,8 +856,6 @@ where
             .with_resizable(self.config.resizable);
         let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
         let context = Context::new(window.clone(), self.config.clone());
-        self.state.frame_instant = Instant::now();
-        self.state.last_update_instant = self.state.frame_instant;
         self.state.current_window_dimensions = context.sizes.native_window_dimensions;
         self.context = Some(context);
         window.request_redraw();
@@ -828,22 +894,18 @@ where
                 context.wgpu_state.modifier_state = modifiers.state();
             }
             winit::event::WindowEvent::RedrawRequested => {
-                let target_frametime = Duration::from_secs_f64(1.0 / 60.0);
-                let time_since_last_frame = self.state.last_update_instant.elapsed();
-                let update_on_this_frame = time_since_last_frame >= target_frametime;
-                if update_on_this_frame {
-                    self.state.last_update_instant = Instant::now();
-                    let frame_duration = self.state.frame_instant.elapsed();
-                    self.state.frame_instant = Instant::now();
+                let now = self.state.start_instant.elapsed();
+                if self.state.frame_scheduler.is_frame_due(now) {
+                    let since_last_frame = self.state.frame_scheduler.start_frame(now);
                     if let Some(app::Exit) = on_frame(
                         &mut self.component,
-                        frame_duration,
+                        since_last_frame,
                         &mut context.wgpu_state.chargrid_frame_buffer,
                     ) {
                         self.state.exited = true;
                     }
                 }
-                context.wgpu_state.render();
+                context.wgpu_state.render(now);
                 context.window.request_redraw();
             }
             other => {
@@ -859,12 +921,27 @@ where
                     &mut context.input_state.last_mouse_button,
                     &mut context.wgpu_state.scale_factor,
                     context.wgpu_state.modifier_state,
+                    self.config.key_repeat_and_release,
                 ) {
                     match event {
                         input::Event::Input(input) => {
-                            if let Some(app::Exit) = on_input(
+                            let frame_buffer = &context.wgpu_state.chargrid_frame_buffer;
+                            let output = match context
+                                .clipboard
+                                .as_mut()
+                                .and_then(|clipboard| input::paste_from_clipboard(input, clipboard))
+                            {
+                                Some(text) => on_paste(&mut self.component, &text, frame_buffer),
+                                None => on_input(&mut self.component, input, frame_buffer),
+                            };
+                            if let Some(app::Exit) = output {
+                                self.state.exited = true;
+                            }
+                        }
+                        input::Event::Lifecycle(lifecycle) => {
+                            if let Some(app::Exit) = on_lifecycle(
                                 &mut self.component,
-                                input,
+                                lifecycle,
                                 &context.wgpu_state.chargrid_frame_buffer,
                             ) {
                                 self.state.exited = true;
@@ -874,6 +951,12 @@ where
                             context.wgpu_state.resize(&context.sizes, size);
                             self.state.current_window_dimensions =
                                 dimensions_from_logical_size(context.wgpu_state.window_size);
+                            if let Some(app::Exit) = on_window_resize(
+                                &mut self.component,
+                                &context.wgpu_state.chargrid_frame_buffer,
+                            ) {
+                                self.state.exited = true;
+                            }
                         }
                     }
                 }
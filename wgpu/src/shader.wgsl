@@ -15,14 +15,26 @@ struct VertexOutput {
     @location(0) @interpolate(flat) background_colour: vec3<f32>,
     @location(1) @interpolate(flat) foreground_colour: vec3<f32>,
     @location(2) cell_ratio: f32,
-    @location(3) @interpolate(flat) underline: u32,
+    @location(3) @interpolate(flat) decorations: u32,
+    @location(4) @interpolate(flat) underline_colour: vec3<f32>,
+    @location(5) cell_ratio_x: f32,
 };
 
+// The lowest 3 bits of the decorations select the style of underline
+const UNDERLINE_MASK: u32 = 7u;
+const UNDERLINE_SINGLE: u32 = 1u;
+const UNDERLINE_DOUBLE: u32 = 2u;
+const UNDERLINE_CURLY: u32 = 3u;
+const UNDERLINE_DOTTED: u32 = 4u;
+const UNDERLINE_DASHED: u32 = 5u;
+const STRIKETHROUGH: u32 = 8u;
+
 @vertex
 fn vs_main(
     @location(0) background_colour: vec3<f32>,
     @location(1) foreground_colour: vec3<f32>,
-    @location(2) underline: u32,
+    @location(2) decorations: u32,
+    @location(3) underline_colour: vec3<f32>,
     @builtin(vertex_index) in_vertex_index: u32,
     @builtin(instance_index) in_instance_index: u32,
 ) -> VertexOutput {
@@ -44,22 +56,58 @@ fn vs_main(
 
     var out: VertexOutput;
     out.cell_ratio = corner_offset_y;
+    out.cell_ratio_x = corner_offset_x;
     out.background_colour = background_colour;
     out.foreground_colour = foreground_colour;
-    out.underline = underline;
+    out.decorations = decorations;
+    out.underline_colour = underline_colour;
     out.position = vec4<f32>(absolute.x, -absolute.y, 0.0, 1.0);
     return out;
 }
 
+// Whether a pixel at the given vertical position within a cell is part of a horizontal line
+// starting at `top`
+fn is_in_line(cell_ratio: f32, top: f32) -> bool {
+    return cell_ratio >= top && cell_ratio <= top + globals.underline_width_cell_ratio;
+}
+
+fn is_underline(in: VertexOutput) -> bool {
+    let top = globals.underline_top_offset_cell_ratio;
+    let width = globals.underline_width_cell_ratio;
+    switch (in.decorations & UNDERLINE_MASK) {
+        case UNDERLINE_SINGLE: {
+            return is_in_line(in.cell_ratio, top);
+        }
+        case UNDERLINE_DOUBLE: {
+            return is_in_line(in.cell_ratio, top) || is_in_line(in.cell_ratio, top + 2.0 * width);
+        }
+        case UNDERLINE_CURLY: {
+            // one period of a sine wave per cell
+            let wave = sin(in.cell_ratio_x * 6.2831853) * width;
+            return is_in_line(in.cell_ratio, top + wave);
+        }
+        case UNDERLINE_DOTTED: {
+            return is_in_line(in.cell_ratio, top) && fract(in.cell_ratio_x * 4.0) < 0.5;
+        }
+        case UNDERLINE_DASHED: {
+            return is_in_line(in.cell_ratio, top) && fract(in.cell_ratio_x * 2.0 + 0.25) < 0.5;
+        }
+        default: {
+            return false;
+        }
+    }
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let pixel_is_underline =
-        in.underline != 0u &&
-        in.cell_ratio >= globals.underline_top_offset_cell_ratio &&
-        in.cell_ratio <= globals.underline_top_offset_cell_ratio + globals.underline_width_cell_ratio;
-    if (pixel_is_underline) {
+    if (is_underline(in)) {
+        return vec4<f32>(in.underline_colour, 1.0);
+    }
+    let pixel_is_strikethrough =
+        (in.decorations & STRIKETHROUGH) != 0u &&
+        is_in_line(in.cell_ratio, 0.5 - globals.underline_width_cell_ratio / 2.0);
+    if (pixel_is_strikethrough) {
         return vec4<f32>(in.foreground_colour, 1.0);
-    } else {
-        return vec4<f32>(in.background_colour, 1.0);
     }
+    return vec4<f32>(in.background_colour, 1.0);
 }
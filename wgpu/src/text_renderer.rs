@@ -1,12 +1,17 @@
 use crate::{Dimensions, FontBytes};
-use chargrid_runtime::{FrameBuffer, Rgba32};
+use chargrid_runtime::{Cursor, CursorShape, FrameBuffer, Rgba32};
 use glyphon::fontdb;
-use grid_2d::{Grid, UCoord};
+use grid_2d::{Grid, ICoord, UCoord};
 use std::sync::Arc;
+use std::time::Duration;
 
 const FONT_NAME_NORMAL: &str = "user-normal";
 const FONT_NAME_BOLD: &str = "user-bold";
 
+/// Drawn over the cell containing a bar cursor. Block and underline cursors are drawn by the
+/// background renderer instead.
+const BAR_CURSOR: &str = "\u{258F}";
+
 fn font_data_to_font_source(data: Arc<Vec<u8>>) -> fontdb::Source {
     fontdb::Source::Binary(data)
 }
@@ -61,6 +66,7 @@ pub struct TextRenderer {
     atlas: glyphon::TextAtlas,
     text_renderer: glyphon::TextRenderer,
     text_buffer_grid: Grid<glyphon::Buffer>,
+    bar_cursor_text_buffer: glyphon::Buffer,
     string_buffer: String,
     cell_dimensions: Dimensions<f64>,
     character_cell_offset_px: Dimensions<f64>,
@@ -88,7 +94,7 @@ impl TextRenderer {
         let mut atlas = glyphon::TextAtlas::new(device, queue, &cache, texture_format);
         let text_renderer =
             glyphon::TextRenderer::new(&mut atlas, device, wgpu::MultisampleState::default(), None);
-        let text_buffer_grid = Grid::new_fn(grid_size, |_coord| {
+        let mut new_text_buffer = || {
             let mut text_buffer = glyphon::Buffer::new(
                 &mut font_system,
                 glyphon::Metrics {
@@ -102,7 +108,17 @@ impl TextRenderer {
                 Some(cell_dimensions.height as f32),
             );
             text_buffer
-        });
+        };
+        let text_buffer_grid = Grid::new_fn(grid_size, |_coord| new_text_buffer());
+        let mut bar_cursor_text_buffer = new_text_buffer();
+        bar_cursor_text_buffer.set_text(
+            &mut font_system,
+            BAR_CURSOR,
+            &glyphon::Attrs::new().family(fontdb::Family::Name(FONT_NAME_NORMAL)),
+            glyphon::Shaping::Advanced,
+            None,
+        );
+        bar_cursor_text_buffer.shape_until_scroll(&mut font_system, false);
         Self {
             font_system,
             swash_cache,
@@ -110,6 +126,7 @@ impl TextRenderer {
             atlas,
             text_renderer,
             text_buffer_grid,
+            bar_cursor_text_buffer,
             string_buffer: String::new(),
             cell_dimensions,
             character_cell_offset_px,
@@ -120,11 +137,15 @@ impl TextRenderer {
     pub fn render(
         &mut self,
         frame_buffer: &FrameBuffer,
+        time: Duration,
         surface_configuration: &wgpu::SurfaceConfiguration,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         render_pass: &mut wgpu::RenderPass<'_>,
     ) -> anyhow::Result<()> {
+        let cursor = frame_buffer
+            .cursor()
+            .filter(|cursor| cursor.is_visible_at(time));
         self.viewport.update(
             queue,
             glyphon::Resolution {
@@ -134,8 +155,11 @@ impl TextRenderer {
         );
         for (cell, text_buffer) in frame_buffer.iter().zip(self.text_buffer_grid.iter_mut()) {
             self.string_buffer.clear();
-            self.string_buffer.push(cell.character);
-            let attrs = if cell.bold {
+            // the right half of a wide character is drawn as part of the cell to its left
+            if !cell.is_continuation() {
+                self.string_buffer.push_str(cell.character.as_str());
+            }
+            let mut attrs = if cell.bold {
                 glyphon::Attrs::new()
                     .family(fontdb::Family::Name(FONT_NAME_BOLD))
                     .weight(fontdb::Weight::BOLD)
@@ -144,6 +168,10 @@ impl TextRenderer {
                     .family(fontdb::Family::Name(FONT_NAME_NORMAL))
                     .weight(fontdb::Weight::NORMAL)
             };
+            if cell.italic {
+                // there are no italic fonts, so the glyphs of the regular fonts are slanted
+                attrs = attrs.cache_key_flags(glyphon::cosmic_text::CacheKeyFlags::FAKE_ITALIC);
+            }
             text_buffer.set_text(
                 &mut self.font_system,
                 self.string_buffer.as_str(),
@@ -153,32 +181,53 @@ impl TextRenderer {
             );
             text_buffer.shape_until_scroll(&mut self.font_system, false);
         }
+        let text_area = |text_buffer, coord: ICoord, colour: Rgba32| glyphon::TextArea {
+            buffer: text_buffer,
+            left: (coord.x as f32 * self.cell_dimensions.width as f32
+                + self.character_cell_offset_px.width as f32)
+                * self.window_scale_factor as f32,
+            top: (coord.y as f32 * self.cell_dimensions.height as f32
+                + self.character_cell_offset_px.height as f32)
+                * self.window_scale_factor as f32,
+            scale: 1.,
+            bounds: glyphon::TextBounds {
+                left: 0,
+                top: 0,
+                right: surface_configuration.width as i32,
+                bottom: surface_configuration.height as i32,
+            },
+            default_color: {
+                let Rgba32 { r, g, b, a } = colour;
+                glyphon::Color::rgba(r, g, b, a)
+            },
+            custom_glyphs: &[],
+        };
         // XXX this allocates every frame
-        let mut text_areas = Vec::with_capacity(self.text_buffer_grid.len());
+        let mut text_areas = Vec::with_capacity(self.text_buffer_grid.len() + 1);
         for ((coord, cell), text_buffer) in
             frame_buffer.enumerate().zip(self.text_buffer_grid.iter())
         {
-            text_areas.push(glyphon::TextArea {
-                buffer: text_buffer,
-                left: (coord.x as f32 * self.cell_dimensions.width as f32
-                    + self.character_cell_offset_px.width as f32)
-                    * self.window_scale_factor as f32,
-                top: (coord.y as f32 * self.cell_dimensions.height as f32
-                    + self.character_cell_offset_px.height as f32)
-                    * self.window_scale_factor as f32,
-                scale: 1.,
-                bounds: glyphon::TextBounds {
-                    left: 0,
-                    top: 0,
-                    right: surface_configuration.width as i32,
-                    bottom: surface_configuration.height as i32,
-                },
-                default_color: {
-                    let Rgba32 { r, g, b, a } = cell.foreground;
-                    glyphon::Color::rgba(r, g, b, a)
-                },
-                custom_glyphs: &[],
-            });
+            let colours = cell.display_colours_at(time);
+            let colour = match cursor {
+                // a block cursor inverts the colours of its cell
+                Some(Cursor {
+                    coord: cursor_coord,
+                    shape: CursorShape::Block,
+                    ..
+                }) if cursor_coord == coord => colours.background,
+                _ => colours.foreground,
+            };
+            text_areas.push(text_area(text_buffer, coord, colour));
+        }
+        if let Some(cursor) = cursor
+            && cursor.shape == CursorShape::Bar
+            && let Some(cell) = frame_buffer.get(cursor.coord)
+        {
+            text_areas.push(text_area(
+                &self.bar_cursor_text_buffer,
+                cursor.coord,
+                cell.display_colours_at(time).foreground,
+            ));
         }
         self.text_renderer.prepare(
             device,